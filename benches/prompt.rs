@@ -0,0 +1,88 @@
+// Copyright (C) 2020 Stephane Raux. Distributed under the MIT license.
+
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+use eliprompt::{
+    shell::{self, Zsh},
+    Config, Environment,
+};
+use git2::{Repository, Signature};
+use std::{fs, path::Path, time::Duration};
+use tempfile::TempDir;
+
+/// Creates a repository with a few commits, a tag and some uncommitted changes.
+fn make_repo() -> TempDir {
+    let dir = tempfile::tempdir().unwrap();
+    let repo = Repository::init(dir.path()).unwrap();
+    let signature = Signature::now("Bench", "bench@example.com").unwrap();
+    let mut parent = None;
+    for i in 0..20 {
+        let name = format!("file{}.txt", i % 5);
+        fs::write(dir.path().join(&name), format!("{}\n", i)).unwrap();
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new(&name)).unwrap();
+        index.write().unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let parents = parent.iter().collect::<Vec<_>>();
+        let oid = repo
+            .commit(
+                Some("HEAD"),
+                &signature,
+                &signature,
+                "Commit",
+                &tree,
+                &parents,
+            )
+            .unwrap();
+        parent = Some(repo.find_commit(oid).unwrap());
+    }
+    let head = parent.unwrap();
+    repo.tag_lightweight("v1.0", head.as_object(), false)
+        .unwrap();
+    fs::create_dir(dir.path().join("sub")).unwrap();
+    fs::write(dir.path().join("file0.txt"), "changed\n").unwrap();
+    dir
+}
+
+fn environment(dir: &Path) -> Environment {
+    Environment::new(Some(dir.to_owned()))
+        .with_prev_exit_code(1)
+        .with_prev_cmd_duration(Duration::from_secs(3))
+}
+
+fn parse_config(c: &mut Criterion) {
+    let json = serde_json::to_vec(&Config::default_pretty()).unwrap();
+    c.bench_function("parse default config", |b| {
+        b.iter(|| Config::from_json(&json).unwrap())
+    });
+}
+
+fn produce(c: &mut Criterion) {
+    let repo = make_repo();
+    let outside = tempfile::tempdir().unwrap();
+    let config = Config::default_pretty();
+    c.bench_function("produce default prompt in git repository", |b| {
+        b.iter(|| config.produce(&environment(&repo.path().join("sub"))))
+    });
+    c.bench_function("produce default prompt outside git repository", |b| {
+        b.iter(|| config.produce(&environment(outside.path())))
+    });
+}
+
+fn render(c: &mut Criterion) {
+    let repo = make_repo();
+    let blocks = Config::default_pretty().produce(&environment(repo.path()));
+    c.bench_function("render default prompt for zsh", |b| {
+        b.iter_batched(
+            || blocks.clone(),
+            |blocks| {
+                let mut buffer = Vec::new();
+                shell::write_blocks(&mut Zsh(&mut buffer), blocks).unwrap();
+                buffer
+            },
+            BatchSize::SmallInput,
+        )
+    });
+}
+
+criterion_group!(benches, parse_config, produce, render);
+criterion_main!(benches);
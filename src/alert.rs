@@ -0,0 +1,132 @@
+// Copyright (C) 2020 Stephane Raux. Distributed under the MIT license.
+
+use crate::{color::InvalidColor, Block, BlockProducer, Color, Environment, Pattern, Style};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, env};
+
+/// Theme applied to the whole prompt in risky environments
+///
+/// The theme is active if any of its conditions holds. It then recolors the prompt and shows a
+/// warning before it.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct AlertTheme {
+    conditions: Vec<AlertCondition>,
+    #[serde(default)]
+    palette: Palette,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    warning: Option<Box<BlockProducer>>,
+}
+
+impl AlertTheme {
+    pub fn new<I>(conditions: I) -> Self
+    where
+        I: IntoIterator<Item = AlertCondition>,
+    {
+        AlertTheme {
+            conditions: conditions.into_iter().collect(),
+            palette: Default::default(),
+            warning: None,
+        }
+    }
+
+    pub fn with_palette(self, palette: Palette) -> Self {
+        Self { palette, ..self }
+    }
+
+    pub fn with_warning(self, warning: BlockProducer) -> Self {
+        Self {
+            warning: Some(Box::new(warning)),
+            ..self
+        }
+    }
+
+    pub fn is_active(&self, environment: &Environment) -> bool {
+        self.conditions.iter().any(|c| c.holds(environment))
+    }
+
+    /// Recolors `blocks` and prepends the warning.
+    pub fn apply(&self, blocks: Vec<Block>, environment: &Environment) -> Vec<Block> {
+        let warning = match &self.warning {
+            Some(warning) => warning.produce(environment),
+            None => Vec::new(),
+        };
+        let blocks = blocks.into_iter().map(|block| Block {
+            style: self.palette.apply(&block.style),
+            ..block
+        });
+        warning.into_iter().chain(blocks).collect()
+    }
+}
+
+/// Condition under which an [`AlertTheme`] is active
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub enum AlertCondition {
+    /// The user is root.
+    Root,
+    /// The current Kubernetes context matches the pattern.
+    KubeContext(Pattern),
+    /// The environment variable is set to a non-empty value.
+    EnvVar(String),
+}
+
+impl AlertCondition {
+    pub fn holds(&self, environment: &Environment) -> bool {
+        match self {
+            AlertCondition::Root => environment.is_root(),
+            AlertCondition::KubeContext(pattern) => environment
+                .kube_context()
+                .is_some_and(|context| pattern.is_match(context)),
+            AlertCondition::EnvVar(name) => env::var_os(name).is_some_and(|v| !v.is_empty()),
+        }
+    }
+}
+
+/// Replacement colors
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(try_from = "BTreeMap<String, Color>", into = "BTreeMap<String, Color>")]
+pub struct Palette(Vec<(Color, Color)>);
+
+impl Palette {
+    pub fn new<I>(replacements: I) -> Self
+    where
+        I: IntoIterator<Item = (Color, Color)>,
+    {
+        Palette(replacements.into_iter().collect())
+    }
+
+    pub fn apply(&self, style: &Style) -> Style {
+        Style {
+            foreground: style.foreground.as_ref().map(|c| self.replace(c)),
+            background: style.background.as_ref().map(|c| self.replace(c)),
+        }
+    }
+
+    fn replace(&self, color: &Color) -> Color {
+        self.0
+            .iter()
+            .find(|(from, _)| from.as_rgb() == color.as_rgb())
+            .map_or(color, |(_, to)| to)
+            .clone()
+    }
+}
+
+impl TryFrom<BTreeMap<String, Color>> for Palette {
+    type Error = InvalidColor;
+
+    fn try_from(map: BTreeMap<String, Color>) -> Result<Self, InvalidColor> {
+        map.into_iter()
+            .map(|(from, to)| Ok((Color::try_from(from)?, to)))
+            .collect::<Result<_, _>>()
+            .map(Palette)
+    }
+}
+
+impl From<Palette> for BTreeMap<String, Color> {
+    fn from(palette: Palette) -> Self {
+        palette
+            .0
+            .into_iter()
+            .map(|(from, to)| (from.to_string(), to))
+            .collect()
+    }
+}
@@ -3,43 +3,210 @@
 use crate::{Environment, Style};
 use ansi_term::ANSIString;
 use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
 
+mod azure;
+mod cached;
+mod ci;
+mod command;
+mod container;
+mod date;
+mod descriptor;
+mod direnv;
 mod elapsed;
+mod env_var;
 mod exit_code;
 mod exit_status_symbol;
+mod fill;
+mod git_ahead_behind;
+mod git_commit_age;
+mod git_conflicts;
 mod git_head;
+mod git_identity;
+mod git_operation;
 mod git_path;
+mod git_status;
+mod git_submodules;
+mod git_tag;
+mod go;
+mod greeting;
+mod group;
 mod hostname;
+mod hyperlink;
+mod java;
+mod jj_bookmarks;
+mod jj_change;
+mod jj_description;
+mod kubernetes;
+mod local_ip;
+mod locale;
+mod marker;
+mod multi_repo;
+mod multiplexer;
 mod newline;
+mod nix_shell;
+mod note;
+mod now_playing;
 mod or;
+mod os_icon;
+mod php;
+mod pinned_tools;
+mod public_ip;
 mod pwd;
+mod python;
+mod quota;
+mod right_align;
+mod root;
+mod rotating_text;
+mod rust_toolchain;
 mod separated;
 mod sequence;
+mod shell_level;
+mod shrink;
 mod space;
+mod ssh;
+mod sticky;
 mod styled;
+mod surround;
+mod take_first;
+mod terraform;
 mod text;
+mod time;
+mod timeout;
+mod todo;
+mod tool_version;
+mod transform;
+mod truncate;
 mod username;
+mod vcs_head;
+mod vpn;
+mod weather;
+mod week_number;
+mod when;
+mod when_time;
 
-pub use elapsed::Elapsed;
+pub use azure::Azure;
+pub use cached::Cached;
+pub use ci::Ci;
+pub use command::Command;
+pub use container::Container;
+pub use date::Date;
+pub use descriptor::ProducerDescriptor;
+pub use direnv::Direnv;
+pub use elapsed::{DurationUnits, Elapsed, UnitName};
+pub use env_var::EnvVar;
 pub use exit_code::ExitCode;
 pub use exit_status_symbol::ExitStatusSymbol;
+pub use fill::Fill;
+pub use git_ahead_behind::GitAheadBehind;
+pub use git_commit_age::GitCommitAge;
+pub use git_conflicts::GitConflicts;
 pub use git_head::GitHead;
+pub use git_identity::{GitIdentity, IdentityField};
+pub use git_operation::GitOperation;
 pub use git_path::GitPath;
+pub use git_status::GitStatus;
+pub use git_submodules::GitSubmodules;
+pub use git_tag::GitTag;
+pub use go::Go;
+pub use greeting::{Greeting, GreetingPeriod};
+pub use group::Group;
 pub use hostname::Hostname;
+pub use hyperlink::{Hyperlink, LinkTarget};
+pub use java::Java;
+pub use jj_bookmarks::JjBookmarks;
+pub use jj_change::JjChange;
+pub use jj_description::JjDescription;
+pub use kubernetes::Kubernetes;
+pub use local_ip::LocalIp;
+pub use locale::Locale;
+pub use marker::Marker;
+pub use multi_repo::MultiRepo;
+pub use multiplexer::Multiplexer;
 pub use newline::Newline;
-pub use or::Or;
+pub use nix_shell::NixShell;
+pub use note::{Note, ShownNote};
+pub use now_playing::{NowPlaying, PlayerSource};
+pub use or::{Or, OrElse};
+pub use os_icon::OsIcon;
+pub use php::Php;
+pub use pinned_tools::PinnedTools;
+pub use public_ip::PublicIp;
 pub use pwd::WorkingDirectory;
+pub use python::Python;
+pub use quota::Quota;
+pub use right_align::RightAlign;
+pub use root::Root;
+pub use rotating_text::{RotatingText, RotationOrder};
+pub use rust_toolchain::RustToolchain;
 pub use separated::Separated;
 pub use sequence::Sequence;
+pub use shell_level::ShellLevel;
+pub use shrink::{Shrink, ShrinkItem};
 pub use space::Space;
+pub use ssh::Ssh;
+pub use sticky::{Sticky, StickyBlocks};
 pub use styled::Styled;
+pub use surround::Surround;
+pub use take_first::TakeFirst;
+pub use terraform::Terraform;
 pub use text::Text;
+pub use time::Time;
+pub use timeout::Timeout;
+pub use todo::{Todo, TodoSource};
+pub use tool_version::{ToolVersion, VersionSource};
+pub use transform::{TextTransform, Transform};
+pub use truncate::{Truncate, TruncationSide};
 pub use username::Username;
+pub use vcs_head::VcsHead;
+pub use vpn::Vpn;
+pub use weather::Weather;
+pub use week_number::WeekNumber;
+pub use when::{Condition, When};
+pub use when_time::{InvalidTimeRange, TimeOfDay, TimeRange, Weekday, WhenTime};
+
+pub(crate) use git_operation::operation_label;
+pub(crate) use shrink::shrink_lines;
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
+#[non_exhaustive]
 pub struct Block {
     pub text: String,
     pub style: Style,
+    #[serde(default)]
+    pub kind: BlockKind,
+    /// Byte offsets in `text` where it is preferably cut when truncated, e.g. path separators
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub break_hints: Vec<usize>,
+    /// URL the text links to in terminals supporting hyperlinks
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub link: Option<String>,
+}
+
+/// Role of a block in the prompt
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub enum BlockKind {
+    #[default]
+    Content,
+    /// Delimiter between other blocks
+    Separator,
+    Space,
+    Newline,
+    /// Text repeated to fill the rest of the line up to the terminal width
+    Fill,
+    /// Spaces pushing the next blocks to the right edge of the terminal, taking the columns left
+    /// once fill blocks are expanded
+    Padding,
+    /// Start of an item of a [`Shrink`], followed by its blocks, shrunk with the whole line in
+    /// view
+    ShrinkItem {
+        priority: i32,
+    },
+    /// Start of the compact form of the current [`Shrink`] item
+    ShrinkCompact,
+    /// End of the current [`Shrink`] item
+    ShrinkEnd,
 }
 
 impl Block {
@@ -50,9 +217,16 @@ impl Block {
         Block {
             text: text.into(),
             style: Default::default(),
+            kind: Default::default(),
+            break_hints: Vec::new(),
+            link: None,
         }
     }
 
+    pub fn with_kind(self, kind: BlockKind) -> Self {
+        Block { kind, ..self }
+    }
+
     pub fn with_style<T>(self, style: T) -> Self
     where
         T: Into<Style>,
@@ -63,6 +237,133 @@ impl Block {
         }
     }
 
+    /// Sets the byte offsets in the text where it is preferably cut when truncated.
+    pub fn with_break_hints<I>(self, hints: I) -> Self
+    where
+        I: IntoIterator<Item = usize>,
+    {
+        Block {
+            break_hints: hints.into_iter().collect(),
+            ..self
+        }
+    }
+
+    /// Makes the text a link to `url` in terminals supporting hyperlinks.
+    pub fn with_link<T>(self, url: T) -> Self
+    where
+        T: Into<String>,
+    {
+        Block {
+            link: Some(url.into()),
+            ..self
+        }
+    }
+
+    /// Marks the text as preferably cut before each occurrence of `separator`, e.g. `/` in paths
+    /// or `::` in Rust paths.
+    pub fn with_break_hints_at(self, separator: &str) -> Self {
+        let hints = if separator.is_empty() {
+            Vec::new()
+        } else {
+            self.text.match_indices(separator).map(|(i, _)| i).collect()
+        };
+        self.with_break_hints(hints)
+    }
+
+    /// Width of the text in terminal columns, counting wide characters, e.g. CJK, as two columns
+    pub fn display_width(&self) -> usize {
+        UnicodeWidthStr::width(self.text.as_str())
+    }
+
+    /// Shortens the text to `max_width` columns by removing its start and prepending `ellipsis`.
+    ///
+    /// The text is cut at the first break hint leaving a short enough text, or between
+    /// characters if no hint does, e.g. `/home/user/src/block/git_head.rs` becomes
+    /// `…/block/git_head.rs`.
+    pub fn truncate_start(self, max_width: usize, ellipsis: &str) -> Self {
+        if self.display_width() <= max_width {
+            return self;
+        }
+        let budget = max_width.saturating_sub(UnicodeWidthStr::width(ellipsis));
+        self.cut_start(budget, ellipsis)
+    }
+
+    /// Removes the start of the text so that the rest fits in `budget` columns, and prepends
+    /// `ellipsis`.
+    pub(crate) fn cut_start(self, budget: usize, ellipsis: &str) -> Self {
+        let fits = |i: usize| UnicodeWidthStr::width(&self.text[i..]) <= budget;
+        let cut = self
+            .break_hints
+            .iter()
+            .copied()
+            .filter(|&i| self.text.is_char_boundary(i))
+            .filter(|&i| fits(i))
+            .min()
+            .unwrap_or_else(|| {
+                self.text
+                    .char_indices()
+                    .map(|(i, _)| i)
+                    .find(|&i| fits(i))
+                    .unwrap_or(self.text.len())
+            });
+        let break_hints = self
+            .break_hints
+            .iter()
+            .filter(|&&i| i > cut && i <= self.text.len())
+            .map(|&i| i - cut + ellipsis.len())
+            .collect();
+        Block {
+            text: format!("{}{}", ellipsis, &self.text[cut..]),
+            break_hints,
+            ..self
+        }
+    }
+
+    /// Shortens the text to `max_width` columns by removing its end and appending `ellipsis`.
+    ///
+    /// The text is cut at the last break hint leaving a short enough text, or between characters
+    /// if no hint does.
+    pub fn truncate_end(self, max_width: usize, ellipsis: &str) -> Self {
+        if self.display_width() <= max_width {
+            return self;
+        }
+        let budget = max_width.saturating_sub(UnicodeWidthStr::width(ellipsis));
+        self.cut_end(budget, ellipsis)
+    }
+
+    /// Removes the end of the text so that the rest fits in `budget` columns, and appends
+    /// `ellipsis`.
+    pub(crate) fn cut_end(self, budget: usize, ellipsis: &str) -> Self {
+        let fits = |i: usize| UnicodeWidthStr::width(&self.text[..i]) <= budget;
+        let cut = self
+            .break_hints
+            .iter()
+            .copied()
+            .filter(|&i| i > 0 && i <= self.text.len() && self.text.is_char_boundary(i))
+            .filter(|&i| fits(i))
+            .max()
+            .unwrap_or_else(|| {
+                self.text
+                    .char_indices()
+                    .map(|(i, _)| i)
+                    .chain([self.text.len()])
+                    .take_while(|&i| fits(i))
+                    .last()
+                    .unwrap_or(0)
+            });
+        let break_hints = self
+            .break_hints
+            .iter()
+            .copied()
+            .filter(|&i| i < cut)
+            .collect();
+        Block {
+            text: format!("{}{}", &self.text[..cut], ellipsis),
+            break_hints,
+            ..self
+        }
+    }
+
     pub fn render(&self) -> ANSIString<'_> {
         let style = ansi_term::Style::new();
         let style = match &self.style.foreground {
@@ -77,43 +378,423 @@ impl Block {
     }
 }
 
-#[derive(Clone, Debug, Deserialize, Serialize)]
+/// Removes stray delimiters from `blocks`.
+///
+/// Consecutive identical separators or spaces are collapsed into one, and separators at the start
+/// or end of a line are removed. Blocks with empty text are ignored when looking for neighbors.
+pub fn collapse_separators(blocks: Vec<Block>) -> Vec<Block> {
+    let mut collapsed = Vec::<Block>::with_capacity(blocks.len());
+    let mut line_start = 0;
+    for block in blocks {
+        match block.kind {
+            BlockKind::Content
+            | BlockKind::Fill
+            | BlockKind::Padding
+            | BlockKind::ShrinkItem { .. }
+            | BlockKind::ShrinkCompact
+            | BlockKind::ShrinkEnd => collapsed.push(block),
+            BlockKind::Newline => {
+                trim_trailing_separators(&mut collapsed, line_start);
+                collapsed.push(block);
+                line_start = collapsed.len();
+            }
+            BlockKind::Separator | BlockKind::Space => {
+                let previous = collapsed[line_start..]
+                    .iter()
+                    .rev()
+                    .find(|b| !b.text.is_empty());
+                match previous {
+                    None if block.kind == BlockKind::Separator => {}
+                    Some(p)
+                        if p.kind == block.kind
+                            && p.text == block.text
+                            && p.style == block.style => {}
+                    _ => collapsed.push(block),
+                }
+            }
+        }
+    }
+    trim_trailing_separators(&mut collapsed, line_start);
+    collapsed
+}
+
+/// Expands fill and padding blocks so that each line containing any spans `width` columns.
+///
+/// The free columns of a line are shared between its fill blocks, whose text is repeated to cover
+/// their share. Padding blocks share the free columns instead if the line has no fill block.
+/// These blocks are left empty if the width is unknown or the line is already full.
+pub fn fill_lines(blocks: Vec<Block>, width: Option<usize>) -> Vec<Block> {
+    if !blocks.iter().any(|b| is_filling(b.kind)) {
+        return blocks;
+    }
+    let mut lines = Vec::new();
+    let mut line = Vec::new();
+    for block in blocks {
+        let is_newline = block.kind == BlockKind::Newline;
+        line.push(block);
+        if is_newline {
+            lines.push(std::mem::take(&mut line));
+        }
+    }
+    lines.push(line);
+    lines
+        .into_iter()
+        .flat_map(|line| fill_line(line, width))
+        .collect()
+}
+
+fn fill_line(mut line: Vec<Block>, width: Option<usize>) -> Vec<Block> {
+    let count = |kind| line.iter().filter(|b| b.kind == kind).count();
+    let (kind, fill_count) = match (count(BlockKind::Fill), count(BlockKind::Padding)) {
+        (0, 0) => return line,
+        (0, paddings) => (BlockKind::Padding, paddings),
+        (fills, _) => (BlockKind::Fill, fills),
+    };
+    let used = line
+        .iter()
+        .filter(|b| !is_filling(b.kind) && b.kind != BlockKind::Newline)
+        .map(Block::display_width)
+        .sum::<usize>();
+    let free = width.unwrap_or_default().saturating_sub(used);
+    let mut i = 0;
+    for block in line.iter_mut().filter(|b| is_filling(b.kind)) {
+        let share = if block.kind == kind {
+            i += 1;
+            free / fill_count + usize::from(i <= free % fill_count)
+        } else {
+            0
+        };
+        block.text = repeat_to_width(&block.text, share);
+        block.break_hints.clear();
+    }
+    line
+}
+
+/// Indicates if blocks of `kind` are expanded to fill lines.
+fn is_filling(kind: BlockKind) -> bool {
+    matches!(kind, BlockKind::Fill | BlockKind::Padding)
+}
+
+/// Repeats `text` as long as it fits in `width` columns.
+fn repeat_to_width(text: &str, width: usize) -> String {
+    let mut repeated = String::new();
+    let mut used = 0;
+    for c in text.chars().cycle() {
+        let char_width = c.width().unwrap_or(0);
+        if char_width == 0 || used + char_width > width {
+            break;
+        }
+        repeated.push(c);
+        used += char_width;
+    }
+    repeated
+}
+
+fn trim_trailing_separators(blocks: &mut Vec<Block>, line_start: usize) {
+    let end = blocks[line_start..]
+        .iter()
+        .rposition(|b| !b.text.is_empty() && b.kind != BlockKind::Separator)
+        .map_or(line_start, |i| line_start + i + 1);
+    if blocks[end..].iter().any(|b| b.kind == BlockKind::Separator) {
+        blocks.truncate(end);
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, strum::IntoStaticStr)]
 pub enum BlockProducer {
     Elapsed(Elapsed),
     ExitCode(ExitCode),
+    Time(Time),
+    Date(Date),
+    Greeting(Greeting),
+    WeekNumber(WeekNumber),
+    GitAheadBehind(GitAheadBehind),
+    GitCommitAge(GitCommitAge),
+    GitConflicts(GitConflicts),
     GitHead(GitHead),
+    GitIdentity(GitIdentity),
+    GitOperation(GitOperation),
     GitPath(GitPath),
+    GitStatus(GitStatus),
+    GitSubmodules(GitSubmodules),
+    GitTag(GitTag),
+    JjChange(JjChange),
+    JjBookmarks(JjBookmarks),
+    JjDescription(JjDescription),
+    VcsHead(VcsHead),
+    MultiRepo(MultiRepo),
+    OsIcon(OsIcon),
     Hostname(Hostname),
+    Ssh(Ssh),
+    Multiplexer(Multiplexer),
+    LocalIp(LocalIp),
+    PublicIp(PublicIp),
+    Weather(Weather),
+    Vpn(Vpn),
     WorkingDirectory(WorkingDirectory),
     Username(Username),
+    Root(Root),
+    ShellLevel(ShellLevel),
+    Container(Container),
+    Ci(Ci),
+    NixShell(NixShell),
+    Direnv(Direnv),
+    Locale(Locale),
+    Quota(Quota),
+    Kubernetes(Kubernetes),
+    Azure(Azure),
+    Terraform(Terraform),
+    Python(Python),
+    RustToolchain(RustToolchain),
+    Go(Go),
+    Java(Java),
+    Php(Php),
+    ToolVersion(ToolVersion),
+    PinnedTools(PinnedTools),
     Newline(Newline),
     Space(Space),
     Text(Text),
+    RotatingText(RotatingText),
+    Fill(Fill),
+    EnvVar(EnvVar),
+    Command(Command),
     ExitStatusSymbol(ExitStatusSymbol),
+    Marker(Marker),
+    Note(Note),
+    Todo(Todo),
+    NowPlaying(NowPlaying),
     Or(Or),
+    OrElse(OrElse),
+    TakeFirst(TakeFirst),
     Sequence(Sequence),
     Separated(Separated),
     Styled(Styled),
+    Sticky(Sticky),
+    Cached(Cached),
+    Timeout(Timeout),
+    Group(Group),
+    RightAlign(RightAlign),
+    Shrink(Shrink),
+    Truncate(Truncate),
+    Hyperlink(Hyperlink),
+    Transform(Transform),
+    Surround(Surround),
+    When(When),
+    WhenTime(WhenTime),
 }
 
 impl BlockProducer {
+    pub fn name(&self) -> &'static str {
+        self.into()
+    }
+
     pub fn produce(&self, environment: &Environment) -> Vec<Block> {
+        struct Pop;
+
+        impl Drop for Pop {
+            fn drop(&mut self) {
+                PRODUCER_STACK.with(|stack| stack.borrow_mut().pop());
+            }
+        }
+
+        PRODUCER_STACK.with(|stack| stack.borrow_mut().push(self.name()));
+        let _pop = Pop;
+        environment.inject_faults(self.name());
+        if environment.is_cancelled() {
+            return Vec::new();
+        }
         match self {
             BlockProducer::Elapsed(p) => p.produce(environment),
             BlockProducer::ExitCode(p) => p.produce(environment),
+            BlockProducer::Time(p) => p.produce(environment),
+            BlockProducer::Date(p) => p.produce(environment),
+            BlockProducer::Greeting(p) => p.produce(environment),
+            BlockProducer::WeekNumber(p) => p.produce(environment),
+            BlockProducer::GitAheadBehind(p) => p.produce(environment),
+            BlockProducer::GitCommitAge(p) => p.produce(environment),
+            BlockProducer::GitConflicts(p) => p.produce(environment),
             BlockProducer::GitHead(p) => p.produce(environment),
+            BlockProducer::GitIdentity(p) => p.produce(environment),
+            BlockProducer::GitOperation(p) => p.produce(environment),
             BlockProducer::GitPath(p) => p.produce(environment),
+            BlockProducer::GitStatus(p) => p.produce(environment),
+            BlockProducer::GitSubmodules(p) => p.produce(environment),
+            BlockProducer::GitTag(p) => p.produce(environment),
+            BlockProducer::JjChange(p) => p.produce(environment),
+            BlockProducer::JjBookmarks(p) => p.produce(environment),
+            BlockProducer::JjDescription(p) => p.produce(environment),
+            BlockProducer::VcsHead(p) => p.produce(environment),
+            BlockProducer::MultiRepo(p) => p.produce(environment),
+            BlockProducer::OsIcon(p) => p.produce(environment),
             BlockProducer::Hostname(p) => p.produce(environment),
+            BlockProducer::Ssh(p) => p.produce(environment),
+            BlockProducer::Multiplexer(p) => p.produce(environment),
+            BlockProducer::LocalIp(p) => p.produce(environment),
+            BlockProducer::PublicIp(p) => p.produce(environment),
+            BlockProducer::Weather(p) => p.produce(environment),
+            BlockProducer::Vpn(p) => p.produce(environment),
             BlockProducer::WorkingDirectory(p) => p.produce(environment),
             BlockProducer::Username(p) => p.produce(environment),
+            BlockProducer::Root(p) => p.produce(environment),
+            BlockProducer::ShellLevel(p) => p.produce(environment),
+            BlockProducer::Container(p) => p.produce(environment),
+            BlockProducer::Ci(p) => p.produce(environment),
+            BlockProducer::NixShell(p) => p.produce(environment),
+            BlockProducer::Direnv(p) => p.produce(environment),
+            BlockProducer::Locale(p) => p.produce(environment),
+            BlockProducer::Quota(p) => p.produce(environment),
+            BlockProducer::Kubernetes(p) => p.produce(environment),
+            BlockProducer::Azure(p) => p.produce(environment),
+            BlockProducer::Terraform(p) => p.produce(environment),
+            BlockProducer::Python(p) => p.produce(environment),
+            BlockProducer::RustToolchain(p) => p.produce(environment),
+            BlockProducer::Go(p) => p.produce(environment),
+            BlockProducer::Java(p) => p.produce(environment),
+            BlockProducer::Php(p) => p.produce(environment),
+            BlockProducer::ToolVersion(p) => p.produce(environment),
+            BlockProducer::PinnedTools(p) => p.produce(environment),
             BlockProducer::Newline(p) => p.produce(environment),
             BlockProducer::Space(p) => p.produce(environment),
             BlockProducer::Text(p) => p.produce(environment),
+            BlockProducer::RotatingText(p) => p.produce(environment),
+            BlockProducer::Fill(p) => p.produce(environment),
+            BlockProducer::EnvVar(p) => p.produce(environment),
+            BlockProducer::Command(p) => p.produce(environment),
             BlockProducer::ExitStatusSymbol(p) => p.produce(environment),
+            BlockProducer::Marker(p) => p.produce(environment),
+            BlockProducer::Note(p) => p.produce(environment),
+            BlockProducer::Todo(p) => p.produce(environment),
+            BlockProducer::NowPlaying(p) => p.produce(environment),
             BlockProducer::Or(p) => p.produce(environment),
+            BlockProducer::OrElse(p) => p.produce(environment),
+            BlockProducer::TakeFirst(p) => p.produce(environment),
             BlockProducer::Sequence(p) => p.produce(environment),
             BlockProducer::Separated(p) => p.produce(environment),
             BlockProducer::Styled(p) => p.produce(environment),
+            BlockProducer::Sticky(p) => p.produce(environment),
+            BlockProducer::Cached(p) => p.produce(environment),
+            BlockProducer::Timeout(p) => p.produce(environment),
+            BlockProducer::Group(p) => p.produce(environment),
+            BlockProducer::RightAlign(p) => p.produce(environment),
+            BlockProducer::Shrink(p) => p.produce(environment),
+            BlockProducer::Truncate(p) => p.produce(environment),
+            BlockProducer::Hyperlink(p) => p.produce(environment),
+            BlockProducer::Transform(p) => p.produce(environment),
+            BlockProducer::Surround(p) => p.produce(environment),
+            BlockProducer::When(p) => p.produce(environment),
+            BlockProducer::WhenTime(p) => p.produce(environment),
         }
     }
 }
+
+thread_local! {
+    static PRODUCER_STACK: RefCell<Vec<&'static str>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Returns the names of the producers being evaluated on the current thread, outermost first.
+///
+/// This is meant for diagnostics, e.g. to report which producer panicked.
+pub fn producer_stack() -> Vec<&'static str> {
+    PRODUCER_STACK.with(|stack| stack.borrow().clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{collapse_separators, fill_lines, Block, BlockKind, Sequence, Text, Username};
+    use crate::{BlockProducer, Cancellation, Environment, FaultInjection};
+    use std::time::Duration;
+
+    fn texts(blocks: &[Block]) -> Vec<&str> {
+        blocks.iter().map(|b| b.text.as_str()).collect()
+    }
+
+    #[test]
+    fn blocks_produced_before_cancellation_are_kept() {
+        let environment = Environment::new(None)
+            .with_fault_injection(FaultInjection {
+                timeouts: vec!["username".into()],
+                ..Default::default()
+            })
+            .with_cancellation(Cancellation::with_timeout(Duration::from_millis(50)));
+        let producer = BlockProducer::Sequence(Sequence(vec![
+            BlockProducer::Text(Text::new("a")),
+            BlockProducer::Username(Username::new()),
+            BlockProducer::Text(Text::new("b")),
+        ]));
+        assert_eq!(texts(&producer.produce(&environment)), ["a"]);
+    }
+
+    #[test]
+    fn truncation_prefers_break_hints() {
+        let block = || Block::new("/home/user/src/block/git_head.rs").with_break_hints_at("/");
+        assert_eq!(block().truncate_start(20, "…").text, "…/block/git_head.rs");
+        assert_eq!(block().truncate_start(10, "…").text, "…t_head.rs");
+        assert_eq!(block().truncate_end(16, "…").text, "/home/user/src…");
+        assert_eq!(block().truncate_end(40, "…").text, block().text);
+        let block = Block::new("crate::block::git_head").with_break_hints_at("::");
+        assert_eq!(block.truncate_start(12, "…").text, "…::git_head");
+        assert_eq!(Block::new("日本語の文").truncate_end(6, "…").text, "日本…");
+    }
+
+    #[test]
+    fn stray_separators_are_removed() {
+        let separator = || Block::new(" | ").with_kind(BlockKind::Separator);
+        let space = || Block::new(" ").with_kind(BlockKind::Space);
+        let blocks = vec![
+            separator(),
+            Block::new("a"),
+            separator(),
+            Block::new(""),
+            separator(),
+            Block::new("b"),
+            separator(),
+            Block::new("\n").with_kind(BlockKind::Newline),
+            Block::new(">"),
+            space(),
+            space(),
+        ];
+        assert_eq!(
+            texts(&collapse_separators(blocks)),
+            ["a", " | ", "", "b", "\n", ">", " "]
+        );
+    }
+
+    #[test]
+    fn fill_blocks_share_free_columns_of_their_line() {
+        let fill = |text: &str| Block::new(text).with_kind(BlockKind::Fill);
+        let blocks = vec![
+            Block::new("ab"),
+            fill("-"),
+            Block::new("c"),
+            fill("=+"),
+            Block::new("\n").with_kind(BlockKind::Newline),
+            fill("─"),
+        ];
+        assert_eq!(
+            texts(&fill_lines(blocks.clone(), Some(10))),
+            ["ab", "----", "c", "=+=", "\n", "──────────"]
+        );
+        assert_eq!(
+            texts(&fill_lines(blocks, None)),
+            ["ab", "", "c", "", "\n", ""]
+        );
+        let blocks = vec![Block::new("日本"), fill(" "), Block::new("x"), fill("語")];
+        assert_eq!(
+            texts(&fill_lines(blocks, Some(10))),
+            ["日本", "   ", "x", "語"]
+        );
+    }
+
+    #[test]
+    fn padding_takes_columns_left_by_fill_blocks() {
+        let fill = Block::new("-").with_kind(BlockKind::Fill);
+        let padding = Block::new(" ").with_kind(BlockKind::Padding);
+        let blocks = vec![Block::new("ab"), padding.clone(), Block::new("cd")];
+        assert_eq!(texts(&fill_lines(blocks, Some(8))), ["ab", "    ", "cd"]);
+        let blocks = vec![Block::new("ab"), fill, padding, Block::new("cd")];
+        assert_eq!(
+            texts(&fill_lines(blocks, Some(8))),
+            ["ab", "----", "", "cd"]
+        );
+    }
+}
@@ -0,0 +1,126 @@
+// Copyright (C) 2020 Stephane Raux. Distributed under the MIT license.
+
+use crate::{symbol, Block, Environment, Style, Symbol};
+use serde::{Deserialize, Serialize};
+use std::{env, fs, path::PathBuf};
+
+/// Shows the name of the active Azure CLI subscription
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Azure {
+    #[serde(default)]
+    style: Style,
+    #[serde(default = "default_prefix")]
+    prefix: Symbol,
+}
+
+impl Azure {
+    pub fn new() -> Self {
+        Azure {
+            style: Default::default(),
+            prefix: default_prefix(),
+        }
+    }
+
+    pub fn with_style<T>(self, style: T) -> Self
+    where
+        T: Into<Style>,
+    {
+        Self {
+            style: style.into(),
+            ..self
+        }
+    }
+
+    pub fn with_prefix<T>(self, prefix: T) -> Self
+    where
+        T: Into<Symbol>,
+    {
+        Self {
+            prefix: prefix.into(),
+            ..self
+        }
+    }
+
+    pub fn produce(&self, environment: &Environment) -> Vec<Block> {
+        let Some(path) = profile_path() else {
+            return Vec::new();
+        };
+        let Ok(contents) = fs::read_to_string(&path) else {
+            return Vec::new();
+        };
+        let subscription = match active_subscription(&contents) {
+            Ok(Some(name)) => name,
+            Ok(None) => return Vec::new(),
+            Err(e) => {
+                tracing::error!("Failed to parse {}: {}", path.display(), e);
+                return Vec::new();
+            }
+        };
+        vec![
+            Block::new(self.prefix.get(environment.symbols())).with_style(&self.style),
+            Block::new(subscription).with_style(&self.style),
+        ]
+    }
+}
+
+impl Default for Azure {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Deserialize)]
+struct Profile {
+    #[serde(default)]
+    subscriptions: Vec<Subscription>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Subscription {
+    name: String,
+    #[serde(default)]
+    is_default: bool,
+}
+
+/// Returns the path of the Azure CLI profile, in `$AZURE_CONFIG_DIR` or `~/.azure`.
+fn profile_path() -> Option<PathBuf> {
+    let dir = env::var_os("AZURE_CONFIG_DIR")
+        .map(PathBuf::from)
+        .or_else(|| Some(dirs::home_dir()?.join(".azure")))?;
+    Some(dir.join("azureProfile.json"))
+}
+
+/// Returns the name of the default subscription in an Azure CLI profile.
+fn active_subscription(profile: &str) -> Result<Option<String>, serde_json::Error> {
+    // The Azure CLI writes the profile with a byte order mark.
+    let profile = profile.trim_start_matches('\u{feff}');
+    let profile = serde_json::from_str::<Profile>(profile)?;
+    Ok(profile
+        .subscriptions
+        .into_iter()
+        .find(|s| s.is_default)
+        .map(|s| s.name))
+}
+
+fn default_prefix() -> Symbol {
+    symbol::AZURE
+}
+
+#[cfg(test)]
+mod tests {
+    use super::active_subscription;
+
+    #[test]
+    fn default_subscription_is_found() {
+        let profile = r#"{"installationId": "x", "subscriptions": [
+            {"id": "1", "name": "Dev", "isDefault": false},
+            {"id": "2", "name": "Production", "isDefault": true}
+        ]}"#;
+        let profile = format!("\u{feff}{}", profile);
+        assert_eq!(
+            active_subscription(&profile).unwrap().as_deref(),
+            Some("Production")
+        );
+    }
+}
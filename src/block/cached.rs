@@ -0,0 +1,147 @@
+// Copyright (C) 2020 Stephane Raux. Distributed under the MIT license.
+
+use crate::{cache::Cache, Block, BlockProducer, Environment};
+use serde::{Deserialize, Serialize};
+use std::{
+    path::Path,
+    time::{Duration, SystemTime},
+};
+
+/// Shows the blocks of a producer, reusing those it produced previously until they expire
+///
+/// Blocks are persisted in the cache directory under `key`, separately for each working
+/// directory unless `per_directory` is `false`. Expired blocks are discarded whenever new ones are
+/// stored. Nothing is cached if caching is disabled.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Cached {
+    key: String,
+    #[serde(with = "humantime_serde")]
+    ttl: Duration,
+    producer: Box<BlockProducer>,
+    #[serde(default = "default_per_directory")]
+    per_directory: bool,
+}
+
+impl Cached {
+    pub fn new<T>(key: T, ttl: Duration, producer: BlockProducer) -> Self
+    where
+        T: Into<String>,
+    {
+        Cached {
+            key: key.into(),
+            ttl,
+            producer: Box::new(producer),
+            per_directory: default_per_directory(),
+        }
+    }
+
+    /// Indicates if blocks are cached separately for each working directory. Defaults to `true`.
+    pub fn per_directory(self, yes: bool) -> Self {
+        Self {
+            per_directory: yes,
+            ..self
+        }
+    }
+
+    pub fn produce(&self, environment: &Environment) -> Vec<Block> {
+        let Some(dir) = environment.cache_dir() else {
+            return self.producer.produce(environment);
+        };
+        let path = dir
+            .join("blocks")
+            .join(crate::cache::file_name(Path::new(&self.key)));
+        let mut cache = Cache::open(path, String::new());
+        let name = match environment.working_dir().filter(|_| self.per_directory) {
+            Some(dir) => dir.to_string_lossy().into_owned(),
+            None => String::new(),
+        };
+        let now = SystemTime::now();
+        let is_fresh = |produced_at| {
+            now.duration_since(produced_at)
+                .is_ok_and(|age| age < self.ttl)
+        };
+        let cached = cache
+            .get::<CachedBlocks>(&name, "")
+            .filter(|cached| is_fresh(cached.produced_at));
+        if let Some(cached) = cached {
+            return cached.blocks;
+        }
+        let blocks = self.producer.produce(environment);
+        // Blocks produced after cancellation may be incomplete.
+        if !environment.is_cancelled() {
+            let cached = CachedBlocks {
+                blocks,
+                produced_at: now,
+            };
+            // Entries for other directories would otherwise accumulate forever.
+            cache.retain(|_, other: ProductionTime| is_fresh(other.produced_at));
+            cache.insert(&name, "", &cached);
+            return cached.blocks;
+        }
+        blocks
+    }
+}
+
+#[derive(Deserialize, Serialize)]
+struct CachedBlocks {
+    blocks: Vec<Block>,
+    produced_at: SystemTime,
+}
+
+/// Part of [`CachedBlocks`] needed to prune expired entries without reading their blocks
+#[derive(Deserialize)]
+struct ProductionTime {
+    produced_at: SystemTime,
+}
+
+fn default_per_directory() -> bool {
+    true
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::{Cached, CachedBlocks};
+    use crate::{
+        block::{Command, Text},
+        cache::{self, Cache},
+        BlockProducer, Environment,
+    };
+    use std::{fs, path::Path, time::Duration};
+
+    #[test]
+    fn blocks_are_reused_until_they_expire() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("value");
+        fs::write(&file, "a").unwrap();
+        let environment =
+            Environment::new(Some(dir.path().to_owned())).with_cache_dir(Some(dir.path().into()));
+        let command = Command::new("cat").with_args([file.to_string_lossy()]);
+        let cached = |ttl| Cached::new("test", ttl, BlockProducer::Command(command.clone()));
+        let text = |cached: Cached| cached.produce(&environment).pop().unwrap().text;
+        assert_eq!(text(cached(Duration::from_secs(60))), "a");
+        fs::write(&file, "b").unwrap();
+        assert_eq!(text(cached(Duration::from_secs(60))), "a");
+        assert_eq!(text(cached(Duration::ZERO)), "b");
+    }
+
+    #[test]
+    fn expired_entries_are_pruned_on_insert() {
+        let dir = tempfile::tempdir().unwrap();
+        let produce = |working_dir: &Path, ttl| {
+            let environment = Environment::new(Some(working_dir.to_owned()))
+                .with_cache_dir(Some(dir.path().into()));
+            Cached::new("test", ttl, BlockProducer::Text(Text::new("a"))).produce(&environment);
+        };
+        let (first, second) = (dir.path().join("first"), dir.path().join("second"));
+        produce(&first, Duration::ZERO);
+        produce(&second, Duration::ZERO);
+        let path = dir
+            .path()
+            .join("blocks")
+            .join(cache::file_name(Path::new("test")));
+        let cache = Cache::open(path, String::new());
+        let name = |dir: &Path| dir.to_string_lossy().into_owned();
+        assert!(cache.get::<CachedBlocks>(&name(&first), "").is_none());
+        assert!(cache.get::<CachedBlocks>(&name(&second), "").is_some());
+    }
+}
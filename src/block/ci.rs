@@ -0,0 +1,116 @@
+// Copyright (C) 2020 Stephane Raux. Distributed under the MIT license.
+
+use crate::{symbol, Block, Environment, Style, Symbol};
+use serde::{Deserialize, Serialize};
+use std::env;
+
+/// Shows the name of the CI provider when the shell runs in a CI job
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Ci {
+    #[serde(default)]
+    style: Style,
+    #[serde(default = "default_prefix")]
+    prefix: Symbol,
+}
+
+impl Ci {
+    pub fn new() -> Self {
+        Ci {
+            style: Default::default(),
+            prefix: default_prefix(),
+        }
+    }
+
+    pub fn with_style<T>(self, style: T) -> Self
+    where
+        T: Into<Style>,
+    {
+        Self {
+            style: style.into(),
+            ..self
+        }
+    }
+
+    pub fn with_prefix<T>(self, prefix: T) -> Self
+    where
+        T: Into<Symbol>,
+    {
+        Self {
+            prefix: prefix.into(),
+            ..self
+        }
+    }
+
+    pub fn produce(&self, environment: &Environment) -> Vec<Block> {
+        let Some(provider) = detect(|name| env::var(name).ok()) else {
+            return Vec::new();
+        };
+        vec![
+            Block::new(self.prefix.get(environment.symbols())).with_style(&self.style),
+            Block::new(provider).with_style(&self.style),
+        ]
+    }
+}
+
+impl Default for Ci {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Environment variables identifying CI providers, and the names of the providers
+const PROVIDERS: &[(&str, &str)] = &[
+    ("GITHUB_ACTIONS", "GitHub Actions"),
+    ("GITLAB_CI", "GitLab CI"),
+    ("BUILDKITE", "Buildkite"),
+    ("CIRCLECI", "CircleCI"),
+    ("TRAVIS", "Travis CI"),
+    ("JENKINS_URL", "Jenkins"),
+    ("TF_BUILD", "Azure Pipelines"),
+    ("BITBUCKET_BUILD_NUMBER", "Bitbucket Pipelines"),
+    ("TEAMCITY_VERSION", "TeamCity"),
+    ("APPVEYOR", "AppVeyor"),
+    ("DRONE", "Drone"),
+    ("CODEBUILD_BUILD_ID", "AWS CodeBuild"),
+];
+
+/// Returns the name of the CI provider, given a function returning the value of environment
+/// variables.
+fn detect<F>(var: F) -> Option<&'static str>
+where
+    F: Fn(&str) -> Option<String>,
+{
+    let is_set = |name| var(name).is_some_and(|value| !value.is_empty() && value != "false");
+    PROVIDERS
+        .iter()
+        .find(|(name, _)| is_set(name))
+        .map(|&(_, provider)| provider)
+        .or_else(|| is_set("CI").then_some("CI"))
+}
+
+fn default_prefix() -> Symbol {
+    symbol::CI
+}
+
+#[cfg(test)]
+mod tests {
+    use super::detect;
+
+    #[test]
+    fn ci_providers_are_detected() {
+        let vars = |set: &'static [(&'static str, &'static str)]| {
+            move |name: &str| {
+                set.iter()
+                    .find(|(n, _)| *n == name)
+                    .map(|(_, v)| v.to_string())
+            }
+        };
+        assert_eq!(detect(vars(&[])), None);
+        assert_eq!(detect(vars(&[("CI", "false")])), None);
+        assert_eq!(detect(vars(&[("CI", "true")])), Some("CI"));
+        assert_eq!(
+            detect(vars(&[("CI", "true"), ("GITLAB_CI", "true")])),
+            Some("GitLab CI")
+        );
+    }
+}
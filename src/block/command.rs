@@ -0,0 +1,133 @@
+// Copyright (C) 2020 Stephane Raux. Distributed under the MIT license.
+
+use crate::{Block, Environment, Style, Symbol};
+use serde::{Deserialize, Serialize};
+use std::{io, time::Duration};
+
+/// Shows the first line of the output of a program
+///
+/// The program runs in the working directory with the variables of
+/// [`Environment::variables`] set. Nothing is shown if it fails, prints nothing or does not
+/// complete within `timeout`.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Command {
+    program: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    args: Vec<String>,
+    #[serde(default)]
+    style: Style,
+    #[serde(default)]
+    prefix: Symbol,
+    #[serde(with = "humantime_serde", default = "default_timeout")]
+    timeout: Duration,
+}
+
+impl Command {
+    pub fn new<T>(program: T) -> Self
+    where
+        T: Into<String>,
+    {
+        Command {
+            program: program.into(),
+            args: Vec::new(),
+            style: Default::default(),
+            prefix: Symbol::default(),
+            timeout: default_timeout(),
+        }
+    }
+
+    pub fn with_args<I>(self, args: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        Self {
+            args: args.into_iter().map(Into::into).collect(),
+            ..self
+        }
+    }
+
+    pub fn with_style<T>(self, style: T) -> Self
+    where
+        T: Into<Style>,
+    {
+        Self {
+            style: style.into(),
+            ..self
+        }
+    }
+
+    pub fn with_prefix<T>(self, prefix: T) -> Self
+    where
+        T: Into<Symbol>,
+    {
+        Self {
+            prefix: prefix.into(),
+            ..self
+        }
+    }
+
+    /// Sets the time after which the program is killed and nothing is shown.
+    pub fn with_timeout(self, timeout: Duration) -> Self {
+        Self { timeout, ..self }
+    }
+
+    pub fn produce(&self, environment: &Environment) -> Vec<Block> {
+        let cancellation = environment.cancellation().limited_to(self.timeout);
+        let output = crate::process::run(
+            environment.command(&self.program).args(&self.args),
+            &cancellation,
+        );
+        let output = match output {
+            Ok(output) if output.status.success() => output,
+            Ok(_) => return Vec::new(),
+            Err(e) if e.kind() == io::ErrorKind::TimedOut => {
+                tracing::error!("{} timed out after {:?}", self.program, self.timeout);
+                return Vec::new();
+            }
+            Err(e) => {
+                tracing::error!("Failed to run {}: {}", self.program, e);
+                return Vec::new();
+            }
+        };
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let text = stdout.lines().next().unwrap_or_default().trim();
+        if text.is_empty() {
+            return Vec::new();
+        }
+        vec![
+            Block::new(self.prefix.get(environment.symbols())).with_style(&self.style),
+            Block::new(text).with_style(&self.style),
+        ]
+    }
+}
+
+fn default_timeout() -> Duration {
+    Duration::from_millis(500)
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::Command;
+    use crate::Environment;
+    use std::time::Duration;
+
+    #[test]
+    fn output_is_shown_unless_program_fails_or_times_out() {
+        let environment = Environment::new(None);
+        let sh = |script: &str| Command::new("sh").with_args(["-c", script]);
+        let blocks = sh("echo ' hi '").produce(&environment);
+        assert_eq!(blocks.last().map(|b| b.text.as_str()), Some("hi"));
+        assert!(sh("echo hi; exit 1").produce(&environment).is_empty());
+        let slow = sh("sleep 5; echo hi").with_timeout(Duration::from_millis(50));
+        assert!(slow.produce(&environment).is_empty());
+    }
+
+    #[test]
+    fn first_line_of_output_is_shown() {
+        let blocks = Command::new("printf")
+            .with_args([" build ok \nsee log\n"])
+            .produce(&Environment::new(None));
+        assert_eq!(blocks[1].text, "build ok");
+    }
+}
@@ -0,0 +1,154 @@
+// Copyright (C) 2020 Stephane Raux. Distributed under the MIT license.
+
+use crate::{symbol, Block, Environment, Style, Symbol};
+use serde::{Deserialize, Serialize};
+use std::{env, fs, path::Path};
+
+/// Shows an indicator when the shell runs inside a container, e.g. a dev container
+///
+/// Docker, Podman and LXC are detected from the `container` environment variable, `/.dockerenv`,
+/// `/run/.containerenv` and the control groups of the init process.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Container {
+    #[serde(default)]
+    style: Style,
+    #[serde(default = "default_prefix")]
+    prefix: Symbol,
+    #[serde(default = "default_show_name")]
+    show_name: bool,
+}
+
+impl Container {
+    pub fn new() -> Self {
+        Container {
+            style: Default::default(),
+            prefix: default_prefix(),
+            show_name: default_show_name(),
+        }
+    }
+
+    pub fn with_style<T>(self, style: T) -> Self
+    where
+        T: Into<Style>,
+    {
+        Self {
+            style: style.into(),
+            ..self
+        }
+    }
+
+    pub fn with_prefix<T>(self, prefix: T) -> Self
+    where
+        T: Into<Symbol>,
+    {
+        Self {
+            prefix: prefix.into(),
+            ..self
+        }
+    }
+
+    /// Indicates if the name of the container is shown when known, instead of the name of the
+    /// container engine. Defaults to `true`.
+    pub fn show_name(self, yes: bool) -> Self {
+        Self {
+            show_name: yes,
+            ..self
+        }
+    }
+
+    pub fn produce(&self, environment: &Environment) -> Vec<Block> {
+        let container_var = env::var("container").ok();
+        let Some(container) = detect(container_var.as_deref(), Path::new("/")) else {
+            return Vec::new();
+        };
+        let text = container
+            .name
+            .filter(|_| self.show_name)
+            .unwrap_or(container.engine);
+        vec![
+            Block::new(self.prefix.get(environment.symbols())).with_style(&self.style),
+            Block::new(text).with_style(&self.style),
+        ]
+    }
+}
+
+impl Default for Container {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Container the shell runs in
+#[derive(Debug, Eq, PartialEq)]
+struct DetectedContainer {
+    /// Container engine, e.g. `docker`
+    engine: String,
+    name: Option<String>,
+}
+
+/// Detects the container the shell runs in, given the value of the `container` environment
+/// variable and the root of the file system.
+fn detect(container_var: Option<&str>, root: &Path) -> Option<DetectedContainer> {
+    let podman_env = fs::read_to_string(root.join("run/.containerenv")).ok();
+    let name = podman_env.as_deref().and_then(containerenv_name);
+    let engine = container_var
+        .filter(|v| !v.is_empty())
+        .map(str::to_owned)
+        .or_else(|| podman_env.is_some().then(|| "podman".into()))
+        .or_else(|| root.join(".dockerenv").exists().then(|| "docker".into()))
+        .or_else(|| {
+            let cgroup = fs::read_to_string(root.join("proc/1/cgroup")).ok()?;
+            ["docker", "lxc", "podman", "containerd", "kubepods"]
+                .into_iter()
+                .find(|engine| cgroup.contains(engine))
+                .map(str::to_owned)
+        })?;
+    Some(DetectedContainer { engine, name })
+}
+
+/// Returns the container name found in Podman's `/run/.containerenv`, e.g. `name="dev"`.
+fn containerenv_name(contents: &str) -> Option<String> {
+    let name = contents
+        .lines()
+        .find_map(|line| line.strip_prefix("name="))?
+        .trim()
+        .trim_matches('"');
+    (!name.is_empty()).then(|| name.to_owned())
+}
+
+fn default_prefix() -> Symbol {
+    symbol::CONTAINER
+}
+
+fn default_show_name() -> bool {
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{detect, DetectedContainer};
+    use std::fs;
+
+    #[test]
+    fn containers_are_detected() {
+        let root = tempfile::tempdir().unwrap();
+        assert_eq!(detect(None, root.path()), None);
+        fs::write(root.path().join(".dockerenv"), "").unwrap();
+        let docker = DetectedContainer {
+            engine: "docker".into(),
+            name: None,
+        };
+        assert_eq!(detect(None, root.path()), Some(docker));
+        fs::create_dir(root.path().join("run")).unwrap();
+        fs::write(
+            root.path().join("run/.containerenv"),
+            "engine=\"podman-4.9.3\"\nname=\"dev\"\n",
+        )
+        .unwrap();
+        let podman = DetectedContainer {
+            engine: "podman".into(),
+            name: Some("dev".into()),
+        };
+        assert_eq!(detect(Some("podman"), root.path()), Some(podman));
+    }
+}
@@ -0,0 +1,134 @@
+// Copyright (C) 2020 Stephane Raux. Distributed under the MIT license.
+
+use crate::{symbol, Block, Environment, Style, Symbol};
+use serde::{Deserialize, Serialize};
+
+/// Shows the current date, e.g. `Fri Oct 16`
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Date {
+    #[serde(default)]
+    style: Style,
+    #[serde(default = "default_prefix")]
+    prefix: Symbol,
+    #[serde(default = "default_format")]
+    format: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    alternative_format: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    time_zone: Option<String>,
+}
+
+impl Date {
+    pub fn new() -> Self {
+        Date {
+            style: Default::default(),
+            prefix: default_prefix(),
+            format: default_format(),
+            alternative_format: None,
+            time_zone: None,
+        }
+    }
+
+    pub fn with_style<T>(self, style: T) -> Self
+    where
+        T: Into<Style>,
+    {
+        Self {
+            style: style.into(),
+            ..self
+        }
+    }
+
+    pub fn with_prefix<T>(self, prefix: T) -> Self
+    where
+        T: Into<Symbol>,
+    {
+        Self {
+            prefix: prefix.into(),
+            ..self
+        }
+    }
+
+    /// Sets the strftime-style format of the date. Defaults to `%a %b %-d`.
+    pub fn with_format<T>(self, format: T) -> Self
+    where
+        T: Into<String>,
+    {
+        Self {
+            format: format.into(),
+            ..self
+        }
+    }
+
+    /// Sets the format used when the alternative prompt is used, e.g. a shorter one. The date is
+    /// hidden in the alternative prompt by default.
+    pub fn with_alternative_format<T>(self, format: T) -> Self
+    where
+        T: Into<String>,
+    {
+        Self {
+            alternative_format: Some(format.into()),
+            ..self
+        }
+    }
+
+    /// Shows the date in the time zone with the given IANA name, e.g. `Asia/Tokyo`, instead of the
+    /// system time zone.
+    pub fn with_time_zone<T>(self, time_zone: T) -> Self
+    where
+        T: Into<String>,
+    {
+        Self {
+            time_zone: Some(time_zone.into()),
+            ..self
+        }
+    }
+
+    pub fn produce(&self, environment: &Environment) -> Vec<Block> {
+        let format = if environment.alternative_prompt_is_used() {
+            self.alternative_format.as_deref()
+        } else {
+            Some(self.format.as_str())
+        };
+        let Some(date) =
+            format.and_then(|format| super::time::format_now(format, self.time_zone.as_deref()))
+        else {
+            return Vec::new();
+        };
+        vec![
+            Block::new(self.prefix.get(environment.symbols())).with_style(&self.style),
+            Block::new(date).with_style(&self.style),
+        ]
+    }
+}
+
+impl Default for Date {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn default_prefix() -> Symbol {
+    symbol::DATE
+}
+
+fn default_format() -> String {
+    "%a %b %-d".into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Date;
+    use crate::Environment;
+
+    #[test]
+    fn date_is_hidden_in_alternative_prompt_by_default() {
+        let environment = Environment::new(None).force_alternative_prompt(true);
+        let date = Date::new().with_time_zone("UTC");
+        assert!(date.produce(&environment).is_empty());
+        let date = date.with_alternative_format("%z");
+        assert_eq!(date.produce(&environment)[1].text, "+0000");
+        let date = date.with_format("%Z");
+        assert_eq!(date.produce(&Environment::new(None))[1].text, "UTC");
+    }
+}
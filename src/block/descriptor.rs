@@ -0,0 +1,385 @@
+// Copyright (C) 2020 Stephane Raux. Distributed under the MIT license.
+
+use crate::block::*;
+use serde::{
+    de::{self, DeserializeOwned, Visitor},
+    forward_to_deserialize_any, Deserializer, Serialize,
+};
+use std::cell::Cell;
+
+/// Description of a producer, for documentation and configuration tooling
+#[derive(Clone, Debug, Serialize)]
+pub struct ProducerDescriptor {
+    /// Name identifying the producer in the configuration
+    pub name: &'static str,
+    pub description: &'static str,
+    /// Names of the configuration fields
+    pub fields: &'static [&'static str],
+    /// Configuration of the producer when created with default values, or `None` if it has
+    /// required fields. Optional fields without a default value are omitted.
+    pub defaults: Option<serde_json::Value>,
+}
+
+impl ProducerDescriptor {
+    fn new<T>(name: &'static str, description: &'static str) -> Self
+    where
+        T: DeserializeOwned,
+    {
+        ProducerDescriptor {
+            name,
+            description,
+            fields: names::<T>(),
+            defaults: None,
+        }
+    }
+
+    fn with_defaults<T>(name: &'static str, description: &'static str) -> Self
+    where
+        T: Default + DeserializeOwned + Serialize,
+    {
+        ProducerDescriptor {
+            defaults: Some(serde_json::to_value(T::default()).expect("Serializing cannot fail")),
+            ..Self::new::<T>(name, description)
+        }
+    }
+}
+
+impl BlockProducer {
+    /// Returns descriptions of all producers.
+    pub fn descriptors() -> Vec<ProducerDescriptor> {
+        type D = ProducerDescriptor;
+        vec![
+            D::with_defaults::<Elapsed>("Elapsed", "Shows the duration of the previous command."),
+            D::with_defaults::<ExitCode>(
+                "ExitCode",
+                "Shows the exit code of the previous command if it was not zero.",
+            ),
+            D::with_defaults::<Time>(
+                "Time",
+                "Shows the time at which the prompt was generated.",
+            ),
+            D::with_defaults::<Date>("Date", "Shows the current date."),
+            D::with_defaults::<Greeting>(
+                "Greeting",
+                "Shows a greeting depending on the time of day.",
+            ),
+            D::with_defaults::<WeekNumber>(
+                "WeekNumber",
+                "Shows the ISO week number and the day of the year.",
+            ),
+            D::with_defaults::<GitAheadBehind>(
+                "GitAheadBehind",
+                "Shows how many commits the current branch is ahead and behind its upstream.",
+            ),
+            D::with_defaults::<GitCommitAge>(
+                "GitCommitAge",
+                "Shows how long ago the commit HEAD points to was made.",
+            ),
+            D::with_defaults::<GitConflicts>(
+                "GitConflicts",
+                "Shows the number of files with unresolved merge conflicts.",
+            ),
+            D::with_defaults::<GitHead>(
+                "GitHead",
+                "Shows the current git branch, or the abbreviated commit hash if HEAD is detached.",
+            ),
+            D::with_defaults::<GitIdentity>(
+                "GitIdentity",
+                "Shows the identity git uses to create commits in the current repository.",
+            ),
+            D::with_defaults::<GitOperation>(
+                "GitOperation",
+                "Shows the git operation in progress, e.g. a rebase.",
+            ),
+            D::with_defaults::<GitPath>(
+                "GitPath",
+                "Shows the working directory relative to the root of its git repository.",
+            ),
+            D::with_defaults::<GitStatus>(
+                "GitStatus",
+                "Shows how many files are staged, modified and untracked in the git working tree.",
+            ),
+            D::with_defaults::<GitSubmodules>(
+                "GitSubmodules",
+                "Shows how many git submodules are out of sync or not initialized.",
+            ),
+            D::with_defaults::<GitTag>(
+                "GitTag",
+                "Shows the nearest annotated tag reachable from HEAD.",
+            ),
+            D::with_defaults::<JjChange>(
+                "JjChange",
+                "Shows the id of the working-copy change in a Jujutsu repository.",
+            ),
+            D::with_defaults::<JjBookmarks>(
+                "JjBookmarks",
+                "Shows the local bookmarks pointing to the working-copy change in a Jujutsu \
+                 repository.",
+            ),
+            D::with_defaults::<JjDescription>(
+                "JjDescription",
+                "Shows the first line of the description of the working-copy change in a Jujutsu \
+                 repository.",
+            ),
+            D::with_defaults::<VcsHead>(
+                "VcsHead",
+                "Shows what is checked out in the innermost repository, whatever its version \
+                 control system.",
+            ),
+            D::with_defaults::<MultiRepo>(
+                "MultiRepo",
+                "Summarizes the git repositories directly in the working directory.",
+            ),
+            D::with_defaults::<OsIcon>(
+                "OsIcon",
+                "Shows an icon for the operating system or Linux distribution.",
+            ),
+            D::with_defaults::<Hostname>("Hostname", "Shows the name of the host."),
+            D::with_defaults::<Ssh>("Ssh", "Shows a badge in SSH sessions."),
+            D::with_defaults::<Multiplexer>(
+                "Multiplexer",
+                "Shows the tmux or GNU screen session.",
+            ),
+            D::with_defaults::<LocalIp>("LocalIp", "Shows the primary local IP address."),
+            D::with_defaults::<PublicIp>(
+                "PublicIp",
+                "Shows the public IP address of the machine, fetched in the background.",
+            ),
+            D::with_defaults::<Weather>(
+                "Weather",
+                "Shows the current weather, refreshed in the background.",
+            ),
+            D::with_defaults::<Vpn>("Vpn", "Shows an indicator when a VPN is active."),
+            D::with_defaults::<WorkingDirectory>(
+                "WorkingDirectory",
+                "Shows the current working directory.",
+            ),
+            D::with_defaults::<Username>("Username", "Shows the name of the current user."),
+            D::with_defaults::<Root>("Root", "Shows an indicator when running as root."),
+            D::with_defaults::<ShellLevel>(
+                "ShellLevel",
+                "Shows the nesting level of the shell when it exceeds a threshold.",
+            ),
+            D::with_defaults::<Container>(
+                "Container",
+                "Shows an indicator when the shell runs inside a container.",
+            ),
+            D::with_defaults::<Ci>("Ci", "Shows the CI provider running the shell."),
+            D::with_defaults::<NixShell>(
+                "NixShell",
+                "Shows the name of the Nix shell the shell runs in.",
+            ),
+            D::with_defaults::<Direnv>(
+                "Direnv",
+                "Shows the status of the direnv .envrc file applying to the working directory.",
+            ),
+            D::with_defaults::<Locale>(
+                "Locale",
+                "Shows a warning when the character encoding of the locale is not UTF-8.",
+            ),
+            D::with_defaults::<Quota>(
+                "Quota",
+                "Shows the disk quota left to the user when it runs low.",
+            ),
+            D::with_defaults::<Kubernetes>(
+                "Kubernetes",
+                "Shows the current Kubernetes context and namespace.",
+            ),
+            D::with_defaults::<Azure>(
+                "Azure",
+                "Shows the name of the active Azure CLI subscription.",
+            ),
+            D::with_defaults::<Terraform>(
+                "Terraform",
+                "Shows the current Terraform or OpenTofu workspace in Terraform directories.",
+            ),
+            D::with_defaults::<Python>(
+                "Python",
+                "Shows the active Python virtual environment or Conda environment.",
+            ),
+            D::with_defaults::<RustToolchain>(
+                "RustToolchain",
+                "Shows the Rust toolchain selected by rustup in a Cargo project.",
+            ),
+            D::with_defaults::<Go>("Go", "Shows the installed Go version in a Go module."),
+            D::with_defaults::<Java>(
+                "Java",
+                "Shows the version of the active JDK in a Maven or Gradle project.",
+            ),
+            D::with_defaults::<Php>("Php", "Shows the PHP version in a Composer project."),
+            D::new::<ToolVersion>(
+                "ToolVersion",
+                "Shows the version of a tool, as found by running a command or reading a file.",
+            ),
+            D::with_defaults::<PinnedTools>(
+                "PinnedTools",
+                "Shows the tool versions pinned for the working directory with mise or asdf.",
+            ),
+            D::with_defaults::<Newline>("Newline", "Adds a newline character."),
+            D::with_defaults::<Space>("Space", "Adds a space character."),
+            D::new::<Text>("Text", "Shows fixed text."),
+            D::new::<RotatingText>(
+                "RotatingText",
+                "Shows one of several texts, changing at regular intervals.",
+            ),
+            D::with_defaults::<Fill>(
+                "Fill",
+                "Repeats text to fill the rest of the line up to the terminal width.",
+            ),
+            D::new::<EnvVar>(
+                "EnvVar",
+                "Shows the value of an environment variable, or part of it.",
+            ),
+            D::new::<Command>("Command", "Shows the first line of the output of a program."),
+            D::new::<ExitStatusSymbol>(
+                "ExitStatusSymbol",
+                "Shows a symbol styled according to the exit code of the previous command.",
+            ),
+            D::with_defaults::<Marker>(
+                "Marker",
+                "Shows the marker flagging the shell session, if any.",
+            ),
+            D::with_defaults::<Note>(
+                "Note",
+                "Shows a message left in a file by another program, once.",
+            ),
+            D::with_defaults::<Todo>(
+                "Todo",
+                "Shows the number of pending tasks from Taskwarrior or todo.txt.",
+            ),
+            D::with_defaults::<NowPlaying>(
+                "NowPlaying",
+                "Shows the track playing in MPD or another player.",
+            ),
+            D::with_defaults::<Or>("Or", "Shows the blocks of the first producer that produces any."),
+            D::with_defaults::<OrElse>(
+                "OrElse",
+                "Shows the blocks of the first producer that produces any, or of a placeholder.",
+            ),
+            D::new::<TakeFirst>(
+                "TakeFirst",
+                "Shows the blocks of the first producers that produce any, up to a number of them.",
+            ),
+            D::with_defaults::<Sequence>("Sequence", "Shows the blocks of all producers."),
+            D::new::<Separated>(
+                "Separated",
+                "Shows the blocks of all producers, with separators between non-empty producers.",
+            ),
+            D::new::<Styled>(
+                "Styled",
+                "Shows the blocks of a producer with a style applied to them.",
+            ),
+            D::new::<Sticky>(
+                "Sticky",
+                "Keeps showing the blocks last generated by a producer for some time.",
+            ),
+            D::new::<Cached>(
+                "Cached",
+                "Reuses the blocks previously produced by a producer until they expire.",
+            ),
+            D::new::<Timeout>(
+                "Timeout",
+                "Shows the blocks of a producer if it completes in time, or a placeholder otherwise.",
+            ),
+            D::new::<Group>(
+                "Group",
+                "Shows the blocks of a producer unless the group is hidden with `eliprompt toggle`.",
+            ),
+            D::new::<RightAlign>(
+                "RightAlign",
+                "Shows the blocks of a producer flush to the right edge of the terminal.",
+            ),
+            D::new::<Shrink>(
+                "Shrink",
+                "Shrinks or drops the least important producers to fit in the terminal width.",
+            ),
+            D::new::<Truncate>(
+                "Truncate",
+                "Shortens the blocks of a producer to fit in a maximum width.",
+            ),
+            D::new::<Hyperlink>(
+                "Hyperlink",
+                "Makes the blocks of a producer a link in terminals supporting hyperlinks.",
+            ),
+            D::new::<Transform>(
+                "Transform",
+                "Applies text transformations to the blocks of a producer.",
+            ),
+            D::new::<Surround>(
+                "Surround",
+                "Surrounds the blocks of a producer with text, e.g. brackets or padding.",
+            ),
+            D::new::<When>(
+                "When",
+                "Shows the blocks of a producer if a condition holds, or of another one otherwise.",
+            ),
+            D::new::<WhenTime>(
+                "WhenTime",
+                "Shows the blocks of a producer only at some times of day or days of the week.",
+            ),
+        ]
+    }
+}
+
+/// Returns the names of the fields of a struct, or of the variants of an enum, as known to its
+/// `Deserialize` implementation.
+fn names<T>() -> &'static [&'static str]
+where
+    T: DeserializeOwned,
+{
+    let names = Cell::new(&[][..]);
+    let _ = T::deserialize(NameRecorder(&names));
+    names.get()
+}
+
+/// Deserializer recording the names passed by `Deserialize` implementations and failing
+struct NameRecorder<'a>(&'a Cell<&'static [&'static str]>);
+
+impl<'de> Deserializer<'de> for NameRecorder<'_> {
+    type Error = de::value::Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, _: V) -> Result<V::Value, Self::Error> {
+        Err(de::Error::custom("Only names are recorded"))
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        self.0.set(fields);
+        self.deserialize_any(visitor)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        self.0.set(variants);
+        self.deserialize_any(visitor)
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf option
+        unit unit_struct newtype_struct seq tuple tuple_struct map identifier ignored_any
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::names;
+    use crate::BlockProducer;
+
+    #[test]
+    fn all_producers_are_described() {
+        let described = BlockProducer::descriptors()
+            .into_iter()
+            .map(|d| d.name)
+            .collect::<Vec<_>>();
+        assert_eq!(described, names::<BlockProducer>());
+        assert_eq!(names::<crate::block::Text>(), ["style", "contents"]);
+    }
+}
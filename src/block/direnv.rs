@@ -0,0 +1,246 @@
+// Copyright (C) 2020 Stephane Raux. Distributed under the MIT license.
+
+use crate::{symbol, Block, Environment, Style, Symbol};
+use serde::{Deserialize, Serialize};
+use std::{
+    env,
+    ffi::OsStr,
+    path::{Path, PathBuf},
+};
+
+/// Shows the status of the direnv `.envrc` file applying to the working directory
+///
+/// The status is `loaded`, `blocked` when the file must be allowed with `direnv allow`, e.g.
+/// after it changed, `denied` when it was denied with `direnv deny`, or `stale` when it is allowed
+/// but another file or none is loaded.
+///
+/// The loaded file is known from the variables exported by direnv, so `direnv status` only runs
+/// when the file applying to the working directory is not loaded.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Direnv {
+    #[serde(default)]
+    style: Style,
+    #[serde(default = "default_warning_style")]
+    warning_style: Style,
+    #[serde(default = "default_prefix")]
+    prefix: Symbol,
+}
+
+impl Direnv {
+    pub fn new() -> Self {
+        Direnv {
+            style: Default::default(),
+            warning_style: default_warning_style(),
+            prefix: default_prefix(),
+        }
+    }
+
+    pub fn with_style<T>(self, style: T) -> Self
+    where
+        T: Into<Style>,
+    {
+        Self {
+            style: style.into(),
+            ..self
+        }
+    }
+
+    /// Sets the style used when the `.envrc` file is not loaded.
+    pub fn with_warning_style<T>(self, style: T) -> Self
+    where
+        T: Into<Style>,
+    {
+        Self {
+            warning_style: style.into(),
+            ..self
+        }
+    }
+
+    pub fn with_prefix<T>(self, prefix: T) -> Self
+    where
+        T: Into<Symbol>,
+    {
+        Self {
+            prefix: prefix.into(),
+            ..self
+        }
+    }
+
+    pub fn produce(&self, environment: &Environment) -> Vec<Block> {
+        let Some(dir) = environment.working_dir() else {
+            return Vec::new();
+        };
+        let Some(found) = dir
+            .ancestors()
+            .map(|dir| dir.join(".envrc"))
+            .find(|path| path.is_file())
+        else {
+            return Vec::new();
+        };
+        let loaded = loaded_rc(
+            env::var_os("DIRENV_FILE").as_deref(),
+            env::var_os("DIRENV_DIR").as_deref(),
+        );
+        let state = if loaded.as_ref() == Some(&found) {
+            RcState::Loaded
+        } else {
+            match status(environment) {
+                Some(state) => state,
+                None => return Vec::new(),
+            }
+        };
+        let style = match state {
+            RcState::Loaded => &self.style,
+            RcState::Blocked | RcState::Denied | RcState::Stale => &self.warning_style,
+        };
+        vec![
+            Block::new(self.prefix.get(environment.symbols())).with_style(style),
+            Block::new(state.as_str()).with_style(style),
+        ]
+    }
+}
+
+impl Default for Direnv {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Returns the `.envrc` file loaded by direnv, from the `DIRENV_FILE` variable or the
+/// `DIRENV_DIR` variable of older versions, which holds its directory prefixed with `-`.
+fn loaded_rc(direnv_file: Option<&OsStr>, direnv_dir: Option<&OsStr>) -> Option<PathBuf> {
+    let file = direnv_file.filter(|f| !f.is_empty()).map(PathBuf::from);
+    file.or_else(|| {
+        let dir = direnv_dir?.to_str()?.strip_prefix('-')?;
+        (!dir.is_empty()).then(|| Path::new(dir).join(".envrc"))
+    })
+}
+
+/// Returns the state reported by `direnv status` for the working directory.
+fn status(environment: &Environment) -> Option<RcState> {
+    let direnv = crate::process::find_program("direnv")?;
+    let output = crate::process::output(
+        environment.command(direnv).args(["status", "--json"]),
+        &environment.cancellation(),
+    )?;
+    match serde_json::from_str::<Status>(&output) {
+        Ok(status) => status.state.rc_state(),
+        Err(e) => {
+            tracing::error!("Failed to parse direnv status: {}", e);
+            None
+        }
+    }
+}
+
+/// Output of `direnv status --json`
+#[derive(Debug, Deserialize)]
+struct Status {
+    state: State,
+}
+
+#[derive(Debug, Deserialize)]
+struct State {
+    #[serde(rename = "foundRC")]
+    found_rc: Option<Rc>,
+    #[serde(rename = "loadedRC")]
+    loaded_rc: Option<Rc>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Rc {
+    /// 0 if allowed, 1 if not allowed yet and 2 if denied
+    allowed: u8,
+    path: PathBuf,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum RcState {
+    Loaded,
+    Blocked,
+    Denied,
+    Stale,
+}
+
+impl RcState {
+    fn as_str(self) -> &'static str {
+        match self {
+            RcState::Loaded => "loaded",
+            RcState::Blocked => "blocked",
+            RcState::Denied => "denied",
+            RcState::Stale => "stale",
+        }
+    }
+}
+
+impl State {
+    /// Returns the state of the `.envrc` file applying to the working directory, if any.
+    fn rc_state(&self) -> Option<RcState> {
+        let found = self.found_rc.as_ref()?;
+        let state = match found.allowed {
+            0 if self
+                .loaded_rc
+                .as_ref()
+                .is_some_and(|rc| rc.path == found.path) =>
+            {
+                RcState::Loaded
+            }
+            0 => RcState::Stale,
+            2 => RcState::Denied,
+            _ => RcState::Blocked,
+        };
+        Some(state)
+    }
+}
+
+fn default_warning_style() -> Style {
+    Style::new().with_fg(crate::color::GOLD)
+}
+
+fn default_prefix() -> Symbol {
+    symbol::DIRENV
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{loaded_rc, RcState, Status};
+    use std::{ffi::OsStr, path::Path};
+
+    fn rc_state(json: &str) -> Option<RcState> {
+        serde_json::from_str::<Status>(json)
+            .unwrap()
+            .state
+            .rc_state()
+    }
+
+    #[test]
+    fn state_is_read_from_status() {
+        let found = r#"{"allowed": 0, "path": "/p/.envrc"}"#;
+        let blocked = r#"{"allowed": 1, "path": "/p/.envrc"}"#;
+        let status = |found: &str, loaded: &str| {
+            format!(
+                r#"{{"config": {{}}, "state": {{"foundRC": {}, "loadedRC": {}}}}}"#,
+                found, loaded
+            )
+        };
+        assert_eq!(rc_state(&status(found, found)), Some(RcState::Loaded));
+        assert_eq!(rc_state(&status(found, "null")), Some(RcState::Stale));
+        assert_eq!(rc_state(&status(blocked, found)), Some(RcState::Blocked));
+        assert_eq!(rc_state(&status("null", found)), None);
+    }
+
+    #[test]
+    fn loaded_file_is_read_from_variables() {
+        let file = Some(OsStr::new("/p/.envrc"));
+        let dir = Some(OsStr::new("-/q"));
+        assert_eq!(
+            loaded_rc(file, dir).as_deref(),
+            Some(Path::new("/p/.envrc"))
+        );
+        assert_eq!(
+            loaded_rc(None, dir).as_deref(),
+            Some(Path::new("/q/.envrc"))
+        );
+        assert_eq!(loaded_rc(Some(OsStr::new("")), None), None);
+        assert_eq!(loaded_rc(None, Some(OsStr::new("/q"))), None);
+    }
+}
@@ -1,6 +1,6 @@
 // Copyright (C) 2020 Stephane Raux. Distributed under the MIT license.
 
-use crate::{Block, Environment, Style};
+use crate::{symbol, Block, Environment, Style, Symbol};
 use serde::{Deserialize, Serialize};
 use std::time::Duration;
 
@@ -9,9 +9,13 @@ pub struct Elapsed {
     #[serde(default)]
     style: Style,
     #[serde(default = "default_prefix")]
-    prefix: String,
+    prefix: Symbol,
     #[serde(with = "humantime_serde", default = "default_threshold")]
     threshold: Duration,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    units: Option<DurationUnits>,
+    #[serde(default)]
+    exclude_suspended: bool,
 }
 
 impl Elapsed {
@@ -20,6 +24,8 @@ impl Elapsed {
             style: Default::default(),
             prefix: default_prefix(),
             threshold: default_threshold(),
+            units: None,
+            exclude_suspended: false,
         }
     }
 
@@ -35,7 +41,7 @@ impl Elapsed {
 
     pub fn with_prefix<T>(self, prefix: T) -> Self
     where
-        T: Into<String>,
+        T: Into<Symbol>,
     {
         Self {
             prefix: prefix.into(),
@@ -43,14 +49,37 @@ impl Elapsed {
         }
     }
 
+    pub fn with_units(self, units: DurationUnits) -> Self {
+        Self {
+            units: Some(units),
+            ..self
+        }
+    }
+
+    /// Excludes time the command was suspended, e.g. after Ctrl-Z, from the duration.
+    pub fn exclude_suspended(self, yes: bool) -> Self {
+        Self {
+            exclude_suspended: yes,
+            ..self
+        }
+    }
+
     pub fn produce(&self, environment: &Environment) -> Vec<Block> {
-        match environment.prev_cmd_duration() {
+        let duration = if self.exclude_suspended {
+            environment.prev_cmd_active_duration()
+        } else {
+            environment.prev_cmd_duration()
+        };
+        match duration {
             Some(elapsed) if elapsed >= self.threshold => {
                 let elapsed = Duration::from_secs(elapsed.as_secs())
                     + Duration::from_millis(elapsed.subsec_millis() as u64);
-                let elapsed = humantime::format_duration(elapsed).to_string();
+                let elapsed = match &self.units {
+                    Some(units) => units.format(elapsed),
+                    None => humantime::format_duration(elapsed).to_string(),
+                };
                 vec![
-                    Block::new(&self.prefix).with_style(&self.style),
+                    Block::new(self.prefix.get(environment.symbols())).with_style(&self.style),
                     Block::new(elapsed).with_style(&self.style),
                 ]
             }
@@ -69,10 +98,175 @@ impl Default for Elapsed {
     }
 }
 
-fn default_prefix() -> String {
-    "\u{fa1a}".into()
+fn default_prefix() -> Symbol {
+    symbol::ELAPSED
 }
 
 fn default_threshold() -> Duration {
     Duration::from_secs(2)
 }
+
+/// Words used to format a duration
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct DurationUnits {
+    #[serde(default = "default_days")]
+    days: UnitName,
+    #[serde(default = "default_hours")]
+    hours: UnitName,
+    #[serde(default = "default_minutes")]
+    minutes: UnitName,
+    #[serde(default = "default_seconds")]
+    seconds: UnitName,
+    #[serde(default = "default_milliseconds")]
+    milliseconds: UnitName,
+    #[serde(default = "default_unit_separator")]
+    separator: String,
+}
+
+impl DurationUnits {
+    pub fn new() -> Self {
+        DurationUnits {
+            days: default_days(),
+            hours: default_hours(),
+            minutes: default_minutes(),
+            seconds: default_seconds(),
+            milliseconds: default_milliseconds(),
+            separator: default_unit_separator(),
+        }
+    }
+
+    pub fn with_days(self, days: UnitName) -> Self {
+        Self { days, ..self }
+    }
+
+    pub fn with_hours(self, hours: UnitName) -> Self {
+        Self { hours, ..self }
+    }
+
+    pub fn with_minutes(self, minutes: UnitName) -> Self {
+        Self { minutes, ..self }
+    }
+
+    pub fn with_seconds(self, seconds: UnitName) -> Self {
+        Self { seconds, ..self }
+    }
+
+    pub fn with_milliseconds(self, milliseconds: UnitName) -> Self {
+        Self {
+            milliseconds,
+            ..self
+        }
+    }
+
+    pub fn with_separator<T>(self, separator: T) -> Self
+    where
+        T: Into<String>,
+    {
+        Self {
+            separator: separator.into(),
+            ..self
+        }
+    }
+
+    /// Formats a duration with a millisecond precision, omitting units whose count is zero.
+    pub fn format(&self, d: Duration) -> String {
+        let units = [
+            (&self.days, 86_400_000),
+            (&self.hours, 3_600_000),
+            (&self.minutes, 60_000),
+            (&self.seconds, 1_000),
+            (&self.milliseconds, 1),
+        ];
+        let (parts, _) = units.iter().fold(
+            (Vec::new(), d.as_millis()),
+            |(mut parts, remaining), &(unit, millis)| {
+                let n = remaining / millis;
+                if n > 0 {
+                    parts.push(format!("{}{}", n, unit.get(n)));
+                }
+                (parts, remaining % millis)
+            },
+        );
+        if parts.is_empty() {
+            format!("0{}", self.seconds.get(0))
+        } else {
+            parts.join(&self.separator)
+        }
+    }
+}
+
+impl Default for DurationUnits {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Name of a duration unit, possibly depending on the count
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum UnitName {
+    Invariable(String),
+    Plural { one: String, other: String },
+}
+
+impl UnitName {
+    pub fn get(&self, n: u128) -> &str {
+        match self {
+            UnitName::Invariable(s) => s,
+            UnitName::Plural { one, .. } if n == 1 => one,
+            UnitName::Plural { other, .. } => other,
+        }
+    }
+}
+
+impl From<&str> for UnitName {
+    fn from(s: &str) -> Self {
+        UnitName::Invariable(s.into())
+    }
+}
+
+fn default_days() -> UnitName {
+    "d".into()
+}
+
+fn default_hours() -> UnitName {
+    "h".into()
+}
+
+fn default_minutes() -> UnitName {
+    "m".into()
+}
+
+fn default_seconds() -> UnitName {
+    "s".into()
+}
+
+fn default_milliseconds() -> UnitName {
+    "ms".into()
+}
+
+fn default_unit_separator() -> String {
+    " ".into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DurationUnits, UnitName};
+    use std::time::Duration;
+
+    #[test]
+    fn units_use_plural_form_depending_on_count() {
+        let units = DurationUnits::new()
+            .with_minutes(UnitName::Plural {
+                one: " minute".into(),
+                other: " minutes".into(),
+            })
+            .with_seconds(" s".into());
+        assert_eq!(units.format(Duration::from_secs(61)), "1 minute 1 s");
+        assert_eq!(
+            units.format(Duration::from_millis(120_030)),
+            "2 minutes 30ms"
+        );
+        assert_eq!(units.format(Duration::ZERO), "0 s");
+    }
+}
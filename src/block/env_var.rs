@@ -0,0 +1,103 @@
+// Copyright (C) 2020 Stephane Raux. Distributed under the MIT license.
+
+use crate::{Block, Environment, Pattern, Style, Symbol};
+use serde::{Deserialize, Serialize};
+use std::env;
+
+/// Shows the value of an environment variable, or part of it
+///
+/// Nothing is shown if the variable is unset or empty, or if its value does not match the
+/// pattern.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct EnvVar {
+    name: String,
+    #[serde(default)]
+    style: Style,
+    #[serde(default)]
+    prefix: Symbol,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pattern: Option<Pattern>,
+}
+
+impl EnvVar {
+    pub fn new<T>(name: T) -> Self
+    where
+        T: Into<String>,
+    {
+        EnvVar {
+            name: name.into(),
+            style: Default::default(),
+            prefix: Symbol::default(),
+            pattern: None,
+        }
+    }
+
+    pub fn with_style<T>(self, style: T) -> Self
+    where
+        T: Into<Style>,
+    {
+        Self {
+            style: style.into(),
+            ..self
+        }
+    }
+
+    pub fn with_prefix<T>(self, prefix: T) -> Self
+    where
+        T: Into<Symbol>,
+    {
+        Self {
+            prefix: prefix.into(),
+            ..self
+        }
+    }
+
+    /// Only shows the part of the value matched by the first capture group of `pattern`, or by
+    /// the whole pattern if it has no groups.
+    pub fn with_pattern(self, pattern: Pattern) -> Self {
+        Self {
+            pattern: Some(pattern),
+            ..self
+        }
+    }
+
+    pub fn produce(&self, environment: &Environment) -> Vec<Block> {
+        let Some(value) = env::var_os(&self.name) else {
+            return Vec::new();
+        };
+        let value = value.to_string_lossy();
+        let Some(value) = self.extract(&value).filter(|v| !v.is_empty()) else {
+            return Vec::new();
+        };
+        vec![
+            Block::new(self.prefix.get(environment.symbols())).with_style(&self.style),
+            Block::new(value).with_style(&self.style),
+        ]
+    }
+
+    fn extract<'a>(&self, value: &'a str) -> Option<&'a str> {
+        let Some(pattern) = &self.pattern else {
+            return Some(value);
+        };
+        let captures = pattern.captures(value)?;
+        captures
+            .get(1)
+            .or_else(|| captures.get(0))
+            .map(|m| m.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::EnvVar;
+    use crate::Pattern;
+
+    #[test]
+    fn value_is_extracted_with_pattern() {
+        let var = EnvVar::new("AWS_PROFILE");
+        assert_eq!(var.extract("prod-admin"), Some("prod-admin"));
+        let var = var.with_pattern(Pattern::new(r"^(\w+)-").unwrap());
+        assert_eq!(var.extract("prod-admin"), Some("prod"));
+        assert_eq!(var.extract("prod"), None);
+    }
+}
@@ -1,6 +1,6 @@
 // Copyright (C) 2020 Stephane Raux. Distributed under the MIT license.
 
-use crate::{Block, Environment, Style};
+use crate::{symbol, Block, Environment, Style, Symbol, Template};
 use serde::{Deserialize, Serialize};
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -8,7 +8,11 @@ pub struct ExitCode {
     #[serde(default)]
     style: Style,
     #[serde(default = "default_prefix")]
-    prefix: String,
+    prefix: Symbol,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    format: Option<Template>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    ignore_codes: Vec<i32>,
 }
 
 impl ExitCode {
@@ -16,6 +20,8 @@ impl ExitCode {
         ExitCode {
             style: Default::default(),
             prefix: default_prefix(),
+            format: None,
+            ignore_codes: Vec::new(),
         }
     }
 
@@ -31,7 +37,7 @@ impl ExitCode {
 
     pub fn with_prefix<T>(self, prefix: T) -> Self
     where
-        T: Into<String>,
+        T: Into<Symbol>,
     {
         Self {
             prefix: prefix.into(),
@@ -39,13 +45,36 @@ impl ExitCode {
         }
     }
 
+    pub fn with_format(self, format: Template) -> Self {
+        Self {
+            format: Some(format),
+            ..self
+        }
+    }
+
+    /// Treats the exit codes in `codes` like success, e.g. 130 after Ctrl-C.
+    pub fn with_ignore_codes<I>(self, codes: I) -> Self
+    where
+        I: IntoIterator<Item = i32>,
+    {
+        Self {
+            ignore_codes: codes.into_iter().collect(),
+            ..self
+        }
+    }
+
     pub fn produce(&self, environment: &Environment) -> Vec<Block> {
         match environment.prev_exit_code() {
-            0 => Vec::new(),
-            code => vec![
-                Block::new(&self.prefix).with_style(&self.style),
-                Block::new(code.to_string()).with_style(&self.style),
-            ],
+            code if code == 0 || self.ignore_codes.contains(&code) => Vec::new(),
+            code => match &self.format {
+                Some(format) => {
+                    vec![Block::new(format.render(&code.to_string())).with_style(&self.style)]
+                }
+                None => vec![
+                    Block::new(self.prefix.get(environment.symbols())).with_style(&self.style),
+                    Block::new(code.to_string()).with_style(&self.style),
+                ],
+            },
         }
     }
 }
@@ -56,6 +85,28 @@ impl Default for ExitCode {
     }
 }
 
-fn default_prefix() -> String {
-    "\u{f071}".into()
+fn default_prefix() -> Symbol {
+    symbol::EXIT_CODE
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ExitCode;
+    use crate::Environment;
+
+    #[test]
+    fn ignored_codes_are_treated_like_success() {
+        let exit_code = ExitCode::new().with_prefix("").with_ignore_codes([130]);
+        let texts = |code| {
+            let environment = Environment::new(None).with_prev_exit_code(code);
+            exit_code
+                .produce(&environment)
+                .into_iter()
+                .map(|b| b.text)
+                .collect::<Vec<_>>()
+        };
+        assert!(texts(0).is_empty());
+        assert!(texts(130).is_empty());
+        assert_eq!(texts(1), ["", "1"]);
+    }
 }
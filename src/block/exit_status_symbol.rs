@@ -1,6 +1,6 @@
 // Copyright (C) 2020 Stephane Raux. Distributed under the MIT license.
 
-use crate::{Block, Environment, Style};
+use crate::{Block, Environment, Style, Symbol};
 use serde::{Deserialize, Serialize};
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -9,18 +9,27 @@ pub struct ExitStatusSymbol {
     style: Style,
     #[serde(default)]
     error_style: Style,
-    contents: String,
+    contents: Symbol,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    ignore_codes: Vec<i32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    root_contents: Option<Symbol>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    root_style: Option<Style>,
 }
 
 impl ExitStatusSymbol {
     pub fn new<T>(contents: T) -> Self
     where
-        T: Into<String>,
+        T: Into<Symbol>,
     {
         ExitStatusSymbol {
             style: Default::default(),
             error_style: Default::default(),
             contents: contents.into(),
+            ignore_codes: Vec::new(),
+            root_contents: None,
+            root_style: None,
         }
     }
 
@@ -44,16 +53,107 @@ impl ExitStatusSymbol {
         }
     }
 
+    /// Treats the exit codes in `codes` like success, e.g. 130 after Ctrl-C.
+    pub fn with_ignore_codes<I>(self, codes: I) -> Self
+    where
+        I: IntoIterator<Item = i32>,
+    {
+        Self {
+            ignore_codes: codes.into_iter().collect(),
+            ..self
+        }
+    }
+
+    /// Shows `contents` instead when running as root, e.g. `#`.
+    pub fn with_root_contents<T>(self, contents: T) -> Self
+    where
+        T: Into<Symbol>,
+    {
+        Self {
+            root_contents: Some(contents.into()),
+            ..self
+        }
+    }
+
+    /// Uses `style` instead of the success style when running as root. The error style still
+    /// applies after a failure.
+    pub fn with_root_style<T>(self, style: T) -> Self
+    where
+        T: Into<Style>,
+    {
+        Self {
+            root_style: Some(style.into()),
+            ..self
+        }
+    }
+
     pub fn produce(&self, environment: &Environment) -> Vec<Block> {
-        let style = if environment.prev_exit_code() == 0 {
-            &self.style
+        let code = environment.prev_exit_code();
+        let is_root =
+            (self.root_contents.is_some() || self.root_style.is_some()) && environment.is_root();
+        let style = if code == 0 || self.ignore_codes.contains(&code) {
+            self.root_style
+                .as_ref()
+                .filter(|_| is_root)
+                .unwrap_or(&self.style)
         } else {
             &self.error_style
         };
-        if self.contents.is_empty() {
+        let contents = self
+            .root_contents
+            .as_ref()
+            .filter(|_| is_root)
+            .unwrap_or(&self.contents)
+            .get(environment.symbols());
+        if contents.is_empty() {
             Vec::new()
         } else {
-            vec![Block::new(&self.contents).with_style(style)]
+            vec![Block::new(contents).with_style(style)]
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::ExitStatusSymbol;
+    use crate::{color, symbol, Environment, Style, SymbolSet};
+
+    #[test]
+    fn ignored_codes_use_the_success_style() {
+        let error_style = Style::new().with_fg(color::CRIMSON);
+        let symbol = ExitStatusSymbol::new("$")
+            .with_error_style(error_style.clone())
+            .with_ignore_codes([130]);
+        let style = |code| {
+            let environment = Environment::new(None).with_prev_exit_code(code);
+            symbol.produce(&environment)[0].style.clone()
+        };
+        assert_eq!(style(130), Style::new());
+        assert_eq!(style(1), error_style);
+    }
+
+    #[test]
+    fn root_options_apply_as_root() {
+        let root_style = Style::new().with_fg(color::CRIMSON);
+        let symbol = ExitStatusSymbol::new("$")
+            .with_root_contents("#")
+            .with_root_style(root_style.clone());
+        let block = |root| {
+            let environment = Environment::new(None).force_root(root);
+            symbol.produce(&environment).remove(0)
+        };
+        let root = block(true);
+        assert_eq!((root.text.as_str(), root.style), ("#", root_style));
+        let user = block(false);
+        assert_eq!((user.text.as_str(), user.style), ("$", Style::new()));
+    }
+
+    #[test]
+    fn contents_follow_the_symbol_set() {
+        let symbol = ExitStatusSymbol::new(symbol::PROMPT);
+        let environment = Environment::new(None);
+        let text = || symbol.produce(&environment).remove(0).text;
+        assert_eq!(text(), "→");
+        assert_eq!(SymbolSet::Ascii.scope(text), ">");
+    }
+}
@@ -0,0 +1,62 @@
+// Copyright (C) 2020 Stephane Raux. Distributed under the MIT license.
+
+use crate::{block::BlockKind, symbol, Block, Environment, Style, Symbol};
+use serde::{Deserialize, Serialize};
+
+/// Repeats text to fill the rest of the line up to the terminal width, e.g. to draw a rule
+///
+/// Fill blocks on the same line share the free columns. Nothing is shown if the terminal width
+/// is unknown.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Fill {
+    #[serde(default)]
+    style: Style,
+    #[serde(default = "default_contents")]
+    contents: Symbol,
+}
+
+impl Fill {
+    pub fn new() -> Self {
+        Fill {
+            style: Default::default(),
+            contents: default_contents(),
+        }
+    }
+
+    pub fn with_style<T>(self, style: T) -> Self
+    where
+        T: Into<Style>,
+    {
+        Self {
+            style: style.into(),
+            ..self
+        }
+    }
+
+    /// Sets the text to repeat.
+    pub fn with_contents<T>(self, contents: T) -> Self
+    where
+        T: Into<Symbol>,
+    {
+        Self {
+            contents: contents.into(),
+            ..self
+        }
+    }
+
+    pub fn produce(&self, environment: &Environment) -> Vec<Block> {
+        vec![Block::new(self.contents.get(environment.symbols()))
+            .with_style(&self.style)
+            .with_kind(BlockKind::Fill)]
+    }
+}
+
+impl Default for Fill {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn default_contents() -> Symbol {
+    symbol::RULE
+}
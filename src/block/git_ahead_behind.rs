@@ -0,0 +1,195 @@
+// Copyright (C) 2020 Stephane Raux. Distributed under the MIT license.
+
+use crate::{symbol, Block, Environment, Style, Symbol};
+use git2::Branch;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct GitAheadBehind {
+    #[serde(default)]
+    ahead_style: Style,
+    #[serde(default = "default_ahead_symbol")]
+    ahead_symbol: Symbol,
+    #[serde(default)]
+    behind_style: Style,
+    #[serde(default = "default_behind_symbol")]
+    behind_symbol: Symbol,
+}
+
+impl GitAheadBehind {
+    pub fn new() -> Self {
+        GitAheadBehind {
+            ahead_style: Default::default(),
+            ahead_symbol: default_ahead_symbol(),
+            behind_style: Default::default(),
+            behind_symbol: default_behind_symbol(),
+        }
+    }
+
+    pub fn with_ahead_style<T>(self, style: T) -> Self
+    where
+        T: Into<Style>,
+    {
+        Self {
+            ahead_style: style.into(),
+            ..self
+        }
+    }
+
+    pub fn with_ahead_symbol<T>(self, symbol: T) -> Self
+    where
+        T: Into<Symbol>,
+    {
+        Self {
+            ahead_symbol: symbol.into(),
+            ..self
+        }
+    }
+
+    pub fn with_behind_style<T>(self, style: T) -> Self
+    where
+        T: Into<Style>,
+    {
+        Self {
+            behind_style: style.into(),
+            ..self
+        }
+    }
+
+    pub fn with_behind_symbol<T>(self, symbol: T) -> Self
+    where
+        T: Into<Symbol>,
+    {
+        Self {
+            behind_symbol: symbol.into(),
+            ..self
+        }
+    }
+
+    pub fn produce(&self, environment: &Environment) -> Vec<Block> {
+        let repo = match environment.repo() {
+            Some(repo) => repo,
+            None => return Vec::new(),
+        };
+        let head = match repo.head() {
+            Ok(head) if head.is_branch() => head,
+            Ok(_) => return Vec::new(),
+            Err(e) if e.code() == git2::ErrorCode::UnbornBranch => return Vec::new(),
+            Err(e) => {
+                tracing::error!("Failed to get git repository HEAD: {}", e);
+                return Vec::new();
+            }
+        };
+        let branch = Branch::wrap(head);
+        let upstream = match branch.upstream() {
+            Ok(upstream) => upstream,
+            Err(e) if e.code() == git2::ErrorCode::NotFound => return Vec::new(),
+            Err(e) => {
+                tracing::error!("Failed to get upstream branch: {}", e);
+                return Vec::new();
+            }
+        };
+        let (Some(local), Some(remote)) = (branch.get().target(), upstream.get().target()) else {
+            return Vec::new();
+        };
+        let compute = || repo.graph_ahead_behind(local, remote);
+        let counts = match environment.git_cache() {
+            Some(mut cache) => cache.get_or_try_insert_with(
+                "ahead_behind",
+                &format!("{}..{}", local, remote),
+                compute,
+            ),
+            None => compute(),
+        };
+        let (ahead, behind) = match counts {
+            Ok(counts) => counts,
+            Err(e) => {
+                tracing::error!("Failed to compare HEAD with its upstream: {}", e);
+                return Vec::new();
+            }
+        };
+        let mut blocks = Vec::new();
+        if ahead > 0 {
+            blocks.push(
+                Block::new(self.ahead_symbol.get(environment.symbols()))
+                    .with_style(&self.ahead_style),
+            );
+            blocks.push(Block::new(ahead.to_string()).with_style(&self.ahead_style));
+        }
+        if behind > 0 {
+            if !blocks.is_empty() {
+                blocks.push(Block::new(" "));
+            }
+            blocks.push(
+                Block::new(self.behind_symbol.get(environment.symbols()))
+                    .with_style(&self.behind_style),
+            );
+            blocks.push(Block::new(behind.to_string()).with_style(&self.behind_style));
+        }
+        // The upstream is only as recent as the last fetch.
+        environment.annotate_local_only(blocks)
+    }
+}
+
+impl Default for GitAheadBehind {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn default_ahead_symbol() -> Symbol {
+    symbol::GIT_AHEAD
+}
+
+fn default_behind_symbol() -> Symbol {
+    symbol::GIT_BEHIND
+}
+
+#[cfg(test)]
+mod tests {
+    use super::GitAheadBehind;
+    use crate::{git::fixture, Environment};
+    use git2::{Branch, Signature};
+
+    #[test]
+    fn commits_are_compared_with_upstream() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = fixture::init(dir.path());
+        let base = fixture::commit_file(&repo, "a", "1");
+        let producer = GitAheadBehind::new()
+            .with_ahead_symbol("+")
+            .with_behind_symbol("-");
+        let texts = || {
+            let environment = Environment::new(Some(dir.path().to_owned()));
+            producer
+                .produce(&environment)
+                .into_iter()
+                .map(|block| block.text)
+                .collect::<Vec<_>>()
+        };
+        assert!(texts().is_empty());
+        repo.remote("origin", "https://example.com/repo.git")
+            .unwrap();
+        let upstream = "refs/remotes/origin/main";
+        repo.reference(upstream, base, true, "").unwrap();
+        Branch::wrap(repo.head().unwrap())
+            .set_upstream(Some("origin/main"))
+            .unwrap();
+        assert!(texts().is_empty());
+        fixture::commit_file(&repo, "a", "2");
+        fixture::commit_file(&repo, "a", "3");
+        assert_eq!(texts(), ["+", "2"]);
+        let signature = Signature::now("Test", "test@example.com").unwrap();
+        let base = repo.find_commit(base).unwrap();
+        repo.commit(
+            Some(upstream),
+            &signature,
+            &signature,
+            "Remote",
+            &base.tree().unwrap(),
+            &[&base],
+        )
+        .unwrap();
+        assert_eq!(texts(), ["+", "2", " ", "-", "1"]);
+    }
+}
@@ -0,0 +1,145 @@
+// Copyright (C) 2020 Stephane Raux. Distributed under the MIT license.
+
+use crate::{symbol, Block, Environment, Style, Symbol};
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct GitCommitAge {
+    #[serde(default)]
+    style: Style,
+    #[serde(default = "default_prefix")]
+    prefix: Symbol,
+    #[serde(default = "default_suffix")]
+    suffix: String,
+    #[serde(with = "humantime_serde", default = "default_threshold")]
+    threshold: Duration,
+}
+
+impl GitCommitAge {
+    pub fn new() -> Self {
+        GitCommitAge {
+            style: Default::default(),
+            prefix: default_prefix(),
+            suffix: default_suffix(),
+            threshold: default_threshold(),
+        }
+    }
+
+    pub fn with_style<T>(self, style: T) -> Self
+    where
+        T: Into<Style>,
+    {
+        Self {
+            style: style.into(),
+            ..self
+        }
+    }
+
+    pub fn with_prefix<T>(self, prefix: T) -> Self
+    where
+        T: Into<Symbol>,
+    {
+        Self {
+            prefix: prefix.into(),
+            ..self
+        }
+    }
+
+    pub fn with_suffix<T>(self, suffix: T) -> Self
+    where
+        T: Into<String>,
+    {
+        Self {
+            suffix: suffix.into(),
+            ..self
+        }
+    }
+
+    pub fn with_threshold(self, threshold: Duration) -> Self {
+        Self { threshold, ..self }
+    }
+
+    pub fn produce(&self, environment: &Environment) -> Vec<Block> {
+        let repo = match environment.git() {
+            Some(repo) => repo,
+            None => return Vec::new(),
+        };
+        let seconds = match repo.head_commit_time() {
+            Ok(Some(seconds)) => seconds,
+            Ok(None) => return Vec::new(),
+            Err(e) => {
+                tracing::error!("Failed to get git HEAD commit: {}", e);
+                return Vec::new();
+            }
+        };
+        let Ok(seconds) = u64::try_from(seconds) else {
+            return Vec::new();
+        };
+        let committed_at = UNIX_EPOCH + Duration::from_secs(seconds);
+        let age = match SystemTime::now().duration_since(committed_at) {
+            Ok(age) if age >= self.threshold => age,
+            _ => return Vec::new(),
+        };
+        let age = format_age(age);
+        vec![
+            Block::new(self.prefix.get(environment.symbols())).with_style(&self.style),
+            Block::new(age).with_style(&self.style),
+            Block::new(&self.suffix).with_style(&self.style),
+        ]
+    }
+}
+
+impl Default for GitCommitAge {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn default_prefix() -> Symbol {
+    symbol::GIT_COMMIT_AGE
+}
+
+fn default_suffix() -> String {
+    " ago".into()
+}
+
+fn default_threshold() -> Duration {
+    Duration::from_secs(24 * 60 * 60)
+}
+
+/// Formats `age` in its most significant unit, rounded down, e.g. `3h` or `2mo`.
+fn format_age(age: Duration) -> String {
+    const UNITS: [(u64, &str); 6] = [
+        (365 * 86_400, "y"),
+        (30 * 86_400, "mo"),
+        (7 * 86_400, "w"),
+        (86_400, "d"),
+        (3_600, "h"),
+        (60, "m"),
+    ];
+    let seconds = age.as_secs();
+    let (unit, name) = UNITS
+        .into_iter()
+        .find(|&(unit, _)| seconds >= unit)
+        .unwrap_or((1, "s"));
+    format!("{}{}", seconds / unit, name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::format_age;
+    use std::time::Duration;
+
+    #[test]
+    fn age_is_shown_in_its_most_significant_unit() {
+        let age = |seconds| format_age(Duration::from_secs(seconds));
+        assert_eq!(age(0), "0s");
+        assert_eq!(age(59), "59s");
+        assert_eq!(age(2 * 3_600 + 59 * 60), "2h");
+        assert_eq!(age(3 * 86_400 + 5 * 3_600), "3d");
+        assert_eq!(age(15 * 86_400), "2w");
+        assert_eq!(age(45 * 86_400 + 13 * 3_600), "1mo");
+        assert_eq!(age(800 * 86_400), "2y");
+    }
+}
@@ -0,0 +1,113 @@
+// Copyright (C) 2020 Stephane Raux. Distributed under the MIT license.
+
+use crate::{symbol, Block, Environment, Style, Symbol};
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct GitConflicts {
+    #[serde(default)]
+    style: Style,
+    #[serde(default = "default_prefix")]
+    prefix: Symbol,
+}
+
+impl GitConflicts {
+    pub fn new() -> Self {
+        GitConflicts {
+            style: Default::default(),
+            prefix: default_prefix(),
+        }
+    }
+
+    pub fn with_style<T>(self, style: T) -> Self
+    where
+        T: Into<Style>,
+    {
+        Self {
+            style: style.into(),
+            ..self
+        }
+    }
+
+    pub fn with_prefix<T>(self, prefix: T) -> Self
+    where
+        T: Into<Symbol>,
+    {
+        Self {
+            prefix: prefix.into(),
+            ..self
+        }
+    }
+
+    pub fn produce(&self, environment: &Environment) -> Vec<Block> {
+        let repo = match environment.git() {
+            Some(repo) => repo,
+            None => return Vec::new(),
+        };
+        let compute = || repo.conflict_count();
+        let count = match environment.git_cache() {
+            Some(mut cache) => cache.get_or_try_insert_with("conflicts", "", compute),
+            None => compute(),
+        };
+        let count = match count {
+            Ok(0) => return Vec::new(),
+            Ok(count) => count,
+            Err(e) => {
+                tracing::error!("Failed to list git conflicts: {}", e);
+                return Vec::new();
+            }
+        };
+        vec![
+            Block::new(self.prefix.get(environment.symbols())).with_style(&self.style),
+            Block::new(count.to_string()).with_style(&self.style),
+        ]
+    }
+}
+
+impl Default for GitConflicts {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn default_prefix() -> Symbol {
+    symbol::GIT_CONFLICTS
+}
+
+#[cfg(test)]
+mod tests {
+    use super::GitConflicts;
+    use crate::{git::fixture, Environment};
+    use git2::{IndexEntry, IndexTime};
+
+    #[test]
+    fn conflicted_paths_are_counted_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = fixture::init(dir.path());
+        let conflicts = GitConflicts::new().with_prefix("");
+        let environment = || Environment::new(Some(dir.path().to_owned()));
+        assert!(conflicts.produce(&environment()).is_empty());
+        let blob = repo.blob(b"contents").unwrap();
+        let mut index = repo.index().unwrap();
+        for (path, stage) in [("a", 1), ("a", 2), ("a", 3), ("b", 2), ("b", 3)] {
+            index
+                .add(&IndexEntry {
+                    ctime: IndexTime::new(0, 0),
+                    mtime: IndexTime::new(0, 0),
+                    dev: 0,
+                    ino: 0,
+                    mode: 0o100644,
+                    uid: 0,
+                    gid: 0,
+                    file_size: 0,
+                    id: blob,
+                    flags: stage << 12,
+                    flags_extended: 0,
+                    path: path.into(),
+                })
+                .unwrap();
+        }
+        index.write().unwrap();
+        assert_eq!(conflicts.produce(&environment())[1].text, "2");
+    }
+}
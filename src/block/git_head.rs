@@ -1,6 +1,6 @@
 // Copyright (C) 2020 Stephane Raux. Distributed under the MIT license.
 
-use crate::{Block, Environment, Style};
+use crate::{symbol, Block, Environment, Head, Style, Symbol, Template};
 use serde::{Deserialize, Serialize};
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -8,7 +8,13 @@ pub struct GitHead {
     #[serde(default)]
     style: Style,
     #[serde(default = "default_prefix")]
-    prefix: String,
+    prefix: Symbol,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    format: Option<Template>,
+    #[serde(default)]
+    detached_style: Style,
+    #[serde(default = "default_hash_length")]
+    hash_length: usize,
 }
 
 impl GitHead {
@@ -16,6 +22,9 @@ impl GitHead {
         GitHead {
             style: Default::default(),
             prefix: default_prefix(),
+            format: None,
+            detached_style: Default::default(),
+            hash_length: default_hash_length(),
         }
     }
 
@@ -31,7 +40,7 @@ impl GitHead {
 
     pub fn with_prefix<T>(self, prefix: T) -> Self
     where
-        T: Into<String>,
+        T: Into<Symbol>,
     {
         Self {
             prefix: prefix.into(),
@@ -39,27 +48,53 @@ impl GitHead {
         }
     }
 
+    pub fn with_detached_style<T>(self, style: T) -> Self
+    where
+        T: Into<Style>,
+    {
+        Self {
+            detached_style: style.into(),
+            ..self
+        }
+    }
+
+    pub fn with_hash_length(self, hash_length: usize) -> Self {
+        Self {
+            hash_length,
+            ..self
+        }
+    }
+
+    pub fn with_format(self, format: Template) -> Self {
+        Self {
+            format: Some(format),
+            ..self
+        }
+    }
+
     pub fn produce(&self, environment: &Environment) -> Vec<Block> {
-        let repo = match environment.repo() {
+        let repo = match environment.git() {
             Some(repo) => repo,
             None => return Vec::new(),
         };
-        let head = repo.head();
-        let name = match head {
-            Ok(ref head) => match head.shorthand() {
-                Some(s) => s,
-                None => return Vec::new(),
-            },
-            Err(e) if e.code() == git2::ErrorCode::UnbornBranch => "master",
+        let (name, style) = match repo.head() {
+            Ok(Head::Detached(mut hash)) => {
+                hash.truncate(self.hash_length);
+                (hash, self.detached_style.or(&self.style))
+            }
+            Ok(Head::Branch { name, .. }) => (name, self.style.clone()),
             Err(e) => {
                 tracing::error!("Failed to get git repository HEAD: {}", e);
                 return Vec::new();
             }
         };
-        vec![
-            Block::new(&self.prefix).with_style(&self.style),
-            Block::new(name).with_style(&self.style),
-        ]
+        match &self.format {
+            Some(format) => vec![Block::new(format.render(&name)).with_style(style)],
+            None => vec![
+                Block::new(self.prefix.get(environment.symbols())).with_style(&style),
+                Block::new(name).with_style(style),
+            ],
+        }
     }
 }
 
@@ -69,6 +104,10 @@ impl Default for GitHead {
     }
 }
 
-fn default_prefix() -> String {
-    "\u{e725}".into()
+fn default_prefix() -> Symbol {
+    symbol::GIT_HEAD
+}
+
+fn default_hash_length() -> usize {
+    7
 }
@@ -0,0 +1,152 @@
+// Copyright (C) 2020 Stephane Raux. Distributed under the MIT license.
+
+use crate::{symbol, Block, Environment, Pattern, Style, Symbol};
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct GitIdentity {
+    #[serde(default)]
+    style: Style,
+    #[serde(default = "default_prefix")]
+    prefix: Symbol,
+    #[serde(default)]
+    show: IdentityField,
+    #[serde(default)]
+    rules: Vec<IdentityStyle>,
+}
+
+impl GitIdentity {
+    pub fn new() -> Self {
+        GitIdentity {
+            style: Default::default(),
+            prefix: default_prefix(),
+            show: Default::default(),
+            rules: Vec::new(),
+        }
+    }
+
+    pub fn with_style<T>(self, style: T) -> Self
+    where
+        T: Into<Style>,
+    {
+        Self {
+            style: style.into(),
+            ..self
+        }
+    }
+
+    pub fn with_prefix<T>(self, prefix: T) -> Self
+    where
+        T: Into<Symbol>,
+    {
+        Self {
+            prefix: prefix.into(),
+            ..self
+        }
+    }
+
+    pub fn with_show(self, show: IdentityField) -> Self {
+        Self { show, ..self }
+    }
+
+    pub fn with_rule<T>(mut self, pattern: Pattern, style: T) -> Self
+    where
+        T: Into<Style>,
+    {
+        self.rules.push(IdentityStyle {
+            pattern,
+            style: style.into(),
+        });
+        self
+    }
+
+    pub fn produce(&self, environment: &Environment) -> Vec<Block> {
+        let repo = match environment.git() {
+            Some(repo) => repo,
+            None => return Vec::new(),
+        };
+        let (name, email) = match repo
+            .config_string("user.name")
+            .and_then(|name| Ok((name, repo.config_string("user.email")?)))
+        {
+            Ok(identity) => identity,
+            Err(e) => {
+                tracing::error!("Failed to read git configuration: {}", e);
+                return Vec::new();
+            }
+        };
+        let identity = match (&name, &email) {
+            (Some(name), Some(email)) => format!("{} <{}>", name, email),
+            (Some(name), None) => name.clone(),
+            (None, Some(email)) => format!("<{}>", email),
+            (None, None) => return Vec::new(),
+        };
+        let text = match self.show {
+            IdentityField::Name => name,
+            IdentityField::Email => email,
+            IdentityField::NameAndEmail => Some(identity.clone()),
+        };
+        let Some(text) = text else {
+            return Vec::new();
+        };
+        let style = self
+            .rules
+            .iter()
+            .find(|rule| rule.pattern.is_match(&identity))
+            .map_or(&self.style, |rule| &rule.style);
+        vec![
+            Block::new(self.prefix.get(environment.symbols())).with_style(style),
+            Block::new(text).with_style(style),
+        ]
+    }
+}
+
+impl Default for GitIdentity {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Part of the git identity to show
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub enum IdentityField {
+    Name,
+    #[default]
+    Email,
+    NameAndEmail,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+struct IdentityStyle {
+    pattern: Pattern,
+    style: Style,
+}
+
+fn default_prefix() -> Symbol {
+    symbol::GIT_IDENTITY
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{GitIdentity, IdentityField};
+    use crate::{color, git::fixture, Environment, Pattern, Style};
+
+    #[test]
+    fn identity_is_styled_by_first_matching_rule() {
+        let dir = tempfile::tempdir().unwrap();
+        fixture::init(dir.path());
+        let environment = Environment::new(Some(dir.path().to_owned()));
+        let work = Style::new().with_fg(color::CYAN);
+        let identity = GitIdentity::new()
+            .with_show(IdentityField::NameAndEmail)
+            .with_rule(Pattern::new("@corp\\.com>$").unwrap(), Style::new())
+            .with_rule(Pattern::new("@example\\.com>$").unwrap(), work.clone());
+        let blocks = identity.produce(&environment);
+        assert_eq!(blocks[1].text, "Test <test@example.com>");
+        assert_eq!(blocks[1].style, work);
+        let blocks = identity
+            .with_show(IdentityField::Name)
+            .produce(&environment);
+        assert_eq!(blocks[1].text, "Test");
+    }
+}
@@ -0,0 +1,131 @@
+// Copyright (C) 2020 Stephane Raux. Distributed under the MIT license.
+
+use crate::{symbol, Block, Environment, GitRepo, Operation, Style, Symbol};
+use serde::{Deserialize, Serialize};
+use std::{fs, path::Path};
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct GitOperation {
+    #[serde(default)]
+    style: Style,
+    #[serde(default = "default_prefix")]
+    prefix: Symbol,
+}
+
+impl GitOperation {
+    pub fn new() -> Self {
+        GitOperation {
+            style: Default::default(),
+            prefix: default_prefix(),
+        }
+    }
+
+    pub fn with_style<T>(self, style: T) -> Self
+    where
+        T: Into<Style>,
+    {
+        Self {
+            style: style.into(),
+            ..self
+        }
+    }
+
+    pub fn with_prefix<T>(self, prefix: T) -> Self
+    where
+        T: Into<Symbol>,
+    {
+        Self {
+            prefix: prefix.into(),
+            ..self
+        }
+    }
+
+    pub fn produce(&self, environment: &Environment) -> Vec<Block> {
+        let repo = match environment.git() {
+            Some(repo) => repo,
+            None => return Vec::new(),
+        };
+        let Some(text) = operation_label(repo) else {
+            return Vec::new();
+        };
+        vec![
+            Block::new(self.prefix.get(environment.symbols())).with_style(&self.style),
+            Block::new(text).with_style(&self.style),
+        ]
+    }
+}
+
+impl Default for GitOperation {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn default_prefix() -> Symbol {
+    symbol::GIT_OPERATION
+}
+
+/// Returns a label describing the operation in progress in `repo`, e.g. `REBASE 2/5`.
+pub(crate) fn operation_label(repo: &GitRepo) -> Option<String> {
+    let label = match repo.operation()? {
+        Operation::Merge => "MERGE",
+        Operation::Revert => "REVERT",
+        Operation::CherryPick => "CHERRY-PICK",
+        Operation::Bisect => "BISECT",
+        Operation::Rebase => "REBASE",
+        Operation::ApplyMailbox => "AM",
+        Operation::ApplyMailboxOrRebase => "AM/REBASE",
+    };
+    let text = match rebase_progress(repo) {
+        Some((step, total)) => format!("{} {}/{}", label, step, total),
+        None => label.to_owned(),
+    };
+    Some(text)
+}
+
+fn rebase_progress(repo: &GitRepo) -> Option<(u32, u32)> {
+    let git_dir = repo.git_dir();
+    let merge_dir = git_dir.join("rebase-merge");
+    let apply_dir = git_dir.join("rebase-apply");
+    if merge_dir.is_dir() {
+        Some((
+            read_number(&merge_dir.join("msgnum"))?,
+            read_number(&merge_dir.join("end"))?,
+        ))
+    } else if apply_dir.is_dir() {
+        Some((
+            read_number(&apply_dir.join("next"))?,
+            read_number(&apply_dir.join("last"))?,
+        ))
+    } else {
+        None
+    }
+}
+
+fn read_number(path: &Path) -> Option<u32> {
+    fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::operation_label;
+    use crate::GitRepo;
+    use git2::Repository;
+    use std::fs;
+
+    #[test]
+    fn operations_are_labeled_with_progress() {
+        let dir = tempfile::tempdir().unwrap();
+        Repository::init(dir.path()).unwrap();
+        let repo = GitRepo::discover(dir.path()).unwrap().unwrap();
+        assert_eq!(operation_label(&repo), None);
+        fs::write(repo.git_dir().join("MERGE_HEAD"), "").unwrap();
+        assert_eq!(operation_label(&repo).as_deref(), Some("MERGE"));
+        fs::remove_file(repo.git_dir().join("MERGE_HEAD")).unwrap();
+        let rebase_dir = repo.git_dir().join("rebase-merge");
+        fs::create_dir(&rebase_dir).unwrap();
+        fs::write(rebase_dir.join("msgnum"), "2\n").unwrap();
+        fs::write(rebase_dir.join("end"), "5\n").unwrap();
+        assert_eq!(operation_label(&repo).as_deref(), Some("REBASE 2/5"));
+    }
+}
@@ -1,14 +1,23 @@
 // Copyright (C) 2020 Stephane Raux. Distributed under the MIT license.
 
-use crate::{Block, Environment, Style};
+use crate::{symbol, Block, Environment, Style, Symbol};
 use serde::{Deserialize, Serialize};
+use std::path::MAIN_SEPARATOR_STR;
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct GitPath {
     #[serde(default)]
     style: Style,
     #[serde(default = "default_prefix")]
-    prefix: String,
+    prefix: Symbol,
+    #[serde(default = "default_deleted_marker")]
+    deleted_marker: String,
+    #[serde(default = "default_deleted_style")]
+    deleted_style: Style,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    max_width: Option<usize>,
+    #[serde(default = "default_ellipsis")]
+    ellipsis: Symbol,
 }
 
 impl GitPath {
@@ -16,6 +25,10 @@ impl GitPath {
         GitPath {
             style: Default::default(),
             prefix: default_prefix(),
+            deleted_marker: default_deleted_marker(),
+            deleted_style: default_deleted_style(),
+            max_width: None,
+            ellipsis: default_ellipsis(),
         }
     }
 
@@ -31,7 +44,7 @@ impl GitPath {
 
     pub fn with_prefix<T>(self, prefix: T) -> Self
     where
-        T: Into<String>,
+        T: Into<Symbol>,
     {
         Self {
             prefix: prefix.into(),
@@ -39,8 +52,48 @@ impl GitPath {
         }
     }
 
+    /// Sets the text shown after the path if the working directory was deleted.
+    pub fn with_deleted_marker<T>(self, marker: T) -> Self
+    where
+        T: Into<String>,
+    {
+        Self {
+            deleted_marker: marker.into(),
+            ..self
+        }
+    }
+
+    pub fn with_deleted_style<T>(self, style: T) -> Self
+    where
+        T: Into<Style>,
+    {
+        Self {
+            deleted_style: style.into(),
+            ..self
+        }
+    }
+
+    /// Shortens the path to `max_width` columns by removing leading components.
+    pub fn with_max_width(self, max_width: usize) -> Self {
+        Self {
+            max_width: Some(max_width),
+            ..self
+        }
+    }
+
+    /// Sets the text replacing the components removed to shorten the path.
+    pub fn with_ellipsis<T>(self, ellipsis: T) -> Self
+    where
+        T: Into<Symbol>,
+    {
+        Self {
+            ellipsis: ellipsis.into(),
+            ..self
+        }
+    }
+
     pub fn produce(&self, environment: &Environment) -> Vec<Block> {
-        let repo = match environment.repo() {
+        let repo = match environment.git() {
             Some(repo) => repo,
             None => return Vec::new(),
         };
@@ -48,7 +101,7 @@ impl GitPath {
             return Vec::new();
         } else {
             let Some(p) = repo
-                .path()
+                .git_dir()
                 .parent()
                 .and_then(|p| environment.working_dir()?.strip_prefix(p.parent()?).ok())
             else {
@@ -56,10 +109,28 @@ impl GitPath {
             };
             p
         };
-        vec![
-            Block::new(&self.prefix).with_style(&self.style),
-            Block::new(path.to_string_lossy()).with_style(&self.style),
-        ]
+        let mut blocks = vec![
+            Block::new(self.prefix.get(environment.symbols())).with_style(&self.style),
+            self.shorten(
+                Block::new(path.to_string_lossy()).with_style(&self.style),
+                environment,
+            ),
+        ];
+        if environment.working_dir_is_deleted() {
+            blocks.push(Block::new(" "));
+            blocks.push(Block::new(&self.deleted_marker).with_style(&self.deleted_style));
+        }
+        blocks
+    }
+
+    fn shorten(&self, path: Block, environment: &Environment) -> Block {
+        let path = path.with_break_hints_at(MAIN_SEPARATOR_STR);
+        match self.max_width {
+            Some(max_width) => {
+                path.truncate_start(max_width, self.ellipsis.get(environment.symbols()))
+            }
+            None => path,
+        }
     }
 }
 
@@ -69,6 +140,18 @@ impl Default for GitPath {
     }
 }
 
-fn default_prefix() -> String {
-    "\u{f7a1}".into()
+fn default_deleted_marker() -> String {
+    "(deleted)".into()
+}
+
+fn default_deleted_style() -> Style {
+    Style::new().with_fg(crate::color::CRIMSON)
+}
+
+fn default_prefix() -> Symbol {
+    symbol::GIT_PATH
+}
+
+fn default_ellipsis() -> Symbol {
+    symbol::ELLIPSIS
 }
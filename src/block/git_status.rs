@@ -0,0 +1,476 @@
+// Copyright (C) 2020 Stephane Raux. Distributed under the MIT license.
+
+use crate::{cache::StableHasher, symbol, Block, Environment, Style, Symbol};
+use git2::{Index, Repository, Status, StatusOptions};
+use serde::{Deserialize, Serialize};
+use std::{
+    ffi::OsString,
+    fs,
+    hash::{Hash, Hasher},
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct GitStatus {
+    #[serde(default)]
+    style: Style,
+    #[serde(default = "default_staged_symbol")]
+    staged_symbol: Symbol,
+    #[serde(default = "default_modified_symbol")]
+    modified_symbol: Symbol,
+    #[serde(default = "default_untracked_symbol")]
+    untracked_symbol: Symbol,
+    #[serde(default = "default_truncated_symbol")]
+    truncated_symbol: Symbol,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    max_untracked_files: Option<usize>,
+    #[serde(default)]
+    skip_submodules: bool,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    ignore_dirty: Vec<PathBuf>,
+}
+
+impl GitStatus {
+    pub fn new() -> Self {
+        GitStatus {
+            style: Default::default(),
+            staged_symbol: default_staged_symbol(),
+            modified_symbol: default_modified_symbol(),
+            untracked_symbol: default_untracked_symbol(),
+            truncated_symbol: default_truncated_symbol(),
+            max_untracked_files: None,
+            skip_submodules: false,
+            ignore_dirty: Vec::new(),
+        }
+    }
+
+    pub fn with_style<T>(self, style: T) -> Self
+    where
+        T: Into<Style>,
+    {
+        Self {
+            style: style.into(),
+            ..self
+        }
+    }
+
+    pub fn with_staged_symbol<T>(self, symbol: T) -> Self
+    where
+        T: Into<Symbol>,
+    {
+        Self {
+            staged_symbol: symbol.into(),
+            ..self
+        }
+    }
+
+    pub fn with_modified_symbol<T>(self, symbol: T) -> Self
+    where
+        T: Into<Symbol>,
+    {
+        Self {
+            modified_symbol: symbol.into(),
+            ..self
+        }
+    }
+
+    pub fn with_untracked_symbol<T>(self, symbol: T) -> Self
+    where
+        T: Into<Symbol>,
+    {
+        Self {
+            untracked_symbol: symbol.into(),
+            ..self
+        }
+    }
+
+    pub fn with_truncated_symbol<T>(self, symbol: T) -> Self
+    where
+        T: Into<Symbol>,
+    {
+        Self {
+            truncated_symbol: symbol.into(),
+            ..self
+        }
+    }
+
+    /// Stops counting untracked files after `max`. `None` means no limit.
+    pub fn with_max_untracked_files(self, max: Option<usize>) -> Self {
+        Self {
+            max_untracked_files: max,
+            ..self
+        }
+    }
+
+    pub fn skip_submodules(self, yes: bool) -> Self {
+        Self {
+            skip_submodules: yes,
+            ..self
+        }
+    }
+
+    /// Ignores changes under `paths`, relative to the root of the working tree.
+    pub fn with_ignore_dirty<I>(self, paths: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<PathBuf>,
+    {
+        Self {
+            ignore_dirty: paths.into_iter().map(Into::into).collect(),
+            ..self
+        }
+    }
+
+    pub fn produce(&self, environment: &Environment) -> Vec<Block> {
+        let repo = match environment.repo() {
+            Some(repo) if !repo.is_bare() => repo,
+            _ => return Vec::new(),
+        };
+        let cache = environment
+            .git_cache()
+            .and_then(|cache| Some((cache, self.worktree_fingerprint(repo, environment)?)));
+        let counts = match cache {
+            Some((mut cache, key)) => {
+                cache.get_or_try_insert_with("status", &key, || self.count(repo))
+            }
+            None => self.count(repo),
+        };
+        let counts = match counts {
+            Ok(counts) => counts,
+            Err(e) => {
+                tracing::error!("Failed to get git status: {}", e);
+                return Vec::new();
+            }
+        };
+        let untracked = if counts.untracked_truncated {
+            let truncated = self.truncated_symbol.get(environment.symbols());
+            format!("{}{}", counts.untracked, truncated)
+        } else {
+            counts.untracked.to_string()
+        };
+        [
+            (
+                &self.staged_symbol,
+                counts.staged,
+                counts.staged.to_string(),
+            ),
+            (
+                &self.modified_symbol,
+                counts.modified,
+                counts.modified.to_string(),
+            ),
+            (&self.untracked_symbol, counts.untracked, untracked),
+        ]
+        .into_iter()
+        .filter(|&(_, n, _)| n > 0)
+        .fold(Vec::new(), |mut blocks, (symbol, _, count)| {
+            if !blocks.is_empty() {
+                blocks.push(Block::new(" "));
+            }
+            blocks.push(Block::new(symbol.get(environment.symbols())).with_style(&self.style));
+            blocks.push(Block::new(count).with_style(&self.style));
+            blocks
+        })
+    }
+
+    fn count(&self, repo: &Repository) -> Result<StatusCounts, git2::Error> {
+        let scan_untracked = self.max_untracked_files.is_none();
+        let mut options = StatusOptions::new();
+        options
+            .include_untracked(scan_untracked)
+            .recurse_untracked_dirs(scan_untracked)
+            .exclude_submodules(self.skip_submodules);
+        if !self.ignore_dirty.is_empty() {
+            // The first matching pathspec applies and exclusions alone would match nothing.
+            for path in &self.ignore_dirty {
+                let mut pathspec = OsString::from("!");
+                pathspec.push(path);
+                options.pathspec(pathspec);
+            }
+            options.pathspec("*");
+        }
+        let mut counts = StatusCounts::default();
+        for entry in repo.statuses(Some(&mut options))?.iter() {
+            let status = entry.status();
+            if status.intersects(
+                Status::INDEX_NEW
+                    | Status::INDEX_MODIFIED
+                    | Status::INDEX_DELETED
+                    | Status::INDEX_RENAMED
+                    | Status::INDEX_TYPECHANGE,
+            ) {
+                counts.staged += 1;
+            }
+            if status.intersects(
+                Status::WT_MODIFIED
+                    | Status::WT_DELETED
+                    | Status::WT_RENAMED
+                    | Status::WT_TYPECHANGE
+                    | Status::CONFLICTED,
+            ) {
+                counts.modified += 1;
+            }
+            if status.contains(Status::WT_NEW) {
+                counts.untracked += 1;
+            }
+        }
+        if let Some(max) = self.max_untracked_files.filter(|&max| max > 0) {
+            (counts.untracked, counts.untracked_truncated) =
+                self.count_untracked(repo, &repo.index()?, max)?;
+        }
+        Ok(counts)
+    }
+
+    /// Counts untracked files up to `max`, returning whether counting stopped early.
+    fn count_untracked(
+        &self,
+        repo: &Repository,
+        index: &Index,
+        max: usize,
+    ) -> Result<(usize, bool), git2::Error> {
+        let Some(workdir) = repo.workdir() else {
+            return Ok((0, false));
+        };
+        let mut count = 0;
+        let mut dirs = vec![PathBuf::new()];
+        while let Some(dir) = dirs.pop() {
+            let Ok(entries) = fs::read_dir(workdir.join(&dir)) else {
+                continue;
+            };
+            for entry in entries.flatten() {
+                let path = dir.join(entry.file_name());
+                if path == Path::new(".git") || self.is_ignored(&path) {
+                    continue;
+                }
+                let Ok(file_type) = entry.file_type() else {
+                    continue;
+                };
+                if file_type.is_dir() {
+                    // Nested repositories, including submodules, have their own status.
+                    if !entry.path().join(".git").exists() && !repo.is_path_ignored(&path)? {
+                        dirs.push(path);
+                    }
+                } else if index.get_path(&path, 0).is_none() && !repo.is_path_ignored(&path)? {
+                    if count == max {
+                        return Ok((count, true));
+                    }
+                    count += 1;
+                }
+            }
+        }
+        Ok((count, false))
+    }
+
+    /// Returns a key identifying the options of this block and the state of the working tree, or
+    /// `None` if it cannot be determined cheaply.
+    ///
+    /// HEAD and the modification time of the index are covered by the cache key. Finding the
+    /// changes in the working tree takes a walk, which gives up after `max_untracked_files`
+    /// entries or once cancelled. Changes in submodules would go unnoticed, so nothing is cached
+    /// when the repository has submodules and they are not skipped.
+    fn worktree_fingerprint(&self, repo: &Repository, environment: &Environment) -> Option<String> {
+        let workdir = repo.workdir()?;
+        if !self.skip_submodules && !repo.submodules().ok()?.is_empty() {
+            return None;
+        }
+        let mut hasher = StableHasher::default();
+        let index_size = fs::metadata(repo.path().join("index")).map_or(0, |m| m.len());
+        (
+            &self.max_untracked_files,
+            self.skip_submodules,
+            &self.ignore_dirty,
+            index_size,
+        )
+            .hash(&mut hasher);
+        let mut budget = self.max_untracked_files;
+        let mut dirs = vec![PathBuf::new()];
+        while let Some(dir) = dirs.pop() {
+            if environment.is_cancelled() {
+                return None;
+            }
+            let metadata = fs::metadata(workdir.join(&dir)).ok()?;
+            (&dir, mtime(&metadata)).hash(&mut hasher);
+            let mut entries = fs::read_dir(workdir.join(&dir))
+                .ok()?
+                .flatten()
+                .collect::<Vec<_>>();
+            entries.sort_by_key(|entry| entry.file_name());
+            for entry in entries {
+                let path = dir.join(entry.file_name());
+                if path == Path::new(".git") || self.is_ignored(&path) {
+                    continue;
+                }
+                if let Some(budget) = &mut budget {
+                    *budget = budget.checked_sub(1)?;
+                }
+                let metadata = entry.metadata().ok()?;
+                if !metadata.is_dir() {
+                    (&path, metadata.len(), mtime(&metadata)).hash(&mut hasher);
+                } else if !entry.path().join(".git").exists()
+                    && !repo.is_path_ignored(&path).ok()?
+                {
+                    // Nested repositories, including skipped submodules, have their own status.
+                    dirs.push(path);
+                }
+            }
+        }
+        Some(format!("{:016x}", hasher.finish()))
+    }
+
+    fn is_ignored(&self, path: &Path) -> bool {
+        self.ignore_dirty.iter().any(|p| path.starts_with(p))
+    }
+}
+
+impl Default for GitStatus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+struct StatusCounts {
+    staged: usize,
+    modified: usize,
+    untracked: usize,
+    untracked_truncated: bool,
+}
+
+fn mtime(metadata: &fs::Metadata) -> u128 {
+    metadata
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(SystemTime::UNIX_EPOCH).ok())
+        .unwrap_or_default()
+        .as_nanos()
+}
+
+fn default_staged_symbol() -> Symbol {
+    symbol::GIT_STAGED
+}
+
+fn default_modified_symbol() -> Symbol {
+    symbol::GIT_MODIFIED
+}
+
+fn default_untracked_symbol() -> Symbol {
+    symbol::GIT_UNTRACKED
+}
+
+fn default_truncated_symbol() -> Symbol {
+    "+".into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::GitStatus;
+    use crate::{git::fixture, Cancellation, Environment};
+    use std::{fs, path::Path};
+
+    fn counts(status: &GitStatus, dir: &Path) -> String {
+        let environment = Environment::new(Some(dir.to_owned()));
+        status
+            .produce(&environment)
+            .into_iter()
+            .map(|block| block.text)
+            .collect()
+    }
+
+    fn status() -> GitStatus {
+        GitStatus::new()
+            .with_staged_symbol("+")
+            .with_modified_symbol("!")
+            .with_untracked_symbol("?")
+            .with_truncated_symbol("…")
+    }
+
+    fn make_repo(dir: &Path) {
+        let repo = fixture::init(dir);
+        fixture::commit_file(&repo, "a", "1");
+        fixture::commit_file(&repo, "vendor/b", "1");
+        fixture::stage(&repo, "c", "1");
+        fs::write(dir.join("a"), "2").unwrap();
+        fs::write(dir.join("vendor/b"), "2").unwrap();
+        for path in ["d", "e/f", "e/g", "vendor/h"] {
+            let path = dir.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, "1").unwrap();
+        }
+    }
+
+    #[test]
+    fn files_are_counted_by_status() {
+        let dir = tempfile::tempdir().unwrap();
+        make_repo(dir.path());
+        assert_eq!(counts(&status(), dir.path()), "+1 !2 ?4");
+    }
+
+    #[test]
+    fn untracked_files_are_counted_up_to_max() {
+        let dir = tempfile::tempdir().unwrap();
+        make_repo(dir.path());
+        assert_eq!(
+            counts(&status().with_max_untracked_files(Some(2)), dir.path()),
+            "+1 !2 ?2…"
+        );
+        assert_eq!(
+            counts(&status().with_max_untracked_files(Some(4)), dir.path()),
+            "+1 !2 ?4"
+        );
+        assert_eq!(
+            counts(&status().with_max_untracked_files(Some(0)), dir.path()),
+            "+1 !2"
+        );
+    }
+
+    #[test]
+    fn ignored_paths_are_not_counted() {
+        let dir = tempfile::tempdir().unwrap();
+        make_repo(dir.path());
+        let status = status().with_ignore_dirty(["vendor"]);
+        assert_eq!(counts(&status, dir.path()), "+1 !1 ?3");
+        assert_eq!(
+            counts(&status.with_max_untracked_files(Some(10)), dir.path()),
+            "+1 !1 ?3"
+        );
+    }
+
+    #[test]
+    fn cached_counts_follow_the_working_tree() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache_dir = tempfile::tempdir().unwrap();
+        make_repo(dir.path());
+        let counts = || {
+            let environment = Environment::new(Some(dir.path().to_owned()))
+                .with_cache_dir(Some(cache_dir.path().to_owned()));
+            status()
+                .produce(&environment)
+                .into_iter()
+                .map(|block| block.text)
+                .collect::<String>()
+        };
+        assert_eq!(counts(), "+1 !2 ?4");
+        assert_eq!(counts(), "+1 !2 ?4");
+        fs::write(dir.path().join("e/i"), "1").unwrap();
+        assert_eq!(counts(), "+1 !2 ?5");
+    }
+
+    #[test]
+    fn fingerprint_gives_up_past_max_untracked_files_or_cancellation() {
+        let dir = tempfile::tempdir().unwrap();
+        make_repo(dir.path());
+        let repo = git2::Repository::open(dir.path()).unwrap();
+        let environment = Environment::new(Some(dir.path().to_owned()));
+        let fingerprint = |status: GitStatus, environment: &Environment| {
+            status.worktree_fingerprint(&repo, environment)
+        };
+        assert!(fingerprint(status(), &environment).is_some());
+        assert!(fingerprint(status().with_max_untracked_files(Some(100)), &environment).is_some());
+        assert!(fingerprint(status().with_max_untracked_files(Some(2)), &environment).is_none());
+        let cancellation = Cancellation::new();
+        cancellation.cancel();
+        let environment = environment.with_cancellation(cancellation);
+        assert!(fingerprint(status(), &environment).is_none());
+    }
+}
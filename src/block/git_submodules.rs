@@ -0,0 +1,202 @@
+// Copyright (C) 2020 Stephane Raux. Distributed under the MIT license.
+
+use crate::{symbol, Block, Environment, Style, Symbol};
+use git2::{SubmoduleIgnore, SubmoduleStatus};
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct GitSubmodules {
+    #[serde(default)]
+    style: Style,
+    #[serde(default = "default_prefix")]
+    prefix: Symbol,
+    #[serde(default = "default_out_of_sync_symbol")]
+    out_of_sync_symbol: Symbol,
+    #[serde(default = "default_uninitialized_symbol")]
+    uninitialized_symbol: Symbol,
+}
+
+impl GitSubmodules {
+    pub fn new() -> Self {
+        GitSubmodules {
+            style: Default::default(),
+            prefix: default_prefix(),
+            out_of_sync_symbol: default_out_of_sync_symbol(),
+            uninitialized_symbol: default_uninitialized_symbol(),
+        }
+    }
+
+    pub fn with_style<T>(self, style: T) -> Self
+    where
+        T: Into<Style>,
+    {
+        Self {
+            style: style.into(),
+            ..self
+        }
+    }
+
+    pub fn with_prefix<T>(self, prefix: T) -> Self
+    where
+        T: Into<Symbol>,
+    {
+        Self {
+            prefix: prefix.into(),
+            ..self
+        }
+    }
+
+    pub fn with_out_of_sync_symbol<T>(self, symbol: T) -> Self
+    where
+        T: Into<Symbol>,
+    {
+        Self {
+            out_of_sync_symbol: symbol.into(),
+            ..self
+        }
+    }
+
+    pub fn with_uninitialized_symbol<T>(self, symbol: T) -> Self
+    where
+        T: Into<Symbol>,
+    {
+        Self {
+            uninitialized_symbol: symbol.into(),
+            ..self
+        }
+    }
+
+    pub fn produce(&self, environment: &Environment) -> Vec<Block> {
+        let repo = match environment.repo() {
+            Some(repo) => repo,
+            None => return Vec::new(),
+        };
+        let submodules = match repo.submodules() {
+            Ok(submodules) => submodules,
+            Err(e) => {
+                tracing::error!("Failed to list git submodules: {}", e);
+                return Vec::new();
+            }
+        };
+        let (out_of_sync, uninitialized) =
+            submodules
+                .iter()
+                .fold((0, 0), |(out_of_sync, uninitialized), submodule| {
+                    let Some(name) = submodule.name() else {
+                        return (out_of_sync, uninitialized);
+                    };
+                    match repo.submodule_status(name, SubmoduleIgnore::Unspecified) {
+                        Ok(status) if status.contains(SubmoduleStatus::WD_UNINITIALIZED) => {
+                            (out_of_sync, uninitialized + 1)
+                        }
+                        Ok(status) if status.intersects(out_of_sync_flags()) => {
+                            (out_of_sync + 1, uninitialized)
+                        }
+                        Ok(_) => (out_of_sync, uninitialized),
+                        Err(e) => {
+                            tracing::error!("Failed to get status of submodule {}: {}", name, e);
+                            (out_of_sync, uninitialized)
+                        }
+                    }
+                });
+        let counts = [
+            (&self.out_of_sync_symbol, out_of_sync),
+            (&self.uninitialized_symbol, uninitialized),
+        ];
+        let counts = counts
+            .iter()
+            .filter(|&&(_, n)| n > 0)
+            .map(|(symbol, n)| format!("{}{}", symbol.get(environment.symbols()), n))
+            .collect::<Vec<_>>();
+        if counts.is_empty() {
+            return Vec::new();
+        }
+        vec![
+            Block::new(self.prefix.get(environment.symbols())).with_style(&self.style),
+            Block::new(counts.join(" ")).with_style(&self.style),
+        ]
+    }
+}
+
+impl Default for GitSubmodules {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn default_prefix() -> Symbol {
+    symbol::GIT_SUBMODULES
+}
+
+fn default_out_of_sync_symbol() -> Symbol {
+    symbol::GIT_SUBMODULE_OUT_OF_SYNC
+}
+
+fn default_uninitialized_symbol() -> Symbol {
+    symbol::GIT_SUBMODULE_UNINITIALIZED
+}
+
+fn out_of_sync_flags() -> SubmoduleStatus {
+    SubmoduleStatus::WD_MODIFIED
+        | SubmoduleStatus::WD_ADDED
+        | SubmoduleStatus::WD_DELETED
+        | SubmoduleStatus::INDEX_MODIFIED
+}
+
+#[cfg(test)]
+mod tests {
+    use super::GitSubmodules;
+    use crate::{git::fixture, Environment};
+    use git2::{IndexEntry, IndexTime, Repository};
+    use std::fs;
+
+    #[test]
+    fn uninitialized_and_out_of_sync_submodules_are_counted() {
+        let upstream_dir = tempfile::tempdir().unwrap();
+        let upstream = fixture::init(upstream_dir.path());
+        let commit = fixture::commit_file(&upstream, "a", "1");
+        let dir = tempfile::tempdir().unwrap();
+        let repo = fixture::init(dir.path());
+        let url = upstream_dir.path().to_str().unwrap();
+        let gitmodules = ["a", "b"]
+            .iter()
+            .map(|name| format!("[submodule \"{name}\"]\n\tpath = {name}\n\turl = {url}\n"))
+            .collect::<String>();
+        fixture::stage(&repo, ".gitmodules", &gitmodules);
+        let mut index = repo.index().unwrap();
+        for path in ["a", "b"] {
+            index
+                .add(&IndexEntry {
+                    ctime: IndexTime::new(0, 0),
+                    mtime: IndexTime::new(0, 0),
+                    dev: 0,
+                    ino: 0,
+                    mode: 0o160000,
+                    uid: 0,
+                    gid: 0,
+                    file_size: 0,
+                    id: commit,
+                    flags: 0,
+                    flags_extended: 0,
+                    path: path.into(),
+                })
+                .unwrap();
+        }
+        index.write().unwrap();
+        fixture::commit(&repo, "Add submodules");
+        // Like a checkout, leave empty directories for uninitialized submodules.
+        fs::create_dir(dir.path().join("a")).unwrap();
+        fs::create_dir(dir.path().join("b")).unwrap();
+        let submodules = GitSubmodules::new()
+            .with_prefix("")
+            .with_out_of_sync_symbol("~")
+            .with_uninitialized_symbol("?");
+        let environment = || Environment::new(Some(dir.path().to_owned()));
+        assert_eq!(submodules.produce(&environment())[1].text, "?2");
+        fs::remove_dir(dir.path().join("a")).unwrap();
+        let clone = Repository::clone(url, dir.path().join("a")).unwrap();
+        assert_eq!(submodules.produce(&environment())[1].text, "?1");
+        fixture::commit_file(&clone, "a", "2");
+        assert_eq!(submodules.produce(&environment())[1].text, "~1 ?1");
+    }
+}
@@ -0,0 +1,122 @@
+// Copyright (C) 2020 Stephane Raux. Distributed under the MIT license.
+
+use crate::{symbol, Block, Environment, Style, Symbol};
+use git2::{DescribeFormatOptions, DescribeOptions};
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct GitTag {
+    #[serde(default)]
+    style: Style,
+    #[serde(default = "default_prefix")]
+    prefix: Symbol,
+    #[serde(default)]
+    describe: bool,
+}
+
+impl GitTag {
+    pub fn new() -> Self {
+        GitTag {
+            style: Default::default(),
+            prefix: default_prefix(),
+            describe: false,
+        }
+    }
+
+    pub fn with_style<T>(self, style: T) -> Self
+    where
+        T: Into<Style>,
+    {
+        Self {
+            style: style.into(),
+            ..self
+        }
+    }
+
+    pub fn with_prefix<T>(self, prefix: T) -> Self
+    where
+        T: Into<Symbol>,
+    {
+        Self {
+            prefix: prefix.into(),
+            ..self
+        }
+    }
+
+    pub fn with_describe(self, describe: bool) -> Self {
+        Self { describe, ..self }
+    }
+
+    pub fn produce(&self, environment: &Environment) -> Vec<Block> {
+        let repo = match environment.repo() {
+            Some(repo) => repo,
+            None => return Vec::new(),
+        };
+        let description = match repo.describe(&DescribeOptions::new()) {
+            Ok(description) => description,
+            Err(e) if e.code() == git2::ErrorCode::NotFound => return Vec::new(),
+            Err(e) if e.code() == git2::ErrorCode::UnbornBranch => return Vec::new(),
+            Err(e) => {
+                tracing::error!("Failed to find nearest git tag: {}", e);
+                return Vec::new();
+            }
+        };
+        let mut format = DescribeFormatOptions::new();
+        if !self.describe {
+            format.abbreviated_size(0);
+        }
+        let tag = match description.format(Some(&format)) {
+            Ok(tag) => tag,
+            Err(e) => {
+                tracing::error!("Failed to format git description: {}", e);
+                return Vec::new();
+            }
+        };
+        vec![
+            Block::new(self.prefix.get(environment.symbols())).with_style(&self.style),
+            Block::new(tag).with_style(&self.style),
+        ]
+    }
+}
+
+impl Default for GitTag {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn default_prefix() -> Symbol {
+    symbol::GIT_TAG
+}
+
+#[cfg(test)]
+mod tests {
+    use super::GitTag;
+    use crate::{git::fixture, Environment};
+
+    fn texts(tag: &GitTag, environment: &Environment) -> Vec<String> {
+        tag.produce(environment)
+            .into_iter()
+            .map(|block| block.text)
+            .collect()
+    }
+
+    #[test]
+    fn nearest_annotated_tag_is_shown() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = fixture::init(dir.path());
+        let tag = GitTag::new().with_prefix("");
+        let environment = || Environment::new(Some(dir.path().to_owned()));
+        assert!(texts(&tag, &environment()).is_empty());
+        let commit = fixture::commit_file(&repo, "a", "1");
+        let commit = repo.find_object(commit, None).unwrap();
+        let signature = repo.signature().unwrap();
+        repo.tag("v1.0", &commit, &signature, "Release", false)
+            .unwrap();
+        assert_eq!(texts(&tag, &environment()), ["", "v1.0"]);
+        let head = fixture::commit_file(&repo, "a", "2");
+        assert_eq!(texts(&tag, &environment()), ["", "v1.0"]);
+        let described = texts(&tag.with_describe(true), &environment());
+        assert_eq!(described[1], format!("v1.0-1-g{}", &head.to_string()[..7]));
+    }
+}
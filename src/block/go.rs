@@ -0,0 +1,185 @@
+// Copyright (C) 2020 Stephane Raux. Distributed under the MIT license.
+
+use crate::{symbol, Block, Environment, Style, Symbol};
+use serde::{Deserialize, Serialize};
+use std::{cmp::Ordering, fs};
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Go {
+    #[serde(default)]
+    style: Style,
+    #[serde(default = "default_prefix")]
+    prefix: Symbol,
+    #[serde(default = "default_show_declared")]
+    show_declared: bool,
+    #[serde(default = "default_mismatch_style")]
+    mismatch_style: Style,
+}
+
+impl Go {
+    pub fn new() -> Self {
+        Go {
+            style: Default::default(),
+            prefix: default_prefix(),
+            show_declared: default_show_declared(),
+            mismatch_style: default_mismatch_style(),
+        }
+    }
+
+    pub fn with_style<T>(self, style: T) -> Self
+    where
+        T: Into<Style>,
+    {
+        Self {
+            style: style.into(),
+            ..self
+        }
+    }
+
+    pub fn with_prefix<T>(self, prefix: T) -> Self
+    where
+        T: Into<Symbol>,
+    {
+        Self {
+            prefix: prefix.into(),
+            ..self
+        }
+    }
+
+    /// Shows the version declared by the `go` directive in `go.mod` if it differs from the
+    /// installed version.
+    pub fn show_declared(self, yes: bool) -> Self {
+        Self {
+            show_declared: yes,
+            ..self
+        }
+    }
+
+    /// Sets the style used when the installed version is older than the declared one.
+    pub fn with_mismatch_style<T>(self, style: T) -> Self
+    where
+        T: Into<Style>,
+    {
+        Self {
+            mismatch_style: style.into(),
+            ..self
+        }
+    }
+
+    pub fn produce(&self, environment: &Environment) -> Vec<Block> {
+        let Some(dir) = environment.working_dir() else {
+            return Vec::new();
+        };
+        let Some(go_mod) = dir
+            .ancestors()
+            .map(|d| d.join("go.mod"))
+            .find(|p| p.is_file())
+        else {
+            return Vec::new();
+        };
+        let declared = fs::read_to_string(&go_mod)
+            .ok()
+            .and_then(|contents| go_directive(&contents));
+        // The installed version depends on the module, which may select another toolchain.
+        let installed = crate::process::find_program("go")
+            .and_then(|go| {
+                let name = format!("go:{}", go_mod.display());
+                crate::process::cached_probe_with(environment, &name, &[&go, &go_mod], || {
+                    crate::process::output(
+                        environment.command(&go).args(["env", "GOVERSION"]),
+                        &environment.cancellation(),
+                    )
+                })
+            })
+            .map(|v| v.strip_prefix("go").unwrap_or(&v).to_owned());
+        let outdated = match (&installed, &declared) {
+            (Some(installed), Some(declared)) => {
+                compare_versions(installed, declared) == Ordering::Less
+            }
+            (None, _) => true,
+            (Some(_), None) => false,
+        };
+        let style = if outdated {
+            self.mismatch_style.or(&self.style)
+        } else {
+            self.style.clone()
+        };
+        let text = match (installed, declared) {
+            (Some(installed), Some(declared))
+                if self.show_declared
+                    && compare_versions(&installed, &declared) != Ordering::Equal =>
+            {
+                format!("{} ({})", installed, declared)
+            }
+            (Some(version), _) | (None, Some(version)) => version,
+            (None, None) => return Vec::new(),
+        };
+        vec![
+            Block::new(self.prefix.get(environment.symbols())).with_style(&style),
+            Block::new(text).with_style(style),
+        ]
+    }
+}
+
+impl Default for Go {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Returns the version in the `go` directive of a `go.mod` file.
+fn go_directive(go_mod: &str) -> Option<String> {
+    go_mod.lines().find_map(|line| {
+        let version = line.trim().strip_prefix("go ")?;
+        let version = version.split("//").next().unwrap_or(version).trim();
+        Some(version.to_owned())
+    })
+}
+
+/// Compares dotted versions numerically, e.g. `1.9` is older than `1.21`, which is the same as
+/// `1.21.0`.
+fn compare_versions(a: &str, b: &str) -> Ordering {
+    let numbers = |v: &str| -> Vec<u64> {
+        let mut numbers = v
+            .split('.')
+            .map(|n| {
+                let digits = n.find(|c: char| !c.is_ascii_digit()).unwrap_or(n.len());
+                n[..digits].parse().unwrap_or(0)
+            })
+            .collect::<Vec<_>>();
+        while numbers.last() == Some(&0) {
+            numbers.pop();
+        }
+        numbers
+    };
+    numbers(a).cmp(&numbers(b))
+}
+
+fn default_prefix() -> Symbol {
+    symbol::GO
+}
+
+fn default_show_declared() -> bool {
+    true
+}
+
+fn default_mismatch_style() -> Style {
+    Style::new().with_fg(crate::color::GOLD)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{compare_versions, go_directive};
+    use std::cmp::Ordering;
+
+    #[test]
+    fn go_directive_is_compared_to_installed_version() {
+        let go_mod =
+            "module example.com/m\n\ngo 1.21 // minimum\n\nrequire golang.org/x/text v0.3.0\n";
+        let declared = go_directive(go_mod).unwrap();
+        assert_eq!(declared, "1.21");
+        assert_eq!(compare_versions("1.9.2", &declared), Ordering::Less);
+        assert_eq!(compare_versions("1.22.1", &declared), Ordering::Greater);
+        assert_eq!(compare_versions("1.21.0", &declared), Ordering::Equal);
+    }
+}
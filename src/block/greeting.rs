@@ -0,0 +1,113 @@
+// Copyright (C) 2020 Stephane Raux. Distributed under the MIT license.
+
+use crate::{
+    block::{TimeOfDay, TimeRange},
+    Block, Environment, Style,
+};
+use serde::{Deserialize, Serialize};
+
+/// Shows a greeting or glyph depending on the time of day
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Greeting {
+    #[serde(default = "default_periods")]
+    periods: Vec<GreetingPeriod>,
+}
+
+impl Greeting {
+    pub fn new() -> Self {
+        Greeting {
+            periods: default_periods(),
+        }
+    }
+
+    /// Sets the periods of the day and their greetings. The first period containing the current
+    /// time is used.
+    pub fn with_periods<I>(self, periods: I) -> Self
+    where
+        I: IntoIterator<Item = GreetingPeriod>,
+    {
+        Self {
+            periods: periods.into_iter().collect(),
+        }
+    }
+
+    pub fn produce(&self, _: &Environment) -> Vec<Block> {
+        let now = TimeOfDay::from(jiff::Zoned::now().time());
+        let Some(period) = self.period_at(now) else {
+            return Vec::new();
+        };
+        vec![Block::new(&period.contents).with_style(&period.style)]
+    }
+
+    fn period_at(&self, time: TimeOfDay) -> Option<&GreetingPeriod> {
+        self.periods.iter().find(|p| p.range.contains(time))
+    }
+}
+
+impl Default for Greeting {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Period of the day with its greeting
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct GreetingPeriod {
+    range: TimeRange,
+    contents: String,
+    #[serde(default)]
+    style: Style,
+}
+
+impl GreetingPeriod {
+    pub fn new<T>(range: TimeRange, contents: T) -> Self
+    where
+        T: Into<String>,
+    {
+        GreetingPeriod {
+            range,
+            contents: contents.into(),
+            style: Default::default(),
+        }
+    }
+
+    pub fn with_style<T>(self, style: T) -> Self
+    where
+        T: Into<Style>,
+    {
+        Self {
+            style: style.into(),
+            ..self
+        }
+    }
+}
+
+fn default_periods() -> Vec<GreetingPeriod> {
+    [
+        ("05:00-12:00", "Good morning"),
+        ("12:00-18:00", "Good afternoon"),
+        ("18:00-22:00", "Good evening"),
+        ("22:00-05:00", "Good night"),
+    ]
+    .into_iter()
+    .map(|(range, contents)| {
+        let range = range.parse().expect("Default greeting range is valid");
+        GreetingPeriod::new(range, contents)
+    })
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Greeting;
+
+    #[test]
+    fn greeting_depends_on_time_of_day() {
+        let greeting = Greeting::new();
+        let at = |time: &str| greeting.period_at(time.parse().unwrap()).unwrap();
+        assert_eq!(at("05:00").contents, "Good morning");
+        assert_eq!(at("13:30").contents, "Good afternoon");
+        assert_eq!(at("21:59").contents, "Good evening");
+        assert_eq!(at("02:00").contents, "Good night");
+    }
+}
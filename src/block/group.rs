@@ -0,0 +1,63 @@
+// Copyright (C) 2020 Stephane Raux. Distributed under the MIT license.
+
+use crate::{Block, BlockProducer, Environment};
+use serde::{Deserialize, Serialize};
+
+/// Shows the blocks of a producer unless the named group is hidden
+///
+/// `eliprompt toggle <name>` flips the visibility of the group for the current shell session,
+/// e.g. to show verbose blocks only when needed.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Group {
+    name: String,
+    producer: Box<BlockProducer>,
+    #[serde(default)]
+    hidden: bool,
+}
+
+impl Group {
+    pub fn new<T>(name: T, producer: BlockProducer) -> Self
+    where
+        T: Into<String>,
+    {
+        Group {
+            name: name.into(),
+            producer: Box::new(producer),
+            hidden: false,
+        }
+    }
+
+    /// Hides the group until it is toggled.
+    pub fn hidden(self, yes: bool) -> Self {
+        Self {
+            hidden: yes,
+            ..self
+        }
+    }
+
+    pub fn produce(&self, environment: &Environment) -> Vec<Block> {
+        if self.hidden != environment.group_is_toggled(&self.name) {
+            Vec::new()
+        } else {
+            self.producer.produce(environment)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Group;
+    use crate::{block::Text, BlockProducer, Environment};
+
+    #[test]
+    fn toggling_flips_visibility() {
+        let group = Group::new("verbose", BlockProducer::Text(Text::new("a")));
+        let toggled = Environment::new(None).with_toggled_groups(["verbose".to_owned()]);
+        let other = Environment::new(None).with_toggled_groups(["other".to_owned()]);
+        assert_eq!(group.produce(&other).len(), 1);
+        assert!(group.produce(&toggled).is_empty());
+        let group = group.hidden(true);
+        assert!(group.produce(&other).is_empty());
+        assert_eq!(group.produce(&toggled).len(), 1);
+    }
+}
@@ -0,0 +1,132 @@
+// Copyright (C) 2020 Stephane Raux. Distributed under the MIT license.
+
+use crate::{block::BlockKind, Block, BlockProducer, Environment};
+use serde::{Deserialize, Serialize};
+use std::{fmt::Write as _, path::Path};
+
+/// Makes the blocks of a producer a link in terminals supporting hyperlinks
+///
+/// Links are written with OSC 8 escape sequences, which other terminals ignore. The blocks are
+/// left unchanged if the target has no URL, e.g. outside a git repository.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Hyperlink {
+    producer: Box<BlockProducer>,
+    target: LinkTarget,
+}
+
+impl Hyperlink {
+    pub fn new(producer: BlockProducer, target: LinkTarget) -> Self {
+        Hyperlink {
+            producer: Box::new(producer),
+            target,
+        }
+    }
+
+    pub fn produce(&self, environment: &Environment) -> Vec<Block> {
+        let blocks = self.producer.produce(environment);
+        if blocks.is_empty() {
+            return blocks;
+        }
+        let Some(url) = self.target.url(environment) else {
+            return blocks;
+        };
+        blocks
+            .into_iter()
+            .map(|block| match block.kind {
+                BlockKind::Newline => block,
+                _ => block.with_link(&url),
+            })
+            .collect()
+    }
+}
+
+/// Destination of a [`Hyperlink`]
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub enum LinkTarget {
+    Url(String),
+    /// The working directory, as a `file://` URL
+    WorkingDirectory,
+    /// The web page of the `origin` remote of the git repository
+    GitRemote,
+}
+
+impl LinkTarget {
+    fn url(&self, environment: &Environment) -> Option<String> {
+        match self {
+            LinkTarget::Url(url) => Some(url.clone()),
+            LinkTarget::WorkingDirectory => {
+                let dir = environment.working_dir()?;
+                Some(file_url(&whoami::hostname(), dir))
+            }
+            LinkTarget::GitRemote => {
+                let url = environment.git()?.remote_url("origin")?;
+                web_url(&url)
+            }
+        }
+    }
+}
+
+/// Returns the `file://` URL of `path` on `host`.
+fn file_url(host: &str, path: &Path) -> String {
+    let path = path.to_string_lossy().replace('\\', "/");
+    let encoded = path.bytes().fold(String::new(), |mut acc, b| {
+        if b.is_ascii_alphanumeric() || b"/:-._~".contains(&b) {
+            acc.push(char::from(b));
+        } else {
+            let _ = write!(acc, "%{:02X}", b);
+        }
+        acc
+    });
+    let separator = if encoded.starts_with('/') { "" } else { "/" };
+    format!("file://{}{}{}", host, separator, encoded)
+}
+
+/// Returns the URL of the web page of the repository at the git remote URL `remote`, assuming it
+/// is served over HTTPS by the same host, as with GitHub or GitLab.
+///
+/// Returns `None` for local remotes.
+fn web_url(remote: &str) -> Option<String> {
+    let (host, path) = if let Some((_, rest)) = remote.split_once("://") {
+        let rest = rest.rsplit_once('@').map_or(rest, |(_, rest)| rest);
+        let (host, path) = rest.split_once('/')?;
+        let host = host.split_once(':').map_or(host, |(host, _)| host);
+        (host, path)
+    } else {
+        let (host, path) = remote.split_once(':')?;
+        let host = host.rsplit_once('@').map_or(host, |(_, host)| host);
+        if host.contains('/') {
+            return None;
+        }
+        (host, path)
+    };
+    if host.is_empty() {
+        return None;
+    }
+    let path = path.trim_matches('/');
+    let path = path.strip_suffix(".git").unwrap_or(path);
+    Some(format!("https://{}/{}", host, path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{file_url, web_url};
+    use std::path::Path;
+
+    #[test]
+    fn remotes_are_converted_to_web_urls() {
+        let expected = Some("https://github.com/user/repo".to_owned());
+        assert_eq!(web_url("git@github.com:user/repo.git"), expected);
+        assert_eq!(web_url("ssh://git@github.com:22/user/repo.git"), expected);
+        assert_eq!(web_url("https://user@github.com/user/repo"), expected);
+        assert_eq!(web_url("/srv/git/repo.git"), None);
+        assert_eq!(web_url("file:///srv/git/repo.git"), None);
+    }
+
+    #[test]
+    fn paths_are_percent_encoded() {
+        assert_eq!(
+            file_url("host", Path::new("/home/user/my dir%")),
+            "file://host/home/user/my%20dir%25"
+        );
+    }
+}
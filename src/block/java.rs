@@ -0,0 +1,162 @@
+// Copyright (C) 2020 Stephane Raux. Distributed under the MIT license.
+
+use crate::{symbol, Block, Environment, Style, Symbol};
+use serde::{Deserialize, Serialize};
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Java {
+    #[serde(default)]
+    style: Style,
+    #[serde(default = "default_prefix")]
+    prefix: Symbol,
+    #[serde(default = "default_project_files")]
+    project_files: Vec<String>,
+}
+
+impl Java {
+    pub fn new() -> Self {
+        Java {
+            style: Default::default(),
+            prefix: default_prefix(),
+            project_files: default_project_files(),
+        }
+    }
+
+    pub fn with_style<T>(self, style: T) -> Self
+    where
+        T: Into<Style>,
+    {
+        Self {
+            style: style.into(),
+            ..self
+        }
+    }
+
+    pub fn with_prefix<T>(self, prefix: T) -> Self
+    where
+        T: Into<Symbol>,
+    {
+        Self {
+            prefix: prefix.into(),
+            ..self
+        }
+    }
+
+    pub fn with_project_files<I>(self, files: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        Self {
+            project_files: files.into_iter().map(Into::into).collect(),
+            ..self
+        }
+    }
+
+    pub fn produce(&self, environment: &Environment) -> Vec<Block> {
+        let Some(dir) = environment.working_dir() else {
+            return Vec::new();
+        };
+        let is_project = dir
+            .ancestors()
+            .any(|dir| self.project_files.iter().any(|f| dir.join(f).is_file()));
+        if !is_project {
+            return Vec::new();
+        }
+        let Some(version) = jdk_version(environment) else {
+            return Vec::new();
+        };
+        vec![
+            Block::new(self.prefix.get(environment.symbols())).with_style(&self.style),
+            Block::new(version).with_style(&self.style),
+        ]
+    }
+}
+
+impl Default for Java {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Returns the version of the active JDK, from `$JAVA_HOME` or the `java` found in `$PATH`.
+///
+/// The version is read from the `release` file of the JDK when there is one, as running `java` is
+/// slow. Otherwise `java -version` is run and its result is cached.
+fn jdk_version(environment: &Environment) -> Option<String> {
+    let java = match env::var_os("JAVA_HOME").filter(|home| !home.is_empty()) {
+        Some(home) => PathBuf::from(home).join("bin").join("java"),
+        None => crate::process::find_program("java")?,
+    };
+    let java = fs::canonicalize(&java).ok()?;
+    let home = java.parent().and_then(Path::parent)?;
+    if let Some(version) = fs::read_to_string(home.join("release"))
+        .ok()
+        .and_then(|release| release_version(&release))
+    {
+        return Some(version);
+    }
+    crate::process::cached_probe(environment, "java", &java, || {
+        java_version(environment, &java)
+    })
+}
+
+/// Returns the `JAVA_VERSION` value from the `release` file of a JDK.
+fn release_version(release: &str) -> Option<String> {
+    let version = release
+        .lines()
+        .find_map(|line| line.strip_prefix("JAVA_VERSION="))?
+        .trim()
+        .trim_matches('"');
+    (!version.is_empty()).then(|| version.to_owned())
+}
+
+fn java_version(environment: &Environment, java: &Path) -> Option<String> {
+    let mut command = environment.command(java);
+    command.arg("-version");
+    let output = match crate::process::run(&mut command, &environment.cancellation()) {
+        Ok(output) if output.status.success() => output,
+        Ok(_) => return None,
+        Err(e) => {
+            tracing::error!("Failed to run {}: {}", java.display(), e);
+            return None;
+        }
+    };
+    // The version is printed to stderr, e.g. `openjdk version "17.0.2" 2022-01-18`.
+    let output = String::from_utf8_lossy(&output.stderr);
+    let first_line = output.lines().next()?;
+    let version = first_line.split('"').nth(1)?;
+    Some(version.to_owned())
+}
+
+fn default_prefix() -> Symbol {
+    symbol::JAVA
+}
+
+fn default_project_files() -> Vec<String> {
+    [
+        "pom.xml",
+        "build.gradle",
+        "build.gradle.kts",
+        "settings.gradle",
+        "settings.gradle.kts",
+    ]
+    .into_iter()
+    .map(Into::into)
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::release_version;
+
+    #[test]
+    fn version_is_read_from_release_file() {
+        let release = "IMPLEMENTOR=\"Eclipse Adoptium\"\nJAVA_VERSION=\"17.0.2\"\n";
+        assert_eq!(release_version(release).as_deref(), Some("17.0.2"));
+    }
+}
@@ -0,0 +1,82 @@
+// Copyright (C) 2020 Stephane Raux. Distributed under the MIT license.
+
+use crate::{symbol, Block, Environment, Style, Symbol};
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct JjBookmarks {
+    #[serde(default)]
+    style: Style,
+    #[serde(default = "default_prefix")]
+    prefix: Symbol,
+    #[serde(default = "default_separator")]
+    separator: String,
+}
+
+impl JjBookmarks {
+    pub fn new() -> Self {
+        JjBookmarks {
+            style: Default::default(),
+            prefix: default_prefix(),
+            separator: default_separator(),
+        }
+    }
+
+    pub fn with_style<T>(self, style: T) -> Self
+    where
+        T: Into<Style>,
+    {
+        Self {
+            style: style.into(),
+            ..self
+        }
+    }
+
+    pub fn with_prefix<T>(self, prefix: T) -> Self
+    where
+        T: Into<Symbol>,
+    {
+        Self {
+            prefix: prefix.into(),
+            ..self
+        }
+    }
+
+    pub fn with_separator<T>(self, separator: T) -> Self
+    where
+        T: Into<String>,
+    {
+        Self {
+            separator: separator.into(),
+            ..self
+        }
+    }
+
+    pub fn produce(&self, environment: &Environment) -> Vec<Block> {
+        let Some(working_copy) = environment.jj().and_then(|jj| jj.working_copy()) else {
+            return Vec::new();
+        };
+        if working_copy.bookmarks.is_empty() {
+            return Vec::new();
+        }
+        let text = working_copy.bookmarks.join(&self.separator);
+        vec![
+            Block::new(self.prefix.get(environment.symbols())).with_style(&self.style),
+            Block::new(text).with_style(&self.style),
+        ]
+    }
+}
+
+impl Default for JjBookmarks {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn default_prefix() -> Symbol {
+    symbol::JJ_BOOKMARKS
+}
+
+fn default_separator() -> String {
+    " ".into()
+}
@@ -0,0 +1,80 @@
+// Copyright (C) 2020 Stephane Raux. Distributed under the MIT license.
+
+use crate::{Block, Environment, Style, Symbol};
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct JjDescription {
+    #[serde(default)]
+    style: Style,
+    #[serde(default = "default_prefix")]
+    prefix: Symbol,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    empty_text: Option<String>,
+}
+
+impl JjDescription {
+    pub fn new() -> Self {
+        JjDescription {
+            style: Default::default(),
+            prefix: default_prefix(),
+            empty_text: None,
+        }
+    }
+
+    pub fn with_style<T>(self, style: T) -> Self
+    where
+        T: Into<Style>,
+    {
+        Self {
+            style: style.into(),
+            ..self
+        }
+    }
+
+    pub fn with_prefix<T>(self, prefix: T) -> Self
+    where
+        T: Into<Symbol>,
+    {
+        Self {
+            prefix: prefix.into(),
+            ..self
+        }
+    }
+
+    /// Sets the text to show when the working-copy change has no description.
+    pub fn with_empty_text<T>(self, text: T) -> Self
+    where
+        T: Into<String>,
+    {
+        Self {
+            empty_text: Some(text.into()),
+            ..self
+        }
+    }
+
+    pub fn produce(&self, environment: &Environment) -> Vec<Block> {
+        let Some(working_copy) = environment.jj().and_then(|jj| jj.working_copy()) else {
+            return Vec::new();
+        };
+        let text = match (&*working_copy.description, &self.empty_text) {
+            ("", None) => return Vec::new(),
+            ("", Some(empty_text)) => empty_text,
+            (description, _) => description,
+        };
+        vec![
+            Block::new(self.prefix.get(environment.symbols())).with_style(&self.style),
+            Block::new(text).with_style(&self.style),
+        ]
+    }
+}
+
+impl Default for JjDescription {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn default_prefix() -> Symbol {
+    "".into()
+}
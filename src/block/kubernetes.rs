@@ -0,0 +1,128 @@
+// Copyright (C) 2020 Stephane Raux. Distributed under the MIT license.
+
+use crate::{symbol, Block, Environment, Pattern, Style, Symbol};
+use serde::{Deserialize, Serialize};
+
+/// Shows the current Kubernetes context and namespace, e.g. `prod-eu:payments`
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Kubernetes {
+    #[serde(default)]
+    style: Style,
+    #[serde(default = "default_prefix")]
+    prefix: Symbol,
+    #[serde(default = "default_show_namespace")]
+    show_namespace: bool,
+    #[serde(default = "default_namespace_separator")]
+    namespace_separator: String,
+    #[serde(default)]
+    rules: Vec<ContextStyle>,
+}
+
+impl Kubernetes {
+    pub fn new() -> Self {
+        Kubernetes {
+            style: Default::default(),
+            prefix: default_prefix(),
+            show_namespace: default_show_namespace(),
+            namespace_separator: default_namespace_separator(),
+            rules: Vec::new(),
+        }
+    }
+
+    pub fn with_style<T>(self, style: T) -> Self
+    where
+        T: Into<Style>,
+    {
+        Self {
+            style: style.into(),
+            ..self
+        }
+    }
+
+    pub fn with_prefix<T>(self, prefix: T) -> Self
+    where
+        T: Into<Symbol>,
+    {
+        Self {
+            prefix: prefix.into(),
+            ..self
+        }
+    }
+
+    /// Indicates if the namespace of the context should be shown after it. The namespace is
+    /// `default` if the context does not set one.
+    pub fn show_namespace(self, yes: bool) -> Self {
+        Self {
+            show_namespace: yes,
+            ..self
+        }
+    }
+
+    pub fn with_namespace_separator<T>(self, separator: T) -> Self
+    where
+        T: Into<String>,
+    {
+        Self {
+            namespace_separator: separator.into(),
+            ..self
+        }
+    }
+
+    /// Uses `style` for contexts matching `pattern`, e.g. production contexts. The first matching
+    /// rule applies.
+    pub fn with_rule<T>(mut self, pattern: Pattern, style: T) -> Self
+    where
+        T: Into<Style>,
+    {
+        self.rules.push(ContextStyle {
+            pattern,
+            style: style.into(),
+        });
+        self
+    }
+
+    pub fn produce(&self, environment: &Environment) -> Vec<Block> {
+        let Some(context) = environment.kube_context() else {
+            return Vec::new();
+        };
+        let style = self
+            .rules
+            .iter()
+            .find(|rule| rule.pattern.is_match(context))
+            .map_or(&self.style, |rule| &rule.style);
+        let mut blocks = vec![
+            Block::new(self.prefix.get(environment.symbols())).with_style(style),
+            Block::new(context).with_style(style),
+        ];
+        if self.show_namespace {
+            let namespace = crate::kube::context_namespace(context);
+            blocks.push(Block::new(&self.namespace_separator).with_style(style));
+            blocks.push(Block::new(namespace.as_deref().unwrap_or("default")).with_style(style));
+        }
+        blocks
+    }
+}
+
+impl Default for Kubernetes {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+struct ContextStyle {
+    pattern: Pattern,
+    style: Style,
+}
+
+fn default_prefix() -> Symbol {
+    symbol::KUBERNETES
+}
+
+fn default_show_namespace() -> bool {
+    true
+}
+
+fn default_namespace_separator() -> String {
+    ":".into()
+}
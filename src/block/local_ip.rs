@@ -0,0 +1,178 @@
+// Copyright (C) 2020 Stephane Raux. Distributed under the MIT license.
+
+use crate::{symbol, Block, Environment, Pattern, Style, Symbol};
+use serde::{Deserialize, Serialize};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, UdpSocket};
+
+/// Shows the primary local IP address, i.e. the one of the interface of the default route, or the
+/// address of the first interface matching a pattern
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct LocalIp {
+    #[serde(default)]
+    style: Style,
+    #[serde(default = "default_prefix")]
+    prefix: Symbol,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    interface: Option<Pattern>,
+    #[serde(default)]
+    ipv6: bool,
+}
+
+impl LocalIp {
+    pub fn new() -> Self {
+        LocalIp {
+            style: Default::default(),
+            prefix: default_prefix(),
+            interface: None,
+            ipv6: false,
+        }
+    }
+
+    pub fn with_style<T>(self, style: T) -> Self
+    where
+        T: Into<Style>,
+    {
+        Self {
+            style: style.into(),
+            ..self
+        }
+    }
+
+    pub fn with_prefix<T>(self, prefix: T) -> Self
+    where
+        T: Into<Symbol>,
+    {
+        Self {
+            prefix: prefix.into(),
+            ..self
+        }
+    }
+
+    /// Shows the address of the first interface whose name matches `pattern`, e.g. `^wg` for a
+    /// WireGuard VPN, instead of the primary address.
+    pub fn with_interface(self, pattern: Pattern) -> Self {
+        Self {
+            interface: Some(pattern),
+            ..self
+        }
+    }
+
+    /// Indicates if an IPv6 address is shown instead of an IPv4 address.
+    pub fn ipv6(self, yes: bool) -> Self {
+        Self { ipv6: yes, ..self }
+    }
+
+    pub fn produce(&self, environment: &Environment) -> Vec<Block> {
+        let address = match &self.interface {
+            Some(pattern) => interface_addresses()
+                .into_iter()
+                .filter(|(name, _)| pattern.is_match(name))
+                .map(|(_, address)| address)
+                .find(|address| address.is_ipv6() == self.ipv6 && !address.is_loopback()),
+            None => primary_address(self.ipv6),
+        };
+        let Some(address) = address else {
+            return Vec::new();
+        };
+        vec![
+            Block::new(self.prefix.get(environment.symbols())).with_style(&self.style),
+            Block::new(address.to_string()).with_style(&self.style),
+        ]
+    }
+}
+
+impl Default for LocalIp {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Returns the local address used to reach the Internet.
+///
+/// Connecting a UDP socket selects the route without sending anything.
+fn primary_address(ipv6: bool) -> Option<IpAddr> {
+    let (local, remote): (IpAddr, IpAddr) = if ipv6 {
+        (
+            Ipv6Addr::UNSPECIFIED.into(),
+            Ipv6Addr::new(0x2001, 0x4860, 0x4860, 0, 0, 0, 0, 0x8888).into(),
+        )
+    } else {
+        (
+            Ipv4Addr::UNSPECIFIED.into(),
+            Ipv4Addr::new(8, 8, 8, 8).into(),
+        )
+    };
+    let socket = UdpSocket::bind(SocketAddr::new(local, 0)).ok()?;
+    socket.connect(SocketAddr::new(remote, 53)).ok()?;
+    let address = socket.local_addr().ok()?.ip();
+    (!address.is_unspecified()).then_some(address)
+}
+
+/// Returns the names and addresses of the network interfaces.
+#[cfg(unix)]
+pub(super) fn interface_addresses() -> Vec<(String, IpAddr)> {
+    use std::{ffi::CStr, ptr};
+
+    let mut interfaces = ptr::null_mut();
+    // SAFETY: `interfaces` is a valid location for the list, which is freed below.
+    if unsafe { libc::getifaddrs(&mut interfaces) } != 0 {
+        tracing::error!(
+            "Failed to list network interfaces: {}",
+            std::io::Error::last_os_error()
+        );
+        return Vec::new();
+    }
+    let mut addresses = Vec::new();
+    let mut current = interfaces;
+    // SAFETY: The list returned by `getifaddrs` is valid until freed, and addresses point to
+    // structures matching their family.
+    unsafe {
+        while let Some(interface) = current.as_ref() {
+            current = interface.ifa_next;
+            let Some(address) = interface.ifa_addr.as_ref() else {
+                continue;
+            };
+            let address = match i32::from(address.sa_family) {
+                libc::AF_INET => {
+                    let address = &*(interface.ifa_addr as *const libc::sockaddr_in);
+                    IpAddr::from(u32::from_be(address.sin_addr.s_addr).to_be_bytes())
+                }
+                libc::AF_INET6 => {
+                    let address = &*(interface.ifa_addr as *const libc::sockaddr_in6);
+                    IpAddr::from(address.sin6_addr.s6_addr)
+                }
+                _ => continue,
+            };
+            let name = CStr::from_ptr(interface.ifa_name)
+                .to_string_lossy()
+                .into_owned();
+            addresses.push((name, address));
+        }
+        libc::freeifaddrs(interfaces);
+    }
+    addresses
+}
+
+#[cfg(not(unix))]
+pub(super) fn interface_addresses() -> Vec<(String, IpAddr)> {
+    Vec::new()
+}
+
+fn default_prefix() -> Symbol {
+    symbol::LOCAL_IP
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::interface_addresses;
+    use std::net::{IpAddr, Ipv4Addr};
+
+    #[test]
+    fn loopback_interface_is_listed() {
+        let addresses = interface_addresses();
+        assert!(addresses
+            .iter()
+            .any(|(name, address)| name.starts_with("lo")
+                && *address == IpAddr::V4(Ipv4Addr::LOCALHOST)));
+    }
+}
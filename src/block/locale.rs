@@ -0,0 +1,125 @@
+// Copyright (C) 2020 Stephane Raux. Distributed under the MIT license.
+
+use crate::{symbol, Block, Environment, Style, Symbol};
+use serde::{Deserialize, Serialize};
+use std::env;
+
+/// Shows a warning when the character encoding of the locale is not UTF-8
+///
+/// Such a locale breaks non-ASCII symbols and many tools. The locale is taken from `LC_ALL`,
+/// `LC_CTYPE` or `LANG`, in this order.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Locale {
+    #[serde(default = "default_style")]
+    style: Style,
+    #[serde(default = "default_prefix")]
+    prefix: Symbol,
+    #[serde(default = "default_show_locale")]
+    show_locale: bool,
+}
+
+impl Locale {
+    pub fn new() -> Self {
+        Locale {
+            style: default_style(),
+            prefix: default_prefix(),
+            show_locale: default_show_locale(),
+        }
+    }
+
+    pub fn with_style<T>(self, style: T) -> Self
+    where
+        T: Into<Style>,
+    {
+        Self {
+            style: style.into(),
+            ..self
+        }
+    }
+
+    pub fn with_prefix<T>(self, prefix: T) -> Self
+    where
+        T: Into<Symbol>,
+    {
+        Self {
+            prefix: prefix.into(),
+            ..self
+        }
+    }
+
+    /// Indicates if the offending locale is shown after the prefix. Defaults to `true`.
+    pub fn show_locale(self, yes: bool) -> Self {
+        Self {
+            show_locale: yes,
+            ..self
+        }
+    }
+
+    pub fn produce(&self, environment: &Environment) -> Vec<Block> {
+        let vars = ["LC_ALL", "LC_CTYPE", "LANG"].map(|name| env::var(name).ok());
+        let locale = ctype_locale(vars.iter().map(Option::as_deref));
+        if locale.is_some_and(is_utf8) {
+            return Vec::new();
+        }
+        let mut blocks =
+            vec![Block::new(self.prefix.get(environment.symbols())).with_style(&self.style)];
+        if self.show_locale {
+            blocks.push(Block::new(locale.unwrap_or("unset")).with_style(&self.style));
+        }
+        blocks
+    }
+}
+
+impl Default for Locale {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Returns the locale used for character encoding given the values of `LC_ALL`, `LC_CTYPE` and
+/// `LANG`. The first one set to a non-empty value applies.
+fn ctype_locale<'a, I>(vars: I) -> Option<&'a str>
+where
+    I: IntoIterator<Item = Option<&'a str>>,
+{
+    vars.into_iter().flatten().find(|v| !v.is_empty())
+}
+
+/// Indicates if `locale` uses UTF-8, e.g. `en_US.UTF-8` or `C.utf8`.
+fn is_utf8(locale: &str) -> bool {
+    let codeset = locale
+        .split_once('.')
+        .map_or("", |(_, rest)| rest.split('@').next().unwrap_or(rest));
+    codeset.eq_ignore_ascii_case("utf-8") || codeset.eq_ignore_ascii_case("utf8")
+}
+
+fn default_style() -> Style {
+    Style::new().with_fg(crate::color::GOLD)
+}
+
+fn default_prefix() -> Symbol {
+    symbol::LOCALE
+}
+
+fn default_show_locale() -> bool {
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ctype_locale, is_utf8};
+
+    #[test]
+    fn utf8_locales_are_recognized() {
+        assert!(is_utf8("en_US.UTF-8"));
+        assert!(is_utf8("C.utf8"));
+        assert!(is_utf8("sr_RS.UTF-8@latin"));
+        assert!(!is_utf8("C"));
+        assert!(!is_utf8("en_US.ISO-8859-1"));
+        assert_eq!(
+            ctype_locale([Some(""), Some("C"), Some("en_US.UTF-8")]),
+            Some("C")
+        );
+        assert_eq!(ctype_locale([None, None, None]), None);
+    }
+}
@@ -0,0 +1,86 @@
+// Copyright (C) 2020 Stephane Raux. Distributed under the MIT license.
+
+use crate::{symbol, Block, Environment, Style, Symbol};
+use serde::{Deserialize, Serialize};
+
+/// Shows the marker set with `eliprompt mark set`
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Marker {
+    #[serde(default = "default_style")]
+    style: Style,
+    #[serde(default = "default_prefix")]
+    prefix: Symbol,
+}
+
+impl Marker {
+    pub fn new() -> Self {
+        Marker {
+            style: default_style(),
+            prefix: default_prefix(),
+        }
+    }
+
+    pub fn with_style<T>(self, style: T) -> Self
+    where
+        T: Into<Style>,
+    {
+        Self {
+            style: style.into(),
+            ..self
+        }
+    }
+
+    pub fn with_prefix<T>(self, prefix: T) -> Self
+    where
+        T: Into<Symbol>,
+    {
+        Self {
+            prefix: prefix.into(),
+            ..self
+        }
+    }
+
+    pub fn produce(&self, environment: &Environment) -> Vec<Block> {
+        match environment.marker() {
+            Some(marker) => vec![
+                Block::new(self.prefix.get(environment.symbols())).with_style(&self.style),
+                Block::new(marker).with_style(&self.style),
+            ],
+            None => Vec::new(),
+        }
+    }
+}
+
+impl Default for Marker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn default_style() -> Style {
+    Style::new()
+        .with_fg(crate::color::WHITE)
+        .with_bg(crate::color::CRIMSON)
+}
+
+fn default_prefix() -> Symbol {
+    symbol::MARKER
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Marker;
+    use crate::{color, Environment, Style};
+
+    #[test]
+    fn marker_is_shown_only_when_set() {
+        let style = Style::new().with_fg(color::CYAN);
+        let marker = Marker::new().with_prefix("@").with_style(style.clone());
+        assert!(marker.produce(&Environment::new(None)).is_empty());
+        let environment = Environment::new(None).with_marker(Some("deploy".into()));
+        let blocks = marker.produce(&environment);
+        let texts = blocks.iter().map(|b| b.text.as_str()).collect::<Vec<_>>();
+        assert_eq!(texts, ["@", "deploy"]);
+        assert!(blocks.iter().all(|b| b.style == style));
+    }
+}
@@ -0,0 +1,240 @@
+// Copyright (C) 2020 Stephane Raux. Distributed under the MIT license.
+
+use crate::{Block, Environment, Style};
+use git2::{DiffOptions, Repository};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+/// Summarizes the git repositories directly contained in the working directory, e.g.
+/// `3 repos, 1 dirty`
+///
+/// Nothing is shown if the working directory has more entries than the scan limit, so that large
+/// directories do not slow the prompt down.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct MultiRepo {
+    #[serde(default)]
+    style: Style,
+    #[serde(default)]
+    dirty_style: Style,
+    #[serde(default = "default_min_repos")]
+    min_repos: usize,
+    #[serde(default = "default_scan_limit")]
+    scan_limit: usize,
+    #[serde(default = "default_show_dirty")]
+    show_dirty: bool,
+    #[serde(with = "humantime_serde", default = "default_cache_duration")]
+    cache_duration: Duration,
+}
+
+impl MultiRepo {
+    pub fn new() -> Self {
+        MultiRepo {
+            style: Default::default(),
+            dirty_style: Default::default(),
+            min_repos: default_min_repos(),
+            scan_limit: default_scan_limit(),
+            show_dirty: default_show_dirty(),
+            cache_duration: default_cache_duration(),
+        }
+    }
+
+    pub fn with_style<T>(self, style: T) -> Self
+    where
+        T: Into<Style>,
+    {
+        Self {
+            style: style.into(),
+            ..self
+        }
+    }
+
+    pub fn with_dirty_style<T>(self, style: T) -> Self
+    where
+        T: Into<Style>,
+    {
+        Self {
+            dirty_style: style.into(),
+            ..self
+        }
+    }
+
+    /// Sets the number of repositories needed to show the summary.
+    pub fn with_min_repos(self, min_repos: usize) -> Self {
+        Self { min_repos, ..self }
+    }
+
+    /// Sets the maximum number of directory entries examined.
+    pub fn with_scan_limit(self, scan_limit: usize) -> Self {
+        Self { scan_limit, ..self }
+    }
+
+    /// Indicates if the number of repositories with changes should be shown.
+    pub fn show_dirty(self, yes: bool) -> Self {
+        Self {
+            show_dirty: yes,
+            ..self
+        }
+    }
+
+    /// Sets how long the dirty state of a repository is cached.
+    pub fn with_cache_duration(self, cache_duration: Duration) -> Self {
+        Self {
+            cache_duration,
+            ..self
+        }
+    }
+
+    pub fn produce(&self, environment: &Environment) -> Vec<Block> {
+        let Some(dir) = environment.working_dir() else {
+            return Vec::new();
+        };
+        let Some(repos) = find_repos(dir, self.scan_limit) else {
+            return Vec::new();
+        };
+        if repos.len() < self.min_repos.max(1) {
+            return Vec::new();
+        }
+        let mut blocks = vec![Block::new(format!("{} repos", repos.len())).with_style(&self.style)];
+        if self.show_dirty {
+            let dirty = repos
+                .iter()
+                .take_while(|_| !environment.is_cancelled())
+                .filter(|repo| self.is_dirty(environment, repo))
+                .count();
+            if dirty > 0 {
+                blocks.push(Block::new(", ").with_style(&self.style));
+                blocks.push(Block::new(format!("{} dirty", dirty)).with_style(&self.dirty_style));
+            }
+        }
+        blocks
+    }
+
+    fn is_dirty(&self, environment: &Environment, path: &Path) -> bool {
+        let compute = || is_dirty(&Repository::open(path)?);
+        let name = path.to_string_lossy();
+        let period = self.cache_duration.as_secs().max(1);
+        let now = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let key = format!("{}:{}", period, now / period);
+        let dirty = match environment.workspace_cache() {
+            Some(mut cache) => cache.get_or_try_insert_with(&name, &key, compute),
+            None => compute(),
+        };
+        dirty.unwrap_or_else(|e| {
+            tracing::error!("Failed to get status of {}: {}", path.display(), e);
+            false
+        })
+    }
+}
+
+impl Default for MultiRepo {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Indicates if `repo` has staged, modified or untracked files.
+///
+/// Staged changes are looked for first, so that the working tree is not scanned if there are any.
+/// Untracked directories are not scanned either.
+fn is_dirty(repo: &Repository) -> Result<bool, git2::Error> {
+    let mut options = DiffOptions::new();
+    options
+        .include_untracked(true)
+        .recurse_untracked_dirs(false)
+        .enable_fast_untracked_dirs(true)
+        .ignore_submodules(true);
+    let head = match repo.head() {
+        Ok(head) => Some(head.peel_to_tree()?),
+        Err(e) if e.code() == git2::ErrorCode::UnbornBranch => None,
+        Err(e) => return Err(e),
+    };
+    let staged = repo.diff_tree_to_index(head.as_ref(), None, Some(&mut options))?;
+    if staged.deltas().len() > 0 {
+        return Ok(true);
+    }
+    let unstaged = repo.diff_index_to_workdir(None, Some(&mut options))?;
+    Ok(unstaged.deltas().len() > 0)
+}
+
+/// Returns the git repositories directly in `dir`, or `None` if it has more than `limit` entries.
+fn find_repos(dir: &Path, limit: usize) -> Option<Vec<PathBuf>> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) => {
+            tracing::error!("Failed to list {}: {}", dir.display(), e);
+            return None;
+        }
+    };
+    let mut repos = Vec::new();
+    for (i, entry) in entries.enumerate() {
+        if i == limit {
+            return None;
+        }
+        let Ok(entry) = entry else {
+            continue;
+        };
+        let path = entry.path();
+        if path.join(".git").exists() {
+            repos.push(path);
+        }
+    }
+    repos.sort();
+    Some(repos)
+}
+
+fn default_min_repos() -> usize {
+    2
+}
+
+fn default_scan_limit() -> usize {
+    100
+}
+
+fn default_show_dirty() -> bool {
+    true
+}
+
+fn default_cache_duration() -> Duration {
+    Duration::from_secs(60)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{find_repos, is_dirty};
+    use crate::git::fixture;
+    use std::fs;
+
+    #[test]
+    fn staged_modified_and_untracked_files_make_repos_dirty() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = fixture::init(dir.path());
+        assert!(!is_dirty(&repo).unwrap());
+        fs::create_dir_all(dir.path().join("new/nested")).unwrap();
+        fs::write(dir.path().join("new/nested/a"), "1").unwrap();
+        assert!(is_dirty(&repo).unwrap());
+        fixture::commit_file(&repo, "new/nested/a", "1");
+        assert!(!is_dirty(&repo).unwrap());
+        fs::write(dir.path().join("new/nested/a"), "2").unwrap();
+        assert!(is_dirty(&repo).unwrap());
+        fixture::stage(&repo, "new/nested/a", "2");
+        assert!(is_dirty(&repo).unwrap());
+    }
+
+    #[test]
+    fn repos_are_found_within_scan_limit() {
+        let dir = tempfile::tempdir().unwrap();
+        let dir = dir.path();
+        for name in ["a/.git", "b/.git", "c"] {
+            fs::create_dir_all(dir.join(name)).unwrap();
+        }
+        assert_eq!(find_repos(dir, 3), Some(vec![dir.join("a"), dir.join("b")]));
+        assert_eq!(find_repos(dir, 2), None);
+    }
+}
@@ -0,0 +1,139 @@
+// Copyright (C) 2020 Stephane Raux. Distributed under the MIT license.
+
+use crate::{symbol, Block, Environment, Style, Symbol};
+use serde::{Deserialize, Serialize};
+use std::{env, process::Command};
+
+/// Shows the session and window names when the shell runs inside tmux or GNU screen
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Multiplexer {
+    #[serde(default)]
+    style: Style,
+    #[serde(default = "default_prefix")]
+    prefix: Symbol,
+    #[serde(default = "default_show_window")]
+    show_window: bool,
+    #[serde(default = "default_window_separator")]
+    window_separator: String,
+}
+
+impl Multiplexer {
+    pub fn new() -> Self {
+        Multiplexer {
+            style: Default::default(),
+            prefix: default_prefix(),
+            show_window: default_show_window(),
+            window_separator: default_window_separator(),
+        }
+    }
+
+    pub fn with_style<T>(self, style: T) -> Self
+    where
+        T: Into<Style>,
+    {
+        Self {
+            style: style.into(),
+            ..self
+        }
+    }
+
+    pub fn with_prefix<T>(self, prefix: T) -> Self
+    where
+        T: Into<Symbol>,
+    {
+        Self {
+            prefix: prefix.into(),
+            ..self
+        }
+    }
+
+    /// Indicates if the window name is shown after the session name. Defaults to `true`.
+    pub fn show_window(self, yes: bool) -> Self {
+        Self {
+            show_window: yes,
+            ..self
+        }
+    }
+
+    pub fn with_window_separator<T>(self, separator: T) -> Self
+    where
+        T: Into<String>,
+    {
+        Self {
+            window_separator: separator.into(),
+            ..self
+        }
+    }
+
+    pub fn produce(&self, environment: &Environment) -> Vec<Block> {
+        let Some((session, window)) = tmux(environment).or_else(screen) else {
+            return Vec::new();
+        };
+        let mut blocks = vec![
+            Block::new(self.prefix.get(environment.symbols())).with_style(&self.style),
+            Block::new(session).with_style(&self.style),
+        ];
+        if let Some(window) = window.filter(|w| self.show_window && !w.is_empty()) {
+            blocks.push(Block::new(&self.window_separator).with_style(&self.style));
+            blocks.push(Block::new(window).with_style(&self.style));
+        }
+        blocks
+    }
+}
+
+impl Default for Multiplexer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Returns the session and window names of the tmux session the shell runs in.
+fn tmux(environment: &Environment) -> Option<(String, Option<String>)> {
+    env::var_os("TMUX").filter(|v| !v.is_empty())?;
+    let output = crate::process::output(
+        Command::new("tmux").args(["display-message", "-p", "#S\t#W"]),
+        &environment.cancellation(),
+    )?;
+    let (session, window) = output.split_once('\t').unwrap_or((&output, ""));
+    Some((session.to_owned(), Some(window.to_owned())))
+}
+
+/// Returns the session name and window number of the GNU screen session the shell runs in.
+fn screen() -> Option<(String, Option<String>)> {
+    let session = screen_session_name(&env::var("STY").ok()?)?;
+    Some((session, env::var("WINDOW").ok()))
+}
+
+/// Extracts the session name from the value of `STY`, e.g. `12345.work`.
+fn screen_session_name(sty: &str) -> Option<String> {
+    let (_, name) = sty.split_once('.')?;
+    (!name.is_empty()).then(|| name.to_owned())
+}
+
+fn default_prefix() -> Symbol {
+    symbol::MULTIPLEXER
+}
+
+fn default_show_window() -> bool {
+    true
+}
+
+fn default_window_separator() -> String {
+    ":".into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::screen_session_name;
+
+    #[test]
+    fn screen_session_name_is_parsed() {
+        assert_eq!(screen_session_name("12345.work"), Some("work".into()));
+        assert_eq!(
+            screen_session_name("12345.pts-0.host"),
+            Some("pts-0.host".into())
+        );
+        assert_eq!(screen_session_name("12345."), None);
+        assert_eq!(screen_session_name(""), None);
+    }
+}
@@ -1,6 +1,6 @@
 // Copyright (C) 2020 Stephane Raux. Distributed under the MIT license.
 
-use crate::{Block, Environment};
+use crate::{block::BlockKind, Block, Environment};
 use serde::{Deserialize, Serialize};
 
 #[derive(Clone, Debug, Default, Deserialize, Serialize)]
@@ -8,6 +8,6 @@ pub struct Newline;
 
 impl Newline {
     pub fn produce(&self, _: &Environment) -> Vec<Block> {
-        vec![Block::new("\n")]
+        vec![Block::new("\n").with_kind(BlockKind::Newline)]
     }
 }
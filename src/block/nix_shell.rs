@@ -0,0 +1,119 @@
+// Copyright (C) 2020 Stephane Raux. Distributed under the MIT license.
+
+use crate::{symbol, Block, Environment, Style, Symbol};
+use serde::{Deserialize, Serialize};
+use std::env;
+
+/// Shows the name of the Nix shell the shell runs in, as entered with `nix-shell` or
+/// `nix develop`
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct NixShell {
+    #[serde(default)]
+    style: Style,
+    #[serde(default = "default_pure_style")]
+    pure_style: Style,
+    #[serde(default = "default_prefix")]
+    prefix: Symbol,
+}
+
+impl NixShell {
+    pub fn new() -> Self {
+        NixShell {
+            style: Default::default(),
+            pure_style: default_pure_style(),
+            prefix: default_prefix(),
+        }
+    }
+
+    /// Sets the style of impure shells, which keep the variables of the parent environment.
+    pub fn with_style<T>(self, style: T) -> Self
+    where
+        T: Into<Style>,
+    {
+        Self {
+            style: style.into(),
+            ..self
+        }
+    }
+
+    /// Sets the style of pure shells, e.g. entered with `nix-shell --pure`.
+    pub fn with_pure_style<T>(self, style: T) -> Self
+    where
+        T: Into<Style>,
+    {
+        Self {
+            pure_style: style.into(),
+            ..self
+        }
+    }
+
+    pub fn with_prefix<T>(self, prefix: T) -> Self
+    where
+        T: Into<Symbol>,
+    {
+        Self {
+            prefix: prefix.into(),
+            ..self
+        }
+    }
+
+    pub fn produce(&self, environment: &Environment) -> Vec<Block> {
+        let Ok(kind) = env::var("IN_NIX_SHELL") else {
+            return Vec::new();
+        };
+        let style = if kind == "pure" {
+            &self.pure_style
+        } else {
+            &self.style
+        };
+        // The name of the derivation providing the shell, e.g. the `devShell` of a flake.
+        let name = env::var("name")
+            .ok()
+            .filter(|name| !name.is_empty())
+            .unwrap_or_else(|| "nix-shell".into());
+        vec![
+            Block::new(self.prefix.get(environment.symbols())).with_style(style),
+            Block::new(name).with_style(style),
+        ]
+    }
+}
+
+impl Default for NixShell {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn default_pure_style() -> Style {
+    Style::new().with_fg(crate::color::LIGHTBLUE)
+}
+
+fn default_prefix() -> Symbol {
+    symbol::NIX
+}
+
+#[cfg(test)]
+mod tests {
+    use super::NixShell;
+    use crate::{color, Environment, Style};
+    use std::env;
+
+    #[test]
+    fn pure_shells_have_their_own_style() {
+        let impure = Style::new().with_fg(color::CYAN);
+        let pure = Style::new().with_fg(color::GOLD);
+        let nix_shell = NixShell::new()
+            .with_style(impure.clone())
+            .with_pure_style(pure.clone());
+        let environment = Environment::new(None);
+        env::set_var("name", "dev-shell");
+        env::set_var("IN_NIX_SHELL", "pure");
+        let blocks = nix_shell.produce(&environment);
+        assert_eq!(blocks[1].text, "dev-shell");
+        assert_eq!(blocks[1].style, pure);
+        env::set_var("IN_NIX_SHELL", "impure");
+        assert_eq!(nix_shell.produce(&environment)[1].style, impure);
+        env::remove_var("IN_NIX_SHELL");
+        assert!(nix_shell.produce(&environment).is_empty());
+    }
+}
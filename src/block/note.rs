@@ -0,0 +1,206 @@
+// Copyright (C) 2020 Stephane Raux. Distributed under the MIT license.
+
+use crate::{symbol, Block, Environment, Style, Symbol};
+use serde::{Deserialize, Serialize};
+use std::{
+    env, fs, io,
+    path::{Path, PathBuf},
+    process,
+};
+
+/// Shows a message left by another program in a file, e.g. a build watcher or a deploy script
+///
+/// The file is removed once the prompt showing the message is printed, unless it is to be kept.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Note {
+    #[serde(default = "default_style")]
+    style: Style,
+    #[serde(default = "default_prefix")]
+    prefix: Symbol,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    path: Option<PathBuf>,
+    #[serde(default)]
+    keep: bool,
+}
+
+impl Note {
+    pub fn new() -> Self {
+        Note {
+            style: default_style(),
+            prefix: default_prefix(),
+            path: None,
+            keep: false,
+        }
+    }
+
+    pub fn with_style<T>(self, style: T) -> Self
+    where
+        T: Into<Style>,
+    {
+        Self {
+            style: style.into(),
+            ..self
+        }
+    }
+
+    pub fn with_prefix<T>(self, prefix: T) -> Self
+    where
+        T: Into<Symbol>,
+    {
+        Self {
+            prefix: prefix.into(),
+            ..self
+        }
+    }
+
+    /// Sets the file the message is read from. Defaults to `$XDG_RUNTIME_DIR/eliprompt/note`.
+    pub fn with_path<T>(self, path: T) -> Self
+    where
+        T: Into<PathBuf>,
+    {
+        Self {
+            path: Some(path.into()),
+            ..self
+        }
+    }
+
+    /// Indicates if the message should be shown until the file is removed, instead of once.
+    pub fn keep(self, yes: bool) -> Self {
+        Self { keep: yes, ..self }
+    }
+
+    pub fn produce(&self, environment: &Environment) -> Vec<Block> {
+        let path = self.path.clone().unwrap_or_else(default_path);
+        let Some(contents) = read(&path) else {
+            return Vec::new();
+        };
+        let message = contents
+            .lines()
+            .next()
+            .unwrap_or_default()
+            .trim()
+            .to_owned();
+        if !self.keep {
+            environment.note_shown(ShownNote { path, contents });
+        }
+        if message.is_empty() {
+            return Vec::new();
+        }
+        vec![
+            Block::new(self.prefix.get(environment.symbols())).with_style(&self.style),
+            Block::new(message).with_style(&self.style),
+        ]
+    }
+}
+
+impl Default for Note {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Returns the default file notes are read from, `$XDG_RUNTIME_DIR/eliprompt/note`, or
+/// `eliprompt/note` in the temporary directory if there is no runtime directory.
+fn default_path() -> PathBuf {
+    dirs::runtime_dir()
+        .unwrap_or_else(env::temp_dir)
+        .join("eliprompt")
+        .join("note")
+}
+
+fn read(path: &Path) -> Option<String> {
+    match fs::read_to_string(path) {
+        Ok(contents) => Some(contents),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+        Err(e) => {
+            tracing::error!("Failed to read note {}: {}", path.display(), e);
+            None
+        }
+    }
+}
+
+/// Note shown in a prompt, to be removed once the prompt is printed
+#[derive(Clone, Debug)]
+pub struct ShownNote {
+    path: PathBuf,
+    contents: String,
+}
+
+impl ShownNote {
+    /// Removes the note, unless it was rewritten since it was shown.
+    ///
+    /// The note is renamed first so that a note written concurrently is not lost.
+    pub fn remove(&self) {
+        let path = &self.path;
+        let taken = path.with_extension(format!("{}.taken", process::id()));
+        match fs::rename(path, &taken) {
+            Ok(()) => {}
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return,
+            Err(e) => {
+                tracing::error!("Failed to take note {}: {}", path.display(), e);
+                return;
+            }
+        }
+        let res = if read(&taken).as_ref() == Some(&self.contents) {
+            fs::remove_file(&taken)
+        } else {
+            fs::rename(&taken, path)
+        };
+        if let Err(e) = res {
+            tracing::error!("Failed to remove note {}: {}", taken.display(), e);
+        }
+    }
+}
+
+fn default_style() -> Style {
+    Style::new().with_fg(crate::color::GOLD)
+}
+
+fn default_prefix() -> Symbol {
+    symbol::NOTE
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Note;
+    use crate::Environment;
+    use std::fs;
+
+    #[test]
+    fn note_is_removed_once_shown() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("note");
+        fs::write(&path, "Build failed\nsee log\n").unwrap();
+        let note = Note::new().with_prefix("").with_path(&path);
+        let environment = Environment::new(None);
+        let texts = || {
+            note.produce(&environment)
+                .into_iter()
+                .map(|b| b.text)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(texts(), ["", "Build failed"]);
+        assert_eq!(texts(), ["", "Build failed"]);
+        environment
+            .take_shown_notes()
+            .iter()
+            .for_each(|note| note.remove());
+        assert!(texts().is_empty());
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn rewritten_note_is_kept() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("note");
+        fs::write(&path, "Build failed").unwrap();
+        let environment = Environment::new(None);
+        Note::new().with_path(&path).produce(&environment);
+        fs::write(&path, "Build passed").unwrap();
+        environment
+            .take_shown_notes()
+            .iter()
+            .for_each(|note| note.remove());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "Build passed");
+    }
+}
@@ -0,0 +1,440 @@
+// Copyright (C) 2020 Stephane Raux. Distributed under the MIT license.
+
+use crate::{symbol, Block, Cancellation, Environment, Style, Symbol};
+use serde::{Deserialize, Serialize};
+#[cfg(unix)]
+use std::os::unix::net::UnixStream;
+use std::{
+    env,
+    io::{self, BufRead, BufReader, Read, Write},
+    net::{IpAddr, SocketAddr, TcpStream, ToSocketAddrs},
+    process::Command,
+    sync::mpsc,
+    thread,
+    time::{Duration, Instant},
+};
+
+/// Shows the track currently playing in MPD or in a player controlled by `playerctl`
+///
+/// Nothing is shown when nothing is playing, or when the player does not answer within
+/// `timeout`.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct NowPlaying {
+    #[serde(default)]
+    style: Style,
+    #[serde(default = "default_prefix")]
+    prefix: Symbol,
+    #[serde(default)]
+    source: PlayerSource,
+    #[serde(default = "default_max_width")]
+    max_width: usize,
+    #[serde(with = "humantime_serde", default = "default_timeout")]
+    timeout: Duration,
+}
+
+impl NowPlaying {
+    pub fn new() -> Self {
+        NowPlaying {
+            style: Default::default(),
+            prefix: default_prefix(),
+            source: Default::default(),
+            max_width: default_max_width(),
+            timeout: default_timeout(),
+        }
+    }
+
+    pub fn with_style<T>(self, style: T) -> Self
+    where
+        T: Into<Style>,
+    {
+        Self {
+            style: style.into(),
+            ..self
+        }
+    }
+
+    pub fn with_prefix<T>(self, prefix: T) -> Self
+    where
+        T: Into<Symbol>,
+    {
+        Self {
+            prefix: prefix.into(),
+            ..self
+        }
+    }
+
+    pub fn with_source(self, source: PlayerSource) -> Self {
+        Self { source, ..self }
+    }
+
+    /// Sets the maximum width of the track, beyond which it is truncated.
+    pub fn with_max_width(self, max_width: usize) -> Self {
+        Self { max_width, ..self }
+    }
+
+    /// Sets the time after which the player is given up on.
+    pub fn with_timeout(self, timeout: Duration) -> Self {
+        Self { timeout, ..self }
+    }
+
+    pub fn produce(&self, environment: &Environment) -> Vec<Block> {
+        let cancellation = environment.cancellation().limited_to(self.timeout);
+        let track = match self.source {
+            PlayerSource::Auto => {
+                mpd_track(self.timeout, &cancellation).or_else(|| playerctl_track(&cancellation))
+            }
+            PlayerSource::Mpd => mpd_track(self.timeout, &cancellation),
+            PlayerSource::Playerctl => playerctl_track(&cancellation),
+        };
+        let Some(track) = track else {
+            return Vec::new();
+        };
+        vec![
+            Block::new(self.prefix.get(environment.symbols())).with_style(&self.style),
+            Block::new(track)
+                .with_style(&self.style)
+                .truncate_end(self.max_width, symbol::ELLIPSIS.get(environment.symbols())),
+        ]
+    }
+}
+
+impl Default for NowPlaying {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Player to get the current track from
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PlayerSource {
+    /// MPD if it is running, `playerctl` otherwise
+    #[default]
+    Auto,
+    Mpd,
+    Playerctl,
+}
+
+/// Returns the track MPD is playing, connecting as configured by `MPD_HOST` and `MPD_PORT`.
+///
+/// MPD is given up on after `timeout` overall, or once `cancellation` is cancelled.
+fn mpd_track(timeout: Duration, cancellation: &Cancellation) -> Option<String> {
+    let deadline = Instant::now() + timeout;
+    let host = env::var("MPD_HOST").unwrap_or_else(|_| "localhost".into());
+    let (password, host) = match host.rsplit_once('@') {
+        Some((password, host)) if !host.is_empty() => (Some(password), host),
+        _ => (None, host.as_str()),
+    };
+    let port = env::var("MPD_PORT")
+        .ok()
+        .and_then(|p| p.parse().ok())
+        .unwrap_or(6600);
+    let response = if host.starts_with('/') {
+        mpd_query_socket(host, password, deadline, cancellation)
+    } else {
+        connect_tcp(host, port, deadline).and_then(|stream| {
+            mpd_query(
+                DeadlineStream::new(stream, deadline, cancellation),
+                password,
+            )
+        })
+    };
+    // MPD not running is the common case and not worth reporting.
+    parse_mpd_track(&response.ok()?)
+}
+
+/// Connects to `host` before `deadline`.
+fn connect_tcp(host: &str, port: u16, deadline: Instant) -> io::Result<TcpStream> {
+    let mut last_error = io::ErrorKind::NotFound.into();
+    for addr in resolve(host, port, deadline)? {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            return Err(io::ErrorKind::TimedOut.into());
+        }
+        match TcpStream::connect_timeout(&addr, remaining) {
+            Ok(stream) => return Ok(stream),
+            Err(e) => last_error = e,
+        }
+    }
+    Err(last_error)
+}
+
+/// Resolves `host` before `deadline`.
+///
+/// Name resolution cannot be interrupted, so it runs in a thread that is left behind if it takes
+/// too long.
+fn resolve(host: &str, port: u16, deadline: Instant) -> io::Result<Vec<SocketAddr>> {
+    if let Ok(ip) = host.parse::<IpAddr>() {
+        return Ok(vec![SocketAddr::new(ip, port)]);
+    }
+    let (sender, receiver) = mpsc::channel();
+    let host = host.to_owned();
+    thread::spawn(move || {
+        let addrs = (host.as_str(), port)
+            .to_socket_addrs()
+            .map(Iterator::collect);
+        let _ = sender.send(addrs);
+    });
+    let remaining = deadline.saturating_duration_since(Instant::now());
+    receiver
+        .recv_timeout(remaining)
+        .unwrap_or_else(|_| Err(io::ErrorKind::TimedOut.into()))
+}
+
+#[cfg(unix)]
+fn mpd_query_socket(
+    path: &str,
+    password: Option<&str>,
+    deadline: Instant,
+    cancellation: &Cancellation,
+) -> io::Result<String> {
+    let stream = UnixStream::connect(path)?;
+    mpd_query(
+        DeadlineStream::new(stream, deadline, cancellation),
+        password,
+    )
+}
+
+#[cfg(not(unix))]
+fn mpd_query_socket(_: &str, _: Option<&str>, _: Instant, _: &Cancellation) -> io::Result<String> {
+    Err(io::ErrorKind::Unsupported.into())
+}
+
+/// Socket with timeouts for reads and writes
+trait TimedSocket: Read + Write {
+    fn set_timeouts(&self, timeout: Duration) -> io::Result<()>;
+}
+
+impl TimedSocket for TcpStream {
+    fn set_timeouts(&self, timeout: Duration) -> io::Result<()> {
+        self.set_read_timeout(Some(timeout))?;
+        self.set_write_timeout(Some(timeout))
+    }
+}
+
+#[cfg(unix)]
+impl TimedSocket for UnixStream {
+    fn set_timeouts(&self, timeout: Duration) -> io::Result<()> {
+        self.set_read_timeout(Some(timeout))?;
+        self.set_write_timeout(Some(timeout))
+    }
+}
+
+/// Socket whose reads and writes fail once a deadline passes or a token is cancelled
+struct DeadlineStream<S> {
+    socket: S,
+    deadline: Instant,
+    cancellation: Cancellation,
+}
+
+impl<S: TimedSocket> DeadlineStream<S> {
+    fn new(socket: S, deadline: Instant, cancellation: &Cancellation) -> Self {
+        DeadlineStream {
+            socket,
+            deadline,
+            cancellation: cancellation.clone(),
+        }
+    }
+
+    /// Limits the next operation to the time left before the deadline.
+    fn arm(&self) -> io::Result<()> {
+        let remaining = self.deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() || self.cancellation.is_cancelled() {
+            return Err(io::ErrorKind::TimedOut.into());
+        }
+        self.socket.set_timeouts(remaining)
+    }
+}
+
+impl<S: TimedSocket> Read for DeadlineStream<S> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.arm()?;
+        self.socket.read(buf)
+    }
+}
+
+impl<S: TimedSocket> Write for DeadlineStream<S> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.arm()?;
+        self.socket.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.socket.flush()
+    }
+}
+
+/// Sends the commands returning the player state and current song to MPD, and returns the
+/// response.
+fn mpd_query<S>(stream: S, password: Option<&str>) -> io::Result<String>
+where
+    S: io::Read + Write,
+{
+    let mut stream = BufReader::new(stream);
+    let greeting = read_mpd_response(&mut stream)?;
+    if !greeting.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "Unexpected MPD greeting",
+        ));
+    }
+    if let Some(password) = password {
+        let command = format!("password \"{}\"\n", password.replace('"', "\\\""));
+        stream.get_mut().write_all(command.as_bytes())?;
+        read_mpd_response(&mut stream)?;
+    }
+    stream
+        .get_mut()
+        .write_all(b"command_list_begin\nstatus\ncurrentsong\ncommand_list_end\n")?;
+    let response = read_mpd_response(&mut stream)?;
+    let _ = stream.get_mut().write_all(b"close\n");
+    Ok(response)
+}
+
+/// Reads lines from MPD up to the line starting with `OK`, and returns the lines before it.
+fn read_mpd_response<R: BufRead>(reader: &mut R) -> io::Result<String> {
+    let mut response = String::new();
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 || line.starts_with("ACK") {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                line.trim().to_owned(),
+            ));
+        }
+        if line.starts_with("OK") {
+            return Ok(response);
+        }
+        response.push_str(&line);
+    }
+}
+
+/// Returns the playing track described by the responses of MPD to `status` and `currentsong`.
+fn parse_mpd_track(response: &str) -> Option<String> {
+    let field = |name: &str| {
+        response.lines().find_map(|line| {
+            let (key, value) = line.split_once(": ")?;
+            (key == name).then_some(value)
+        })
+    };
+    if field("state")? != "play" {
+        return None;
+    }
+    track_name(field("Artist"), field("Title")).or_else(|| {
+        let file = field("file")?;
+        Some(file.rsplit('/').next().unwrap_or(file).to_owned())
+    })
+}
+
+/// Returns the track playing in the player selected by `playerctl`.
+fn playerctl_track(cancellation: &Cancellation) -> Option<String> {
+    let playerctl = crate::process::find_program("playerctl")?;
+    let status = crate::process::run(Command::new(&playerctl).arg("status"), cancellation).ok()?;
+    if String::from_utf8_lossy(&status.stdout).trim() != "Playing" {
+        return None;
+    }
+    let metadata = crate::process::output(
+        Command::new(&playerctl).args(["metadata", "--format", "{{artist}}\t{{title}}"]),
+        cancellation,
+    )?;
+    let (artist, title) = metadata.split_once('\t').unwrap_or(("", &metadata));
+    track_name(Some(artist), Some(title))
+}
+
+/// Returns `artist - title`, or whichever is known.
+fn track_name(artist: Option<&str>, title: Option<&str>) -> Option<String> {
+    match (
+        artist.filter(|a| !a.is_empty()),
+        title.filter(|t| !t.is_empty()),
+    ) {
+        (Some(artist), Some(title)) => Some(format!("{} - {}", artist, title)),
+        (artist, title) => title.or(artist).map(str::to_owned),
+    }
+}
+
+fn default_prefix() -> Symbol {
+    symbol::NOW_PLAYING
+}
+
+fn default_max_width() -> usize {
+    40
+}
+
+fn default_timeout() -> Duration {
+    Duration::from_millis(100)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_mpd_track;
+    #[cfg(unix)]
+    use super::{mpd_query, DeadlineStream};
+    #[cfg(unix)]
+    use crate::Cancellation;
+    #[cfg(unix)]
+    use std::{
+        io::{self, Write},
+        os::unix::net::UnixStream,
+        thread,
+        time::{Duration, Instant},
+    };
+
+    /// Answers an MPD query one line at a time, waiting `delay` before each line.
+    #[cfg(unix)]
+    fn slow_mpd(delay: Duration) -> UnixStream {
+        let (client, mut server) = UnixStream::pair().unwrap();
+        thread::spawn(move || {
+            for line in ["OK MPD 0.23.5\n", "state: play\n", "file: a.mp3\n", "OK\n"] {
+                thread::sleep(delay);
+                if server.write_all(line.as_bytes()).is_err() {
+                    return;
+                }
+            }
+            // Commands are consumed until the client disconnects.
+            let _ = io::copy(&mut server, &mut io::sink());
+        });
+        client
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn mpd_query_has_a_single_deadline() {
+        let query = |delay, timeout| {
+            let stream = DeadlineStream::new(
+                slow_mpd(delay),
+                Instant::now() + timeout,
+                &Cancellation::new(),
+            );
+            mpd_query(stream, None)
+        };
+        let response = query(Duration::ZERO, Duration::from_secs(5)).unwrap();
+        assert_eq!(response, "state: play\nfile: a.mp3\n");
+        // Each line arrives within the timeout, but not the whole response.
+        let t0 = Instant::now();
+        assert!(query(Duration::from_millis(100), Duration::from_millis(250)).is_err());
+        assert!(t0.elapsed() < Duration::from_secs(1));
+        let cancellation = Cancellation::new();
+        cancellation.cancel();
+        let stream = DeadlineStream::new(
+            slow_mpd(Duration::ZERO),
+            Instant::now() + Duration::from_secs(5),
+            &cancellation,
+        );
+        assert!(mpd_query(stream, None).is_err());
+    }
+
+    #[test]
+    fn mpd_track_is_parsed() {
+        let status = "volume: 50\nstate: play\nsong: 3\n";
+        let song = "file: music/Artist/Song.flac\nArtist: Artist\nTitle: Song\n";
+        assert_eq!(
+            parse_mpd_track(&format!("{}{}", status, song)),
+            Some("Artist - Song".into())
+        );
+        assert_eq!(
+            parse_mpd_track(&format!("{}file: music/track.mp3\n", status)),
+            Some("track.mp3".into())
+        );
+        assert_eq!(parse_mpd_track(&format!("state: pause\n{}", song)), None);
+    }
+}
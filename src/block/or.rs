@@ -7,11 +7,105 @@ use serde::{Deserialize, Serialize};
 pub struct Or(pub Vec<BlockProducer>);
 
 impl Or {
+    /// Shows the blocks of `producer` when none of the producers produces any block.
+    pub fn otherwise(self, producer: BlockProducer) -> OrElse {
+        OrElse::new(self.0).with_otherwise(producer)
+    }
+
+    /// Considers blocks containing only whitespace as empty if `yes` is true.
+    pub fn ignore_whitespace(self, yes: bool) -> OrElse {
+        OrElse::new(self.0).ignore_whitespace(yes)
+    }
+
     pub fn produce(&self, environment: &Environment) -> Vec<Block> {
-        self.0
-            .iter()
-            .map(|p| p.produce(environment))
-            .find(|blocks| !blocks.is_empty())
+        first_non_empty(&self.0, environment, false).unwrap_or_default()
+    }
+}
+
+/// Shows the blocks of the first producer that produces any, or of a placeholder producer
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct OrElse {
+    producers: Vec<BlockProducer>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    otherwise: Option<Box<BlockProducer>>,
+    #[serde(default)]
+    ignore_whitespace: bool,
+}
+
+impl OrElse {
+    pub fn new<I>(producers: I) -> Self
+    where
+        I: IntoIterator<Item = BlockProducer>,
+    {
+        OrElse {
+            producers: producers.into_iter().collect(),
+            otherwise: None,
+            ignore_whitespace: false,
+        }
+    }
+
+    pub fn with_otherwise(self, producer: BlockProducer) -> Self {
+        Self {
+            otherwise: Some(Box::new(producer)),
+            ..self
+        }
+    }
+
+    pub fn ignore_whitespace(self, yes: bool) -> Self {
+        Self {
+            ignore_whitespace: yes,
+            ..self
+        }
+    }
+
+    pub fn produce(&self, environment: &Environment) -> Vec<Block> {
+        first_non_empty(&self.producers, environment, self.ignore_whitespace)
+            .or_else(|| Some(self.otherwise.as_ref()?.produce(environment)))
             .unwrap_or_default()
     }
 }
+
+fn first_non_empty(
+    producers: &[BlockProducer],
+    environment: &Environment,
+    ignore_whitespace: bool,
+) -> Option<Vec<Block>> {
+    producers
+        .iter()
+        .map(|p| p.produce(environment))
+        .find(|blocks| {
+            if ignore_whitespace {
+                blocks.iter().any(|b| !b.text.trim().is_empty())
+            } else {
+                !blocks.is_empty()
+            }
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Or, OrElse};
+    use crate::{
+        block::{Sequence, Text},
+        BlockProducer, Environment,
+    };
+
+    #[test]
+    fn otherwise_is_shown_when_all_producers_are_empty() {
+        let config = r#"{
+            "producers": [{ "Sequence": [] }, { "Text": { "contents": " " } }],
+            "otherwise": { "Text": { "contents": "<no vcs>" } },
+            "ignore_whitespace": true
+        }"#;
+        let or = serde_json::from_str::<OrElse>(config).unwrap();
+        let blocks = or.produce(&Environment::new(None));
+        assert_eq!(blocks.len(), 1);
+        assert_eq!(blocks[0].text, "<no vcs>");
+        let or = Or(vec![
+            BlockProducer::Sequence(Sequence::default()),
+            BlockProducer::Text(Text::new("main")),
+        ])
+        .otherwise(BlockProducer::Text(Text::new("<no vcs>")));
+        assert_eq!(or.produce(&Environment::new(None))[0].text, "main");
+    }
+}
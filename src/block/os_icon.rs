@@ -0,0 +1,140 @@
+// Copyright (C) 2020 Stephane Raux. Distributed under the MIT license.
+
+use crate::{symbol::Symbol, Block, Environment, Style};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, fs};
+
+/// Shows an icon for the operating system, or for the distribution on Linux
+///
+/// The distribution is read from `/etc/os-release`. Without Nerd Font symbols, the name of the
+/// system is shown instead, e.g. `debian`.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct OsIcon {
+    #[serde(default)]
+    style: Style,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    icons: BTreeMap<String, String>,
+}
+
+impl OsIcon {
+    pub fn new() -> Self {
+        OsIcon {
+            style: Default::default(),
+            icons: BTreeMap::new(),
+        }
+    }
+
+    pub fn with_style<T>(self, style: T) -> Self
+    where
+        T: Into<Style>,
+    {
+        Self {
+            style: style.into(),
+            ..self
+        }
+    }
+
+    /// Shows `icon` for the system with the given identifier, e.g. `debian` or `macos`, instead of
+    /// the built-in icon.
+    pub fn with_icon<S, T>(mut self, system: S, icon: T) -> Self
+    where
+        S: Into<String>,
+        T: Into<String>,
+    {
+        self.icons.insert(system.into(), icon.into());
+        self
+    }
+
+    pub fn produce(&self, environment: &Environment) -> Vec<Block> {
+        let ids = system_ids();
+        let icon = ids
+            .iter()
+            .find_map(|id| self.icons.get(id).cloned())
+            .or_else(|| {
+                ids.iter()
+                    .find_map(|id| builtin_icon(id))
+                    .map(|icon| icon.get(environment.symbols()).to_owned())
+            });
+        let Some(icon) = icon else {
+            return Vec::new();
+        };
+        vec![Block::new(icon).with_style(&self.style)]
+    }
+}
+
+impl Default for OsIcon {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Returns identifiers of the system, from the most to the least specific, e.g. `ubuntu`,
+/// `debian` and `linux`.
+fn system_ids() -> Vec<String> {
+    if cfg!(target_os = "linux") {
+        let os_release = fs::read_to_string("/etc/os-release")
+            .or_else(|_| fs::read_to_string("/usr/lib/os-release"))
+            .unwrap_or_default();
+        let mut ids = distribution_ids(&os_release);
+        ids.push("linux".into());
+        ids
+    } else {
+        vec![std::env::consts::OS.to_owned()]
+    }
+}
+
+/// Returns the `ID` and `ID_LIKE` identifiers found in the contents of `os-release`.
+fn distribution_ids(os_release: &str) -> Vec<String> {
+    let field = |name: &str| {
+        os_release
+            .lines()
+            .find_map(|line| line.strip_prefix(name)?.strip_prefix('='))
+            .map(|value| value.trim().trim_matches(|c| c == '"' || c == '\''))
+            .unwrap_or_default()
+    };
+    let id = field("ID");
+    let like = field("ID_LIKE");
+    std::iter::once(id)
+        .chain(like.split_whitespace())
+        .filter(|id| !id.is_empty())
+        .map(str::to_owned)
+        .collect()
+}
+
+fn builtin_icon(id: &str) -> Option<Symbol> {
+    let icon = match id {
+        "alpine" => Symbol::new("\u{f300}", "alpine", "alpine"),
+        "arch" => Symbol::new("\u{f303}", "arch", "arch"),
+        "centos" => Symbol::new("\u{f304}", "centos", "centos"),
+        "debian" => Symbol::new("\u{f306}", "debian", "debian"),
+        "fedora" => Symbol::new("\u{f30a}", "fedora", "fedora"),
+        "gentoo" => Symbol::new("\u{f30d}", "gentoo", "gentoo"),
+        "linuxmint" => Symbol::new("\u{f30e}", "mint", "mint"),
+        "manjaro" => Symbol::new("\u{f312}", "manjaro", "manjaro"),
+        "nixos" => Symbol::new("\u{f313}", "nixos", "nixos"),
+        "opensuse" | "opensuse-leap" | "opensuse-tumbleweed" | "suse" => {
+            Symbol::new("\u{f314}", "suse", "suse")
+        }
+        "raspbian" => Symbol::new("\u{f315}", "raspbian", "raspbian"),
+        "rhel" => Symbol::new("\u{f316}", "rhel", "rhel"),
+        "ubuntu" => Symbol::new("\u{f31b}", "ubuntu", "ubuntu"),
+        "linux" => Symbol::new("\u{f17c}", "linux", "linux"),
+        "macos" => Symbol::new("\u{f179}", "macos", "macos"),
+        "windows" => Symbol::new("\u{f17a}", "windows", "windows"),
+        "freebsd" => Symbol::new("\u{f30c}", "freebsd", "freebsd"),
+        _ => return None,
+    };
+    Some(icon)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::distribution_ids;
+
+    #[test]
+    fn distribution_is_read_from_os_release() {
+        let os_release = "NAME=\"Pop!_OS\"\nID=pop\nID_LIKE=\"ubuntu debian\"\n";
+        assert_eq!(distribution_ids(os_release), ["pop", "ubuntu", "debian"]);
+        assert!(distribution_ids("NAME=Unknown\n").is_empty());
+    }
+}
@@ -0,0 +1,141 @@
+// Copyright (C) 2020 Stephane Raux. Distributed under the MIT license.
+
+use crate::{symbol, Block, Environment, Style, Symbol};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Php {
+    #[serde(default)]
+    style: Style,
+    #[serde(default = "default_prefix")]
+    prefix: Symbol,
+    #[serde(default = "default_project_files")]
+    project_files: Vec<String>,
+}
+
+impl Php {
+    pub fn new() -> Self {
+        Php {
+            style: Default::default(),
+            prefix: default_prefix(),
+            project_files: default_project_files(),
+        }
+    }
+
+    pub fn with_style<T>(self, style: T) -> Self
+    where
+        T: Into<Style>,
+    {
+        Self {
+            style: style.into(),
+            ..self
+        }
+    }
+
+    pub fn with_prefix<T>(self, prefix: T) -> Self
+    where
+        T: Into<Symbol>,
+    {
+        Self {
+            prefix: prefix.into(),
+            ..self
+        }
+    }
+
+    pub fn with_project_files<I>(self, files: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        Self {
+            project_files: files.into_iter().map(Into::into).collect(),
+            ..self
+        }
+    }
+
+    pub fn produce(&self, environment: &Environment) -> Vec<Block> {
+        let Some(dir) = environment.working_dir() else {
+            return Vec::new();
+        };
+        if !self.is_project(dir) {
+            return Vec::new();
+        }
+        let Some(php) = crate::process::find_program("php") else {
+            return Vec::new();
+        };
+        let Some(version) = version(environment, &php) else {
+            return Vec::new();
+        };
+        vec![
+            Block::new(self.prefix.get(environment.symbols())).with_style(&self.style),
+            Block::new(version).with_style(&self.style),
+        ]
+    }
+}
+
+impl Php {
+    fn is_project(&self, dir: &Path) -> bool {
+        dir.ancestors()
+            .any(|dir| self.project_files.iter().any(|f| dir.join(f).is_file()))
+    }
+}
+
+impl Default for Php {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Returns the version of the PHP interpreter at `php`.
+fn version(environment: &Environment, php: &Path) -> Option<String> {
+    let version = crate::process::cached_probe(environment, "php", php, || {
+        crate::process::output(
+            environment.command(php).args(["-r", "echo PHP_VERSION;"]),
+            &environment.cancellation(),
+        )
+    });
+    version.filter(|v| !v.is_empty())
+}
+
+fn default_prefix() -> Symbol {
+    symbol::PHP
+}
+
+fn default_project_files() -> Vec<String> {
+    vec!["composer.json".into()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{version, Php};
+    use crate::Environment;
+    use std::fs;
+
+    #[test]
+    fn projects_are_found_in_ancestors() {
+        let dir = tempfile::tempdir().unwrap();
+        let src = dir.path().join("src");
+        fs::create_dir(&src).unwrap();
+        let php = Php::new();
+        assert!(!php.is_project(&src));
+        fs::write(dir.path().join("composer.json"), "{}").unwrap();
+        assert!(php.is_project(&src));
+        assert!(!php.with_project_files(["index.php"]).is_project(&src));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn version_is_printed_by_php() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let php = dir.path().join("php");
+        fs::write(&php, "#!/bin/sh\nprintf 8.3.1\n").unwrap();
+        fs::set_permissions(&php, fs::Permissions::from_mode(0o755)).unwrap();
+        assert_eq!(
+            version(&Environment::new(None), &php).as_deref(),
+            Some("8.3.1")
+        );
+    }
+}
@@ -0,0 +1,111 @@
+// Copyright (C) 2020 Stephane Raux. Distributed under the MIT license.
+
+use crate::{block::BlockKind, Block, Environment, Style, Symbol};
+use serde::{Deserialize, Serialize};
+
+/// Shows the tool versions pinned for the working directory with mise or asdf
+///
+/// Pins are read from `.mise.toml`, `mise.toml` and `.tool-versions` files in the working
+/// directory and its ancestors. Versions that are not installed are flagged with a distinct style.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct PinnedTools {
+    #[serde(default)]
+    style: Style,
+    #[serde(default = "default_missing_style")]
+    missing_style: Style,
+    #[serde(default)]
+    prefix: Symbol,
+    #[serde(default)]
+    tools: Vec<String>,
+}
+
+impl PinnedTools {
+    pub fn new() -> Self {
+        PinnedTools {
+            style: Default::default(),
+            missing_style: default_missing_style(),
+            prefix: Symbol::default(),
+            tools: Vec::new(),
+        }
+    }
+
+    pub fn with_style<T>(self, style: T) -> Self
+    where
+        T: Into<Style>,
+    {
+        Self {
+            style: style.into(),
+            ..self
+        }
+    }
+
+    /// Sets the style of versions that are not installed.
+    pub fn with_missing_style<T>(self, style: T) -> Self
+    where
+        T: Into<Style>,
+    {
+        Self {
+            missing_style: style.into(),
+            ..self
+        }
+    }
+
+    pub fn with_prefix<T>(self, prefix: T) -> Self
+    where
+        T: Into<Symbol>,
+    {
+        Self {
+            prefix: prefix.into(),
+            ..self
+        }
+    }
+
+    /// Only shows the named tools. All pinned tools are shown by default.
+    pub fn with_tools<I>(self, tools: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        Self {
+            tools: tools.into_iter().map(Into::into).collect(),
+            ..self
+        }
+    }
+
+    pub fn produce(&self, environment: &Environment) -> Vec<Block> {
+        let Some(dir) = environment.working_dir() else {
+            return Vec::new();
+        };
+        let tools = crate::mise::pinned_tools(dir)
+            .into_iter()
+            .filter(|tool| self.tools.is_empty() || self.tools.contains(&tool.name))
+            .collect::<Vec<_>>();
+        if tools.is_empty() {
+            return Vec::new();
+        }
+        let mut blocks =
+            vec![Block::new(self.prefix.get(environment.symbols())).with_style(&self.style)];
+        for (i, tool) in tools.iter().enumerate() {
+            if i > 0 {
+                blocks.push(Block::new(" ").with_kind(BlockKind::Space));
+            }
+            let style = if tool.is_installed() {
+                &self.style
+            } else {
+                &self.missing_style
+            };
+            blocks.push(Block::new(format!("{} {}", tool.name, tool.version)).with_style(style));
+        }
+        blocks
+    }
+}
+
+impl Default for PinnedTools {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn default_missing_style() -> Style {
+    Style::new().with_fg(crate::color::CRIMSON)
+}
@@ -0,0 +1,141 @@
+// Copyright (C) 2020 Stephane Raux. Distributed under the MIT license.
+
+use crate::{
+    process::{fetch_in_background, file_age},
+    symbol, Block, Environment, Style, Symbol,
+};
+use serde::{Deserialize, Serialize};
+use std::{fs, net::IpAddr, time::Duration};
+
+/// Shows the public IP address of the machine
+///
+/// The address is fetched in the background with `curl` and cached, so that the prompt never
+/// waits for the network. The cached address is shown until it is refreshed, and nothing is shown
+/// before it is first fetched or if caching is disabled.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct PublicIp {
+    #[serde(default)]
+    style: Style,
+    #[serde(default = "default_prefix")]
+    prefix: Symbol,
+    #[serde(default = "default_url")]
+    url: String,
+    #[serde(with = "humantime_serde", default = "default_cache_duration")]
+    cache_duration: Duration,
+}
+
+impl PublicIp {
+    pub fn new() -> Self {
+        PublicIp {
+            style: Default::default(),
+            prefix: default_prefix(),
+            url: default_url(),
+            cache_duration: default_cache_duration(),
+        }
+    }
+
+    pub fn with_style<T>(self, style: T) -> Self
+    where
+        T: Into<Style>,
+    {
+        Self {
+            style: style.into(),
+            ..self
+        }
+    }
+
+    pub fn with_prefix<T>(self, prefix: T) -> Self
+    where
+        T: Into<Symbol>,
+    {
+        Self {
+            prefix: prefix.into(),
+            ..self
+        }
+    }
+
+    /// Sets the URL of the service responding with the public address as plain text. Defaults
+    /// to `https://api.ipify.org`.
+    pub fn with_url<T>(self, url: T) -> Self
+    where
+        T: Into<String>,
+    {
+        Self {
+            url: url.into(),
+            ..self
+        }
+    }
+
+    /// Sets how long the address is cached before being fetched again.
+    pub fn with_cache_duration(self, cache_duration: Duration) -> Self {
+        Self {
+            cache_duration,
+            ..self
+        }
+    }
+
+    pub fn produce(&self, environment: &Environment) -> Vec<Block> {
+        let Some(dir) = environment.cache_dir() else {
+            return Vec::new();
+        };
+        let path = dir.join("public-ip");
+        let expired = file_age(&path).is_none_or(|age| age >= self.cache_duration);
+        if expired && !environment.is_local_only() {
+            fetch_in_background(&self.url, &path);
+        }
+        let address = fs::read_to_string(&path)
+            .ok()
+            .and_then(|address| address.trim().parse::<IpAddr>().ok());
+        let Some(address) = address else {
+            return Vec::new();
+        };
+        let blocks = vec![
+            Block::new(self.prefix.get(environment.symbols())).with_style(&self.style),
+            Block::new(address.to_string()).with_style(&self.style),
+        ];
+        if expired {
+            environment.annotate_local_only(blocks)
+        } else {
+            blocks
+        }
+    }
+}
+
+impl Default for PublicIp {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn default_prefix() -> Symbol {
+    symbol::PUBLIC_IP
+}
+
+fn default_url() -> String {
+    "https://api.ipify.org".into()
+}
+
+fn default_cache_duration() -> Duration {
+    Duration::from_secs(60 * 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PublicIp;
+    use crate::Environment;
+    use std::fs;
+
+    #[test]
+    fn address_is_read_from_cache() {
+        let dir = tempfile::tempdir().unwrap();
+        let environment = Environment::new(None).with_cache_dir(Some(dir.path().to_owned()));
+        // Fetching is disabled in case the cached address is considered expired.
+        environment.restrict_to_local();
+        let path = dir.path().join("public-ip");
+        fs::write(&path, "203.0.113.7\n").unwrap();
+        let blocks = PublicIp::new().produce(&environment);
+        assert_eq!(blocks[1].text, "203.0.113.7");
+        fs::write(&path, "<html>Too many requests</html>").unwrap();
+        assert!(PublicIp::new().produce(&environment).is_empty());
+    }
+}
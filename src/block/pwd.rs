@@ -1,9 +1,9 @@
 // Copyright (C) 2020 Stephane Raux. Distributed under the MIT license.
 
-use crate::{Block, Environment, Style};
+use crate::{symbol, Block, Environment, Style, Symbol};
 use dirs::home_dir;
 use serde::{Deserialize, Serialize};
-use std::path::Path;
+use std::path::{Path, PathBuf, MAIN_SEPARATOR_STR};
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct WorkingDirectory {
@@ -11,8 +11,18 @@ pub struct WorkingDirectory {
     style: Style,
     #[serde(default = "default_home_as_tilde")]
     home_as_tilde: bool,
+    #[serde(default = "default_named_dirs")]
+    named_dirs: bool,
     #[serde(default = "default_prefix")]
-    prefix: String,
+    prefix: Symbol,
+    #[serde(default = "default_deleted_marker")]
+    deleted_marker: String,
+    #[serde(default = "default_deleted_style")]
+    deleted_style: Style,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    max_width: Option<usize>,
+    #[serde(default = "default_ellipsis")]
+    ellipsis: Symbol,
 }
 
 impl WorkingDirectory {
@@ -20,7 +30,12 @@ impl WorkingDirectory {
         WorkingDirectory {
             style: Default::default(),
             home_as_tilde: default_home_as_tilde(),
+            named_dirs: default_named_dirs(),
             prefix: default_prefix(),
+            deleted_marker: default_deleted_marker(),
+            deleted_style: default_deleted_style(),
+            max_width: None,
+            ellipsis: default_ellipsis(),
         }
     }
 
@@ -41,9 +56,15 @@ impl WorkingDirectory {
         }
     }
 
+    /// Indicates if named directories of the shell should be abbreviated as `~name`, like zsh's
+    /// `%~` does.
+    pub fn with_named_dirs(self, named_dirs: bool) -> Self {
+        Self { named_dirs, ..self }
+    }
+
     pub fn with_prefix<T>(self, prefix: T) -> Self
     where
-        T: Into<String>,
+        T: Into<Symbol>,
     {
         Self {
             prefix: prefix.into(),
@@ -51,23 +72,100 @@ impl WorkingDirectory {
         }
     }
 
+    /// Sets the text shown after the path if the working directory was deleted.
+    pub fn with_deleted_marker<T>(self, marker: T) -> Self
+    where
+        T: Into<String>,
+    {
+        Self {
+            deleted_marker: marker.into(),
+            ..self
+        }
+    }
+
+    pub fn with_deleted_style<T>(self, style: T) -> Self
+    where
+        T: Into<Style>,
+    {
+        Self {
+            deleted_style: style.into(),
+            ..self
+        }
+    }
+
+    /// Shortens the path to `max_width` columns by removing leading components.
+    pub fn with_max_width(self, max_width: usize) -> Self {
+        Self {
+            max_width: Some(max_width),
+            ..self
+        }
+    }
+
+    /// Sets the text replacing the components removed to shorten the path.
+    pub fn with_ellipsis<T>(self, ellipsis: T) -> Self
+    where
+        T: Into<Symbol>,
+    {
+        Self {
+            ellipsis: ellipsis.into(),
+            ..self
+        }
+    }
+
     pub fn produce(&self, environment: &Environment) -> Vec<Block> {
         let pwd = match environment.working_dir() {
-            Some(pwd) if self.home_as_tilde => {
-                match home_dir().and_then(|home| pwd.strip_prefix(home).ok()) {
-                    Some(p) if p.as_os_str().is_empty() => "~".into(),
-                    Some(p) => [Path::new("~"), p].iter().collect(),
-                    None => pwd.to_owned(),
-                }
-            }
-            Some(pwd) => pwd.to_owned(),
+            Some(pwd) => self.abbreviate(pwd, environment),
             None => "<NONE>".into(),
         };
         let pwd = pwd.to_string_lossy();
-        vec![
-            Block::new(&self.prefix).with_style(&self.style),
-            Block::new(pwd).with_style(&self.style),
-        ]
+        let mut blocks = vec![
+            Block::new(self.prefix.get(environment.symbols())).with_style(&self.style),
+            self.shorten(Block::new(pwd).with_style(&self.style), environment),
+        ];
+        if environment.working_dir_is_deleted() {
+            blocks.push(Block::new(" "));
+            blocks.push(Block::new(&self.deleted_marker).with_style(&self.deleted_style));
+        }
+        blocks
+    }
+
+    /// Replaces the longest prefix of `pwd` that is the home directory or a named directory with
+    /// `~` or `~name`, as zsh does.
+    fn abbreviate(&self, pwd: &Path, environment: &Environment) -> PathBuf {
+        let home = home_dir()
+            .filter(|_| self.home_as_tilde)
+            .map(|home| (String::new(), home));
+        let named_dirs = environment
+            .named_dirs()
+            .iter()
+            .filter(|_| self.named_dirs)
+            .cloned();
+        let abbreviation = home
+            .into_iter()
+            .chain(named_dirs)
+            .filter_map(|(name, dir)| Some((name, pwd.strip_prefix(&dir).ok()?, dir)))
+            .max_by_key(|(_, _, dir)| dir.components().count());
+        match abbreviation {
+            Some((name, rest, _)) => {
+                let name = format!("~{}", name);
+                if rest.as_os_str().is_empty() {
+                    name.into()
+                } else {
+                    Path::new(&name).join(rest)
+                }
+            }
+            None => pwd.to_owned(),
+        }
+    }
+
+    fn shorten(&self, path: Block, environment: &Environment) -> Block {
+        let path = path.with_break_hints_at(MAIN_SEPARATOR_STR);
+        match self.max_width {
+            Some(max_width) => {
+                path.truncate_start(max_width, self.ellipsis.get(environment.symbols()))
+            }
+            None => path,
+        }
     }
 }
 
@@ -81,6 +179,42 @@ fn default_home_as_tilde() -> bool {
     true
 }
 
-fn default_prefix() -> String {
-    "\u{f07c}".into()
+fn default_named_dirs() -> bool {
+    true
+}
+
+fn default_deleted_marker() -> String {
+    "(deleted)".into()
+}
+
+fn default_deleted_style() -> Style {
+    Style::new().with_fg(crate::color::CRIMSON)
+}
+
+fn default_prefix() -> Symbol {
+    symbol::WORKING_DIRECTORY
+}
+
+fn default_ellipsis() -> Symbol {
+    symbol::ELLIPSIS
+}
+
+#[cfg(test)]
+mod tests {
+    use super::WorkingDirectory;
+    use crate::Environment;
+    use std::path::{Path, PathBuf};
+
+    #[test]
+    fn longest_named_dir_is_abbreviated() {
+        let environment = Environment::new(None).with_named_dirs([
+            ("work".to_owned(), PathBuf::from("/srv/work")),
+            ("proj".to_owned(), PathBuf::from("/srv/work/proj")),
+        ]);
+        let pwd = WorkingDirectory::new().with_home_as_tilde(false);
+        let abbreviate = |path| pwd.abbreviate(Path::new(path), &environment);
+        assert_eq!(abbreviate("/srv/work/docs"), Path::new("~work/docs"));
+        assert_eq!(abbreviate("/srv/work/proj"), Path::new("~proj"));
+        assert_eq!(abbreviate("/srv/other"), Path::new("/srv/other"));
+    }
 }
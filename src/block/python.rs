@@ -0,0 +1,204 @@
+// Copyright (C) 2020 Stephane Raux. Distributed under the MIT license.
+
+use crate::{symbol, Block, Environment, Style, Symbol};
+use serde::{Deserialize, Serialize};
+use std::{
+    env,
+    path::{Path, PathBuf},
+};
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Python {
+    #[serde(default)]
+    style: Style,
+    #[serde(default = "default_prefix")]
+    prefix: Symbol,
+    #[serde(default)]
+    show_version: bool,
+    #[serde(default = "default_project_files")]
+    project_files: Vec<String>,
+    #[serde(default = "default_resolve_shims")]
+    resolve_shims: bool,
+}
+
+impl Python {
+    pub fn new() -> Self {
+        Python {
+            style: Default::default(),
+            prefix: default_prefix(),
+            show_version: false,
+            project_files: default_project_files(),
+            resolve_shims: default_resolve_shims(),
+        }
+    }
+
+    pub fn with_style<T>(self, style: T) -> Self
+    where
+        T: Into<Style>,
+    {
+        Self {
+            style: style.into(),
+            ..self
+        }
+    }
+
+    pub fn with_prefix<T>(self, prefix: T) -> Self
+    where
+        T: Into<Symbol>,
+    {
+        Self {
+            prefix: prefix.into(),
+            ..self
+        }
+    }
+
+    /// Shows the Python version when one of the project files is in the working directory or one
+    /// of its ancestors.
+    pub fn show_version(self, yes: bool) -> Self {
+        Self {
+            show_version: yes,
+            ..self
+        }
+    }
+
+    pub fn with_project_files<I>(self, files: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        Self {
+            project_files: files.into_iter().map(Into::into).collect(),
+            ..self
+        }
+    }
+
+    /// Indicates if the version selected by pyenv should be read from its version files instead of
+    /// running its shim, which is slow. Defaults to `true`.
+    pub fn resolve_shims(self, yes: bool) -> Self {
+        Self {
+            resolve_shims: yes,
+            ..self
+        }
+    }
+
+    pub fn produce(&self, environment: &Environment) -> Vec<Block> {
+        let virtual_env = env::var_os("VIRTUAL_ENV").map(PathBuf::from);
+        let name = virtual_env
+            .as_deref()
+            .and_then(virtual_env_name)
+            .or_else(|| env::var("CONDA_DEFAULT_ENV").ok())
+            .filter(|name| !name.is_empty());
+        let version = if self.show_version && self.is_project(environment) {
+            let python = match &virtual_env {
+                Some(dir) => Some(dir.join("bin").join("python")),
+                None => crate::process::find_program("python3"),
+            };
+            python.and_then(|python| {
+                let resolved = environment
+                    .working_dir()
+                    .filter(|_| self.resolve_shims)
+                    .and_then(|dir| crate::shim::resolved_version(&python, dir));
+                if resolved.is_some() {
+                    return resolved;
+                }
+                let name = format!("python:{}", python.display());
+                crate::process::cached_probe(environment, &name, &python, || {
+                    python_version(environment, &python)
+                })
+            })
+        } else {
+            None
+        };
+        let text = match (name, version) {
+            (Some(name), Some(version)) => format!("{} {}", name, version),
+            (Some(text), None) | (None, Some(text)) => text,
+            (None, None) => return Vec::new(),
+        };
+        vec![
+            Block::new(self.prefix.get(environment.symbols())).with_style(&self.style),
+            Block::new(text).with_style(&self.style),
+        ]
+    }
+
+    fn is_project(&self, environment: &Environment) -> bool {
+        let Some(dir) = environment.working_dir() else {
+            return false;
+        };
+        dir.ancestors()
+            .any(|dir| self.project_files.iter().any(|f| dir.join(f).is_file()))
+    }
+}
+
+impl Default for Python {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Returns the name of a virtual environment, which is its directory name unless that is a
+/// conventional name like `.venv`, in which case the name of the project directory is used.
+fn virtual_env_name(dir: &Path) -> Option<String> {
+    let name = dir.file_name()?.to_string_lossy();
+    let name = match &*name {
+        ".venv" | "venv" | ".env" | "env" => dir.parent()?.file_name()?.to_string_lossy(),
+        _ => name,
+    };
+    Some(name.into_owned())
+}
+
+fn python_version(environment: &Environment, python: &Path) -> Option<String> {
+    let mut command = environment.command(python);
+    command.arg("--version");
+    let output = match crate::process::run(&mut command, &environment.cancellation()) {
+        Ok(output) if output.status.success() => output,
+        Ok(_) => return None,
+        Err(e) => {
+            tracing::error!("Failed to run {}: {}", python.display(), e);
+            return None;
+        }
+    };
+    // Python 2 prints its version to stderr.
+    let output = if output.stdout.is_empty() {
+        output.stderr
+    } else {
+        output.stdout
+    };
+    let version = String::from_utf8_lossy(&output);
+    let version = version.trim();
+    Some(
+        version
+            .strip_prefix("Python ")
+            .unwrap_or(version)
+            .to_owned(),
+    )
+}
+
+fn default_prefix() -> Symbol {
+    symbol::PYTHON
+}
+
+fn default_project_files() -> Vec<String> {
+    vec!["pyproject.toml".into(), "requirements.txt".into()]
+}
+
+fn default_resolve_shims() -> bool {
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::virtual_env_name;
+    use std::path::Path;
+
+    #[test]
+    fn conventional_virtual_env_is_named_after_project() {
+        assert_eq!(
+            virtual_env_name(Path::new("/home/me/envs/ml")).as_deref(),
+            Some("ml")
+        );
+        assert_eq!(
+            virtual_env_name(Path::new("/home/me/site/.venv")).as_deref(),
+            Some("site")
+        );
+    }
+}
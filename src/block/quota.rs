@@ -0,0 +1,245 @@
+// Copyright (C) 2020 Stephane Raux. Distributed under the MIT license.
+
+use crate::{symbol, Block, Environment, Style, Symbol};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Shows the disk quota left to the user on the file system of the working directory, when it
+/// runs low
+///
+/// Quotas are only read on Linux when eliprompt is built with the `quota` feature. Nothing is
+/// shown otherwise.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Quota {
+    #[serde(default)]
+    style: Style,
+    #[serde(default = "default_prefix")]
+    prefix: Symbol,
+    #[serde(default = "default_threshold")]
+    threshold: f64,
+}
+
+impl Quota {
+    pub fn new() -> Self {
+        Quota {
+            style: Default::default(),
+            prefix: default_prefix(),
+            threshold: default_threshold(),
+        }
+    }
+
+    pub fn with_style<T>(self, style: T) -> Self
+    where
+        T: Into<Style>,
+    {
+        Self {
+            style: style.into(),
+            ..self
+        }
+    }
+
+    pub fn with_prefix<T>(self, prefix: T) -> Self
+    where
+        T: Into<Symbol>,
+    {
+        Self {
+            prefix: prefix.into(),
+            ..self
+        }
+    }
+
+    /// Sets the fraction of the quota that must be left for nothing to be shown. Defaults to
+    /// `0.1`.
+    pub fn with_threshold(self, threshold: f64) -> Self {
+        Self { threshold, ..self }
+    }
+
+    pub fn produce(&self, environment: &Environment) -> Vec<Block> {
+        let Some(usage) = environment.working_dir().and_then(user_quota) else {
+            return Vec::new();
+        };
+        let Some(left) = usage.left_below(self.threshold) else {
+            return Vec::new();
+        };
+        vec![
+            Block::new(self.prefix.get(environment.symbols())).with_style(&self.style),
+            Block::new(format_size(left)).with_style(&self.style),
+        ]
+    }
+}
+
+impl Default for Quota {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Disk usage of the user and their quota, in bytes
+#[derive(Clone, Copy, Debug)]
+struct Usage {
+    used: u64,
+    limit: u64,
+}
+
+impl Usage {
+    /// Returns the number of bytes left if they are less than `threshold` times the limit.
+    fn left_below(self, threshold: f64) -> Option<u64> {
+        let left = self.limit.saturating_sub(self.used);
+        (self.limit > 0 && (left as f64) < threshold * self.limit as f64).then_some(left)
+    }
+}
+
+#[cfg(all(feature = "quota", target_os = "linux"))]
+fn user_quota(dir: &Path) -> Option<Usage> {
+    linux::user_quota(dir)
+}
+
+#[cfg(not(all(feature = "quota", target_os = "linux")))]
+fn user_quota(_: &Path) -> Option<Usage> {
+    None
+}
+
+#[cfg(all(feature = "quota", target_os = "linux"))]
+mod linux {
+    use super::Usage;
+    use std::{
+        ffi::CString,
+        fs,
+        os::unix::ffi::OsStrExt,
+        path::{Path, PathBuf},
+    };
+
+    const Q_GETQUOTA: libc::c_int = 0x800007;
+    const USRQUOTA: libc::c_int = 0;
+    /// Size in bytes of the blocks in which limits are expressed
+    const QUOTA_BLOCK_SIZE: u64 = 1024;
+
+    /// Returns the quota of the user on the file system containing `dir`.
+    pub(super) fn user_quota(dir: &Path) -> Option<Usage> {
+        let mountinfo = fs::read_to_string("/proc/self/mountinfo").ok()?;
+        let device = mount_source(&mountinfo, dir)?;
+        let device = CString::new(device.as_os_str().as_bytes()).ok()?;
+        let uid = unsafe { libc::getuid() };
+        let mut quota = unsafe { std::mem::zeroed::<libc::dqblk>() };
+        // SAFETY: The device path is nul-terminated and `quota` has the layout expected by
+        // Q_GETQUOTA.
+        let res = unsafe {
+            libc::quotactl(
+                (Q_GETQUOTA << 8) | USRQUOTA,
+                device.as_ptr(),
+                uid as libc::c_int,
+                &mut quota as *mut libc::dqblk as *mut libc::c_char,
+            )
+        };
+        if res != 0 {
+            // Most file systems have no quota, which is not worth reporting.
+            return None;
+        }
+        let limit = match quota.dqb_bsoftlimit {
+            0 => quota.dqb_bhardlimit,
+            limit => limit,
+        };
+        Some(Usage {
+            used: quota.dqb_curspace,
+            limit: limit.saturating_mul(QUOTA_BLOCK_SIZE),
+        })
+    }
+
+    /// Returns the source of the innermost mount containing `dir`, e.g. `/dev/sda2`, from the
+    /// contents of `/proc/self/mountinfo`.
+    pub(super) fn mount_source(mountinfo: &str, dir: &Path) -> Option<PathBuf> {
+        mountinfo
+            .lines()
+            .filter_map(|line| {
+                let mut fields = line.split(' ');
+                let mount_point = unescape(fields.nth(4)?);
+                let source = fields.skip_while(|f| *f != "-").nth(2)?;
+                Some((PathBuf::from(mount_point), unescape(source)))
+            })
+            .filter(|(mount_point, _)| dir.starts_with(mount_point))
+            .max_by_key(|(mount_point, _)| mount_point.components().count())
+            .map(|(_, source)| PathBuf::from(source))
+    }
+
+    /// Decodes the octal escapes of mountinfo fields, e.g. `\040` for a space.
+    fn unescape(field: &str) -> String {
+        let mut out = String::new();
+        let mut rest = field;
+        while let Some(i) = rest.find('\\') {
+            out.push_str(&rest[..i]);
+            let code = rest
+                .get(i + 1..i + 4)
+                .and_then(|c| u8::from_str_radix(c, 8).ok());
+            match code {
+                Some(c) => {
+                    out.push(char::from(c));
+                    rest = &rest[i + 4..];
+                }
+                None => {
+                    out.push('\\');
+                    rest = &rest[i + 1..];
+                }
+            }
+        }
+        out.push_str(rest);
+        out
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::mount_source;
+        use std::path::Path;
+
+        #[test]
+        fn innermost_mount_is_found() {
+            let mountinfo = "\
+22 1 8:2 / / rw,relatime shared:1 - ext4 /dev/sda2 rw
+35 22 8:3 / /home rw,relatime shared:2 - xfs /dev/sda3 rw,usrquota
+36 35 0:40 / /home/my\\040files rw - nfs server:/export rw";
+            let source = |dir| mount_source(mountinfo, Path::new(dir));
+            assert_eq!(source("/home/user"), Some("/dev/sda3".into()));
+            assert_eq!(source("/home/my files/a"), Some("server:/export".into()));
+            assert_eq!(source("/etc"), Some("/dev/sda2".into()));
+        }
+    }
+}
+
+/// Formats a size in bytes with a binary unit, e.g. `1.5G`.
+fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "K", "M", "G", "T"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit + 1 < UNITS.len() {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 || size >= 10.0 {
+        format!("{:.0}{}", size, UNITS[unit])
+    } else {
+        format!("{:.1}{}", size, UNITS[unit])
+    }
+}
+
+fn default_prefix() -> Symbol {
+    symbol::QUOTA
+}
+
+fn default_threshold() -> f64 {
+    0.1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{format_size, Usage};
+
+    #[test]
+    fn low_quota_is_reported() {
+        let usage = |used| Usage { used, limit: 1000 };
+        assert_eq!(usage(950).left_below(0.1), Some(50));
+        assert_eq!(usage(1200).left_below(0.1), Some(0));
+        assert_eq!(usage(500).left_below(0.1), None);
+        assert_eq!(format_size(512), "512B");
+        assert_eq!(format_size(1536 * 1024 * 1024), "1.5G");
+        assert_eq!(format_size(20 * 1024 * 1024), "20M");
+    }
+}
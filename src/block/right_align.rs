@@ -0,0 +1,62 @@
+// Copyright (C) 2020 Stephane Raux. Distributed under the MIT license.
+
+use crate::{block::BlockKind, Block, BlockProducer, Environment};
+use serde::{Deserialize, Serialize};
+
+/// Shows the blocks of a producer flush to the right edge of the terminal, on the same line as
+/// the preceding blocks
+///
+/// The blocks are shown right after the preceding ones if the terminal width is unknown. If the
+/// line also has [`Fill`](super::Fill) blocks, they take the free columns instead, which also
+/// leaves the blocks at the right edge if they end the line.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct RightAlign {
+    producer: Box<BlockProducer>,
+}
+
+impl RightAlign {
+    pub fn new(producer: BlockProducer) -> Self {
+        RightAlign {
+            producer: Box::new(producer),
+        }
+    }
+
+    pub fn produce(&self, environment: &Environment) -> Vec<Block> {
+        let blocks = self.producer.produce(environment);
+        if blocks.is_empty() {
+            return blocks;
+        }
+        let padding = Block::new(" ").with_kind(BlockKind::Padding);
+        std::iter::once(padding).chain(blocks).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RightAlign;
+    use crate::{
+        block::{fill_lines, Fill, Text},
+        BlockProducer, Environment,
+    };
+
+    #[test]
+    fn blocks_end_at_right_edge() {
+        let environment = Environment::new(None);
+        let right = RightAlign::new(BlockProducer::Text(Text::new("12:00")));
+        let mut blocks = BlockProducer::Text(Text::new("~")).produce(&environment);
+        blocks.extend(right.produce(&environment));
+        let line = fill_lines(blocks.clone(), Some(10))
+            .into_iter()
+            .map(|b| b.text)
+            .collect::<String>();
+        assert_eq!(line, "~    12:00");
+        // A fill before the blocks takes all the free columns, leaving no gap.
+        let fill = Fill::new().with_contents("-").produce(&environment);
+        blocks.splice(1..1, fill);
+        let line = fill_lines(blocks, Some(10))
+            .into_iter()
+            .map(|b| b.text)
+            .collect::<String>();
+        assert_eq!(line, "~----12:00");
+    }
+}
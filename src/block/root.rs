@@ -0,0 +1,85 @@
+// Copyright (C) 2020 Stephane Raux. Distributed under the MIT license.
+
+use crate::{symbol, Block, Environment, Style, Symbol};
+use serde::{Deserialize, Serialize};
+
+/// Shows an indicator when running as root, or as Administrator on systems other than Unix
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Root {
+    #[serde(default = "default_style")]
+    style: Style,
+    #[serde(default = "default_contents")]
+    contents: Symbol,
+}
+
+impl Root {
+    pub fn new() -> Self {
+        Root {
+            style: default_style(),
+            contents: default_contents(),
+        }
+    }
+
+    pub fn with_style<T>(self, style: T) -> Self
+    where
+        T: Into<Style>,
+    {
+        Self {
+            style: style.into(),
+            ..self
+        }
+    }
+
+    pub fn with_contents<T>(self, contents: T) -> Self
+    where
+        T: Into<Symbol>,
+    {
+        Self {
+            contents: contents.into(),
+            ..self
+        }
+    }
+
+    pub fn produce(&self, environment: &Environment) -> Vec<Block> {
+        let contents = self.contents.get(environment.symbols());
+        if environment.is_root() && !contents.is_empty() {
+            vec![Block::new(contents).with_style(&self.style)]
+        } else {
+            Vec::new()
+        }
+    }
+}
+
+impl Default for Root {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn default_style() -> Style {
+    Style::new().with_fg(crate::color::CRIMSON)
+}
+
+fn default_contents() -> Symbol {
+    symbol::ROOT
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Root;
+    use crate::Environment;
+
+    #[test]
+    fn root_is_only_shown_as_root() {
+        let root = Root::new().with_contents("#");
+        let blocks = root.produce(&Environment::new(None).force_root(true));
+        assert_eq!(blocks[0].text, "#");
+        assert!(root
+            .produce(&Environment::new(None).force_root(false))
+            .is_empty());
+        assert!(root
+            .with_contents("")
+            .produce(&Environment::new(None).force_root(true))
+            .is_empty());
+    }
+}
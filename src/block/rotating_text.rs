@@ -0,0 +1,126 @@
+// Copyright (C) 2020 Stephane Raux. Distributed under the MIT license.
+
+use crate::{Block, Environment, Style};
+use serde::{Deserialize, Serialize};
+use std::{
+    hash::{DefaultHasher, Hash, Hasher},
+    time::{Duration, SystemTime},
+};
+
+/// Shows one of several texts, changing at regular intervals
+///
+/// The text shown depends only on the time, so that it stays the same within an interval, e.g.
+/// for a motivational snippet or a reminder.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct RotatingText {
+    #[serde(default)]
+    style: Style,
+    texts: Vec<String>,
+    #[serde(default)]
+    order: RotationOrder,
+    #[serde(with = "humantime_serde", default = "default_interval")]
+    interval: Duration,
+}
+
+impl RotatingText {
+    pub fn new<I, T>(texts: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<String>,
+    {
+        RotatingText {
+            style: Default::default(),
+            texts: texts.into_iter().map(Into::into).collect(),
+            order: Default::default(),
+            interval: default_interval(),
+        }
+    }
+
+    pub fn with_style<T>(self, style: T) -> Self
+    where
+        T: Into<Style>,
+    {
+        Self {
+            style: style.into(),
+            ..self
+        }
+    }
+
+    /// Sets the order in which texts are shown. Defaults to [`RotationOrder::Sequential`].
+    pub fn with_order(self, order: RotationOrder) -> Self {
+        Self { order, ..self }
+    }
+
+    /// Sets how long each text is shown. Defaults to one hour.
+    pub fn with_interval(self, interval: Duration) -> Self {
+        Self { interval, ..self }
+    }
+
+    pub fn produce(&self, _: &Environment) -> Vec<Block> {
+        let now = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default();
+        match self.text_at(now) {
+            Some(text) => vec![Block::new(text).with_style(&self.style)],
+            None => Vec::new(),
+        }
+    }
+
+    /// Returns the text shown `time` after the Unix epoch.
+    fn text_at(&self, time: Duration) -> Option<&str> {
+        if self.texts.is_empty() {
+            return None;
+        }
+        let slot = time.as_millis() / self.interval.as_millis().max(1);
+        let index = match self.order {
+            RotationOrder::Sequential => slot,
+            RotationOrder::Random => {
+                let mut hasher = DefaultHasher::new();
+                slot.hash(&mut hasher);
+                u128::from(hasher.finish())
+            }
+        };
+        let index = (index % self.texts.len() as u128) as usize;
+        Some(&self.texts[index])
+    }
+}
+
+/// Order in which [`RotatingText`] shows its texts
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum RotationOrder {
+    /// Texts are shown in the configured order.
+    #[default]
+    Sequential,
+    /// Texts are shown in an unpredictable order.
+    Random,
+}
+
+fn default_interval() -> Duration {
+    Duration::from_secs(60 * 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{RotatingText, RotationOrder};
+    use std::time::Duration;
+
+    #[test]
+    fn texts_rotate_at_each_interval() {
+        let rotating = RotatingText::new(["a", "b", "c"]).with_interval(Duration::from_secs(60));
+        let at = |secs| rotating.text_at(Duration::from_secs(secs));
+        assert_eq!(at(0), Some("a"));
+        assert_eq!(at(59), Some("a"));
+        assert_eq!(at(60), Some("b"));
+        assert_eq!(at(180), Some("a"));
+        let random = rotating.with_order(RotationOrder::Random);
+        assert_eq!(
+            random.text_at(Duration::from_secs(60)),
+            random.text_at(Duration::from_secs(119))
+        );
+        assert_eq!(
+            RotatingText::new::<_, String>([]).text_at(Duration::ZERO),
+            None
+        );
+    }
+}
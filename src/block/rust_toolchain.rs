@@ -0,0 +1,96 @@
+// Copyright (C) 2020 Stephane Raux. Distributed under the MIT license.
+
+use crate::{
+    rustup::{self, ToolchainSource},
+    symbol, Block, Environment, Style, Symbol,
+};
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct RustToolchain {
+    #[serde(default)]
+    style: Style,
+    #[serde(default = "default_prefix")]
+    prefix: Symbol,
+    #[serde(default = "default_override_style")]
+    override_style: Style,
+}
+
+impl RustToolchain {
+    pub fn new() -> Self {
+        RustToolchain {
+            style: Default::default(),
+            prefix: default_prefix(),
+            override_style: default_override_style(),
+        }
+    }
+
+    pub fn with_style<T>(self, style: T) -> Self
+    where
+        T: Into<Style>,
+    {
+        Self {
+            style: style.into(),
+            ..self
+        }
+    }
+
+    pub fn with_prefix<T>(self, prefix: T) -> Self
+    where
+        T: Into<Symbol>,
+    {
+        Self {
+            prefix: prefix.into(),
+            ..self
+        }
+    }
+
+    /// Sets the style used when the toolchain is overridden and differs from the default one.
+    pub fn with_override_style<T>(self, style: T) -> Self
+    where
+        T: Into<Style>,
+    {
+        Self {
+            override_style: style.into(),
+            ..self
+        }
+    }
+
+    pub fn produce(&self, environment: &Environment) -> Vec<Block> {
+        let Some(dir) = environment.working_dir() else {
+            return Vec::new();
+        };
+        if !dir.ancestors().any(|d| d.join("Cargo.toml").is_file()) {
+            return Vec::new();
+        }
+        let Some(toolchain) = rustup::active_toolchain(dir) else {
+            return Vec::new();
+        };
+        let name = rustup::short_name(&toolchain.name);
+        let overridden = toolchain.source != ToolchainSource::Default
+            && rustup::default_toolchain().is_some_and(|d| rustup::short_name(&d) != name);
+        let style = if overridden {
+            self.override_style.or(&self.style)
+        } else {
+            self.style.clone()
+        };
+        vec![
+            Block::new(self.prefix.get(environment.symbols())).with_style(&style),
+            Block::new(name).with_style(style),
+        ]
+    }
+}
+
+impl Default for RustToolchain {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn default_prefix() -> Symbol {
+    symbol::RUST
+}
+
+fn default_override_style() -> Style {
+    Style::new().with_fg(crate::color::GOLD)
+}
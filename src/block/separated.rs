@@ -1,6 +1,6 @@
 // Copyright (C) 2020 Stephane Raux. Distributed under the MIT license.
 
-use crate::{Block, BlockProducer, Environment, Style};
+use crate::{block::BlockKind, Block, BlockProducer, Environment, Style};
 use serde::{Deserialize, Serialize};
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -49,7 +49,11 @@ impl Separated {
             .fold(Vec::<Block>::new(), |mut acc, producer| {
                 let blocks = producer.produce(environment);
                 if !acc.is_empty() && !blocks.is_empty() {
-                    acc.push(Block::new(&self.separator).with_style(&self.separator_style));
+                    acc.push(
+                        Block::new(&self.separator)
+                            .with_style(&self.separator_style)
+                            .with_kind(BlockKind::Separator),
+                    );
                 }
                 acc.extend(blocks);
                 acc
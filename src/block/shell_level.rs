@@ -0,0 +1,98 @@
+// Copyright (C) 2020 Stephane Raux. Distributed under the MIT license.
+
+use crate::{symbol, Block, Environment, Style, Symbol};
+use serde::{Deserialize, Serialize};
+use std::env;
+
+/// Shows the nesting level of the shell from `SHLVL` when it exceeds a threshold, e.g. in a shell
+/// started from another shell
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ShellLevel {
+    #[serde(default)]
+    style: Style,
+    #[serde(default = "default_prefix")]
+    prefix: Symbol,
+    #[serde(default = "default_threshold")]
+    threshold: u32,
+}
+
+impl ShellLevel {
+    pub fn new() -> Self {
+        ShellLevel {
+            style: Default::default(),
+            prefix: default_prefix(),
+            threshold: default_threshold(),
+        }
+    }
+
+    pub fn with_style<T>(self, style: T) -> Self
+    where
+        T: Into<Style>,
+    {
+        Self {
+            style: style.into(),
+            ..self
+        }
+    }
+
+    pub fn with_prefix<T>(self, prefix: T) -> Self
+    where
+        T: Into<Symbol>,
+    {
+        Self {
+            prefix: prefix.into(),
+            ..self
+        }
+    }
+
+    /// Sets the level above which the level is shown. Defaults to 1.
+    pub fn with_threshold(self, threshold: u32) -> Self {
+        Self { threshold, ..self }
+    }
+
+    pub fn produce(&self, environment: &Environment) -> Vec<Block> {
+        let level = env::var("SHLVL")
+            .ok()
+            .and_then(|level| level.trim().parse::<u32>().ok());
+        match level {
+            Some(level) if level > self.threshold => vec![
+                Block::new(self.prefix.get(environment.symbols())).with_style(&self.style),
+                Block::new(level.to_string()).with_style(&self.style),
+            ],
+            _ => Vec::new(),
+        }
+    }
+}
+
+impl Default for ShellLevel {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn default_prefix() -> Symbol {
+    symbol::SHELL_LEVEL
+}
+
+fn default_threshold() -> u32 {
+    1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ShellLevel;
+    use crate::Environment;
+    use std::env;
+
+    #[test]
+    fn level_is_shown_above_threshold() {
+        let environment = Environment::new(None);
+        let level = ShellLevel::new().with_threshold(2);
+        env::set_var("SHLVL", "2");
+        assert!(level.produce(&environment).is_empty());
+        env::set_var("SHLVL", " 3\n");
+        assert_eq!(level.produce(&environment)[1].text, "3");
+        env::set_var("SHLVL", "nested");
+        assert!(level.produce(&environment).is_empty());
+    }
+}
@@ -0,0 +1,369 @@
+// Copyright (C) 2020 Stephane Raux. Distributed under the MIT license.
+
+use crate::{block::BlockKind, Block, BlockProducer, Environment};
+use serde::{Deserialize, Serialize};
+use std::{cmp::Reverse, ops::Range};
+
+/// Shows the blocks of several producers, shrinking the least important ones to fit in the
+/// terminal width
+///
+/// When the line is too wide, items are shrunk from the lowest priority to the highest until the
+/// line fits. An item is first swapped to its compact form if it has one, and then dropped. Among
+/// items of equal priority, the last ones are shrunk first. The whole line is measured once the
+/// prompt is produced, unless a maximum width is set, in which case only the blocks of the items
+/// must fit in it.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Shrink {
+    items: Vec<ShrinkItem>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    max_width: Option<usize>,
+}
+
+impl Shrink {
+    pub fn new<I>(items: I) -> Self
+    where
+        I: IntoIterator<Item = ShrinkItem>,
+    {
+        Shrink {
+            items: items.into_iter().collect(),
+            max_width: None,
+        }
+    }
+
+    /// Sets the width the blocks of the items must fit in, regardless of the rest of the line.
+    /// Defaults to the width left on the line.
+    pub fn with_max_width(self, max_width: usize) -> Self {
+        Self {
+            max_width: Some(max_width),
+            ..self
+        }
+    }
+
+    pub fn produce(&self, environment: &Environment) -> Vec<Block> {
+        match self.max_width {
+            Some(max_width) => self.produce_within(max_width, environment),
+            None => self.produce_marked(environment),
+        }
+    }
+
+    fn produce_within(&self, max_width: usize, environment: &Environment) -> Vec<Block> {
+        let mut produced = self
+            .items
+            .iter()
+            .map(|item| Some(item.producer.produce(environment)))
+            .collect::<Vec<_>>();
+        let fits = |produced: &[Option<Vec<Block>>]| {
+            widest_line(produced.iter().flatten().flatten()) <= max_width
+        };
+        let mut order = (0..self.items.len()).collect::<Vec<_>>();
+        order.sort_by_key(|&i| (self.items[i].priority, Reverse(i)));
+        for i in order {
+            if fits(&produced) {
+                break;
+            }
+            if let Some(compact) = &self.items[i].compact {
+                produced[i] = Some(compact.produce(environment));
+                if fits(&produced) {
+                    break;
+                }
+            }
+            produced[i] = None;
+        }
+        produced.into_iter().flatten().flatten().collect()
+    }
+
+    /// Produces the blocks of all items and their compact forms, delimited by marker blocks so
+    /// that [`shrink_lines`] picks the forms that fit.
+    fn produce_marked(&self, environment: &Environment) -> Vec<Block> {
+        let marker = |kind| Block::new("").with_kind(kind);
+        let mut blocks = Vec::new();
+        for item in &self.items {
+            let full = item.producer.produce(environment);
+            let compact = item.compact.as_ref().map(|c| c.produce(environment));
+            if full.is_empty() && compact.as_ref().is_none_or(Vec::is_empty) {
+                continue;
+            }
+            blocks.push(marker(BlockKind::ShrinkItem {
+                priority: item.priority,
+            }));
+            blocks.extend(full);
+            if let Some(compact) = compact {
+                blocks.push(marker(BlockKind::ShrinkCompact));
+                blocks.extend(compact);
+            }
+            blocks.push(marker(BlockKind::ShrinkEnd));
+        }
+        blocks
+    }
+}
+
+/// Producer shown by [`Shrink`], along with how to shrink it
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ShrinkItem {
+    producer: BlockProducer,
+    #[serde(default)]
+    priority: i32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    compact: Option<BlockProducer>,
+}
+
+impl ShrinkItem {
+    pub fn new(producer: BlockProducer) -> Self {
+        ShrinkItem {
+            producer,
+            priority: 0,
+            compact: None,
+        }
+    }
+
+    /// Sets the priority of the item. Items with a higher priority are shrunk last. Defaults to 0.
+    pub fn with_priority(self, priority: i32) -> Self {
+        Self { priority, ..self }
+    }
+
+    /// Sets the producer shown instead of the item when space runs out, before dropping it.
+    pub fn with_compact(self, compact: BlockProducer) -> Self {
+        Self {
+            compact: Some(compact),
+            ..self
+        }
+    }
+}
+
+/// Returns the width of the widest line of `blocks`, ignoring blocks filling lines.
+fn widest_line<'a, I>(blocks: I) -> usize
+where
+    I: IntoIterator<Item = &'a Block>,
+{
+    let mut widest = 0;
+    let mut width = 0;
+    for block in blocks {
+        match block.kind {
+            BlockKind::Newline => width = 0,
+            BlockKind::Fill | BlockKind::Padding => {}
+            _ => width += block.display_width(),
+        }
+        widest = widest.max(width);
+    }
+    widest
+}
+
+/// Shrinks the items produced by [`Shrink`] so that each line fits in `width` columns.
+///
+/// Items on lines that are too wide are shrunk from the lowest priority to the highest, the last
+/// ones first among items of equal priority. Items keep their full form if the width is unknown.
+pub(crate) fn shrink_lines(blocks: Vec<Block>, width: Option<usize>) -> Vec<Block> {
+    if !blocks.iter().any(|b| is_marker(b.kind)) {
+        return blocks;
+    }
+    let mut items = Vec::new();
+    let mut blocks = blocks.into_iter();
+    let mut segments = Vec::new();
+    loop {
+        let (mut parsed, end) = parse_segments(&mut blocks, &mut items);
+        segments.append(&mut parsed);
+        if end.is_none() {
+            break;
+        }
+    }
+    let mut forms = vec![Form::Full; items.len()];
+    if let Some(width) = width {
+        let mut order = (0..items.len()).collect::<Vec<_>>();
+        order.sort_by_key(|&id| (items[id].priority, Reverse(id)));
+        for id in order {
+            while lay_out(&segments, &forms).overflows(id, width) {
+                forms[id] = match forms[id] {
+                    Form::Full if items[id].has_compact => Form::Compact,
+                    _ => Form::Dropped,
+                };
+            }
+        }
+    }
+    lay_out(&segments, &forms)
+        .blocks
+        .into_iter()
+        .cloned()
+        .collect()
+}
+
+fn is_marker(kind: BlockKind) -> bool {
+    matches!(
+        kind,
+        BlockKind::ShrinkItem { .. } | BlockKind::ShrinkCompact | BlockKind::ShrinkEnd
+    )
+}
+
+/// Blocks of a line, with the items produced by [`Shrink`] in all their forms
+enum Segment {
+    Block(Block),
+    Item {
+        id: usize,
+        full: Vec<Segment>,
+        compact: Option<Vec<Segment>>,
+    },
+}
+
+struct ItemInfo {
+    priority: i32,
+    has_compact: bool,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Form {
+    Full,
+    Compact,
+    Dropped,
+}
+
+/// Parses segments until the end of the current item or form, returning the marker ending them.
+fn parse_segments<I>(blocks: &mut I, items: &mut Vec<ItemInfo>) -> (Vec<Segment>, Option<BlockKind>)
+where
+    I: Iterator<Item = Block>,
+{
+    let mut segments = Vec::new();
+    while let Some(block) = blocks.next() {
+        match block.kind {
+            BlockKind::ShrinkItem { priority } => {
+                let id = items.len();
+                items.push(ItemInfo {
+                    priority,
+                    has_compact: false,
+                });
+                let (full, end) = parse_segments(blocks, items);
+                let compact = (end == Some(BlockKind::ShrinkCompact))
+                    .then(|| parse_segments(blocks, items).0);
+                items[id].has_compact = compact.is_some();
+                segments.push(Segment::Item { id, full, compact });
+            }
+            kind @ (BlockKind::ShrinkCompact | BlockKind::ShrinkEnd) => {
+                return (segments, Some(kind))
+            }
+            _ => segments.push(Segment::Block(block)),
+        }
+    }
+    (segments, None)
+}
+
+/// Blocks shown for the chosen forms of the items
+struct Layout<'a> {
+    blocks: Vec<&'a Block>,
+    /// Range of the blocks shown for each item
+    spans: Vec<Option<Range<usize>>>,
+}
+
+impl Layout<'_> {
+    /// Indicates if the item `id` is shown on a line wider than `width`.
+    fn overflows(&self, id: usize, width: usize) -> bool {
+        let Some(span) = self.spans[id].clone() else {
+            return false;
+        };
+        let mut line_start = 0;
+        let mut line_width = 0;
+        for (i, block) in self.blocks.iter().enumerate() {
+            if block.kind == BlockKind::Newline {
+                if line_width > width && span.start <= i && line_start < span.end {
+                    return true;
+                }
+                line_start = i + 1;
+                line_width = 0;
+            } else if !matches!(block.kind, BlockKind::Fill | BlockKind::Padding) {
+                line_width += block.display_width();
+            }
+        }
+        line_width > width && line_start < span.end
+    }
+}
+
+fn lay_out<'a>(segments: &'a [Segment], forms: &[Form]) -> Layout<'a> {
+    fn visit<'a>(segments: &'a [Segment], forms: &[Form], layout: &mut Layout<'a>) {
+        for segment in segments {
+            match segment {
+                Segment::Block(block) => layout.blocks.push(block),
+                Segment::Item { id, full, compact } => {
+                    let start = layout.blocks.len();
+                    let shown = match forms[*id] {
+                        Form::Full => Some(full),
+                        Form::Compact => compact.as_ref(),
+                        Form::Dropped => None,
+                    };
+                    if let Some(shown) = shown {
+                        visit(shown, forms, layout);
+                    }
+                    let end = layout.blocks.len();
+                    layout.spans[*id] = (start < end).then_some(start..end);
+                }
+            }
+        }
+    }
+
+    let mut layout = Layout {
+        blocks: Vec::new(),
+        spans: vec![None; forms.len()],
+    };
+    visit(segments, forms, &mut layout);
+    layout
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{shrink_lines, Shrink, ShrinkItem};
+    use crate::{
+        block::{BlockKind, Text},
+        Block, BlockProducer, Environment,
+    };
+
+    fn texts(shrink: &Shrink) -> String {
+        shrink
+            .produce(&Environment::new(None))
+            .into_iter()
+            .map(|block| block.text)
+            .collect()
+    }
+
+    #[test]
+    fn low_priority_items_are_shrunk_first() {
+        let text = |s: &str| BlockProducer::Text(Text::new(s));
+        let shrink = |max_width| {
+            Shrink::new([
+                ShrinkItem::new(text("~/src/eliprompt ")).with_priority(2),
+                ShrinkItem::new(text("main ")).with_compact(text("m ")),
+                ShrinkItem::new(text("12:00")),
+            ])
+            .with_max_width(max_width)
+        };
+        assert_eq!(texts(&shrink(30)), "~/src/eliprompt main 12:00");
+        assert_eq!(texts(&shrink(24)), "~/src/eliprompt main ");
+        assert_eq!(texts(&shrink(18)), "~/src/eliprompt m ");
+        assert_eq!(texts(&shrink(17)), "~/src/eliprompt ");
+        assert_eq!(texts(&shrink(5)), "");
+    }
+
+    #[test]
+    fn items_are_shrunk_to_fit_the_whole_line() {
+        let text = |s: &str| BlockProducer::Text(Text::new(s));
+        let shrink = Shrink::new([
+            ShrinkItem::new(text("main ")).with_compact(text("m ")),
+            ShrinkItem::new(text("12:00")),
+        ]);
+        let line = |width| {
+            let environment = Environment::new(None);
+            let blocks = [Block::new("~/src/eliprompt ")]
+                .into_iter()
+                .chain(shrink.produce(&environment))
+                .chain([
+                    Block::new("\n").with_kind(BlockKind::Newline),
+                    Block::new("→"),
+                ])
+                .collect();
+            shrink_lines(blocks, width)
+                .into_iter()
+                .map(|block| block.text)
+                .collect::<String>()
+        };
+        assert_eq!(line(None), "~/src/eliprompt main 12:00\n→");
+        assert_eq!(line(Some(26)), "~/src/eliprompt main 12:00\n→");
+        assert_eq!(line(Some(24)), "~/src/eliprompt main \n→");
+        assert_eq!(line(Some(18)), "~/src/eliprompt m \n→");
+        assert_eq!(line(Some(10)), "~/src/eliprompt \n→");
+    }
+}
@@ -1,6 +1,6 @@
 // Copyright (C) 2020 Stephane Raux. Distributed under the MIT license.
 
-use crate::{Block, Environment};
+use crate::{block::BlockKind, Block, Environment};
 use serde::{Deserialize, Serialize};
 
 #[derive(Clone, Debug, Default, Deserialize, Serialize)]
@@ -8,6 +8,6 @@ pub struct Space;
 
 impl Space {
     pub fn produce(&self, _: &Environment) -> Vec<Block> {
-        vec![Block::new(" ")]
+        vec![Block::new(" ").with_kind(BlockKind::Space)]
     }
 }
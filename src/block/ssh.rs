@@ -0,0 +1,93 @@
+// Copyright (C) 2020 Stephane Raux. Distributed under the MIT license.
+
+use crate::{symbol, Block, Environment, Style, Symbol};
+use serde::{Deserialize, Serialize};
+
+/// Shows a badge in SSH sessions, optionally followed by the address of the client
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Ssh {
+    #[serde(default)]
+    style: Style,
+    #[serde(default = "default_contents")]
+    contents: Symbol,
+    #[serde(default)]
+    show_client: bool,
+    #[serde(default = "default_client_separator")]
+    client_separator: String,
+}
+
+impl Ssh {
+    pub fn new() -> Self {
+        Ssh {
+            style: Default::default(),
+            contents: default_contents(),
+            show_client: false,
+            client_separator: default_client_separator(),
+        }
+    }
+
+    pub fn with_style<T>(self, style: T) -> Self
+    where
+        T: Into<Style>,
+    {
+        Self {
+            style: style.into(),
+            ..self
+        }
+    }
+
+    pub fn with_contents<T>(self, contents: T) -> Self
+    where
+        T: Into<Symbol>,
+    {
+        Self {
+            contents: contents.into(),
+            ..self
+        }
+    }
+
+    /// Indicates if the address of the SSH client is shown after the badge.
+    pub fn show_client(self, yes: bool) -> Self {
+        Self {
+            show_client: yes,
+            ..self
+        }
+    }
+
+    pub fn with_client_separator<T>(self, separator: T) -> Self
+    where
+        T: Into<String>,
+    {
+        Self {
+            client_separator: separator.into(),
+            ..self
+        }
+    }
+
+    pub fn produce(&self, environment: &Environment) -> Vec<Block> {
+        if !environment.is_ssh_session() {
+            return Vec::new();
+        }
+        let mut blocks =
+            vec![Block::new(self.contents.get(environment.symbols())).with_style(&self.style)];
+        if let Some(client) = environment.ssh_client().filter(|_| self.show_client) {
+            blocks.push(Block::new(&self.client_separator).with_style(&self.style));
+            blocks.push(Block::new(client).with_style(&self.style));
+        }
+        blocks
+    }
+}
+
+impl Default for Ssh {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn default_contents() -> Symbol {
+    symbol::SSH
+}
+
+fn default_client_separator() -> String {
+    " ".into()
+}
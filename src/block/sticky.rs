@@ -0,0 +1,91 @@
+// Copyright (C) 2020 Stephane Raux. Distributed under the MIT license.
+
+use crate::{Block, BlockProducer, Environment};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, time::Duration, time::SystemTime};
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Sticky {
+    id: String,
+    #[serde(with = "humantime_serde")]
+    duration: Duration,
+    producer: Box<BlockProducer>,
+}
+
+impl Sticky {
+    pub fn new<T>(id: T, duration: Duration, producer: BlockProducer) -> Self
+    where
+        T: Into<String>,
+    {
+        Sticky {
+            id: id.into(),
+            duration,
+            producer: Box::new(producer),
+        }
+    }
+
+    pub fn produce(&self, environment: &Environment) -> Vec<Block> {
+        let blocks = self.producer.produce(environment);
+        let now = SystemTime::now();
+        let mut sticky = environment.sticky_blocks();
+        if !blocks.is_empty() {
+            sticky.0.insert(
+                self.id.clone(),
+                StickyEntry {
+                    blocks: blocks.clone(),
+                    produced_at: now,
+                },
+            );
+            return blocks;
+        }
+        match sticky.0.get(&self.id) {
+            Some(entry)
+                if now
+                    .duration_since(entry.produced_at)
+                    .is_ok_and(|age| age < self.duration) =>
+            {
+                entry.blocks.clone()
+            }
+            Some(_) => {
+                sticky.0.remove(&self.id);
+                Vec::new()
+            }
+            None => Vec::new(),
+        }
+    }
+}
+
+/// Blocks last produced by [`Sticky`] producers, persisted across prompts
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct StickyBlocks(BTreeMap<String, StickyEntry>);
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+struct StickyEntry {
+    blocks: Vec<Block>,
+    produced_at: SystemTime,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Sticky;
+    use crate::{
+        block::{Or, Text},
+        BlockProducer, Environment,
+    };
+    use std::time::Duration;
+
+    #[test]
+    fn blocks_are_kept_for_the_sticky_duration() {
+        let environment = Environment::new(None);
+        let sticky = |duration, producer| Sticky::new("id", duration, producer);
+        let long = Duration::from_secs(60);
+        let text = BlockProducer::Text(Text::new("a"));
+        let empty = || BlockProducer::Or(Or::default());
+        assert_eq!(sticky(long, text).produce(&environment)[0].text, "a");
+        assert_eq!(sticky(long, empty()).produce(&environment)[0].text, "a");
+        assert!(sticky(Duration::ZERO, empty())
+            .produce(&environment)
+            .is_empty());
+        assert!(sticky(long, empty()).produce(&environment).is_empty());
+    }
+}
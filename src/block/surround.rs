@@ -0,0 +1,119 @@
+// Copyright (C) 2020 Stephane Raux. Distributed under the MIT license.
+
+use crate::{Block, BlockProducer, Environment, Style, Symbol};
+use serde::{Deserialize, Serialize};
+
+/// Surrounds the blocks of a producer with text, e.g. brackets or padding
+///
+/// Nothing is added when the producer produces no blocks.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Surround {
+    producer: Box<BlockProducer>,
+    #[serde(default)]
+    prefix: Symbol,
+    #[serde(default)]
+    prefix_style: Style,
+    #[serde(default)]
+    suffix: Symbol,
+    #[serde(default)]
+    suffix_style: Style,
+}
+
+impl Surround {
+    pub fn new(producer: BlockProducer) -> Self {
+        Surround {
+            producer: Box::new(producer),
+            prefix: Symbol::default(),
+            prefix_style: Default::default(),
+            suffix: Symbol::default(),
+            suffix_style: Default::default(),
+        }
+    }
+
+    pub fn with_prefix<T>(self, prefix: T) -> Self
+    where
+        T: Into<Symbol>,
+    {
+        Self {
+            prefix: prefix.into(),
+            ..self
+        }
+    }
+
+    pub fn with_prefix_style<T>(self, style: T) -> Self
+    where
+        T: Into<Style>,
+    {
+        Self {
+            prefix_style: style.into(),
+            ..self
+        }
+    }
+
+    pub fn with_suffix<T>(self, suffix: T) -> Self
+    where
+        T: Into<Symbol>,
+    {
+        Self {
+            suffix: suffix.into(),
+            ..self
+        }
+    }
+
+    pub fn with_suffix_style<T>(self, style: T) -> Self
+    where
+        T: Into<Style>,
+    {
+        Self {
+            suffix_style: style.into(),
+            ..self
+        }
+    }
+
+    pub fn produce(&self, environment: &Environment) -> Vec<Block> {
+        let blocks = self.producer.produce(environment);
+        if blocks.is_empty() {
+            return blocks;
+        }
+        let (prefix, suffix) = (
+            self.prefix.get(environment.symbols()),
+            self.suffix.get(environment.symbols()),
+        );
+        let prefix =
+            (!prefix.is_empty()).then(|| Block::new(prefix).with_style(&self.prefix_style));
+        let suffix =
+            (!suffix.is_empty()).then(|| Block::new(suffix).with_style(&self.suffix_style));
+        prefix.into_iter().chain(blocks).chain(suffix).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Surround;
+    use crate::{
+        block::{Or, Text},
+        BlockProducer, Environment,
+    };
+
+    fn texts(surround: &Surround) -> Vec<String> {
+        surround
+            .produce(&Environment::new(None))
+            .into_iter()
+            .map(|block| block.text)
+            .collect()
+    }
+
+    #[test]
+    fn producer_is_surrounded() {
+        let surround = Surround::new(BlockProducer::Text(Text::new("main")))
+            .with_prefix("[")
+            .with_suffix("]");
+        assert_eq!(texts(&surround), ["[", "main", "]"]);
+    }
+
+    #[test]
+    fn nothing_is_added_when_producer_produces_nothing() {
+        let surround = Surround::new(BlockProducer::Or(Or::default())).with_prefix("[");
+        assert!(texts(&surround).is_empty());
+    }
+}
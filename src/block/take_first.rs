@@ -0,0 +1,102 @@
+// Copyright (C) 2020 Stephane Raux. Distributed under the MIT license.
+
+use crate::{block::BlockKind, Block, BlockProducer, Environment, Style};
+use serde::{Deserialize, Serialize};
+
+/// Shows the blocks of the first producers that produce any, up to a number of them
+///
+/// Producers are evaluated in order, and the remaining ones are skipped once enough of them
+/// produced blocks.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct TakeFirst {
+    count: usize,
+    producers: Vec<BlockProducer>,
+    #[serde(default)]
+    separator: String,
+    #[serde(default)]
+    separator_style: Style,
+}
+
+impl TakeFirst {
+    pub fn new<I>(count: usize, producers: I) -> Self
+    where
+        I: IntoIterator<Item = BlockProducer>,
+    {
+        TakeFirst {
+            count,
+            producers: producers.into_iter().collect(),
+            separator: String::new(),
+            separator_style: Default::default(),
+        }
+    }
+
+    /// Sets the text shown between the blocks of consecutive producers. Empty by default.
+    pub fn with_separator<T>(self, separator: T) -> Self
+    where
+        T: Into<String>,
+    {
+        Self {
+            separator: separator.into(),
+            ..self
+        }
+    }
+
+    pub fn with_separator_style<T>(self, style: T) -> Self
+    where
+        T: Into<Style>,
+    {
+        Self {
+            separator_style: style.into(),
+            ..self
+        }
+    }
+
+    pub fn produce(&self, environment: &Environment) -> Vec<Block> {
+        self.producers
+            .iter()
+            .map(|producer| producer.produce(environment))
+            .filter(|blocks| !blocks.is_empty())
+            .take(self.count)
+            .fold(Vec::new(), |mut acc, blocks| {
+                if !acc.is_empty() && !self.separator.is_empty() {
+                    acc.push(
+                        Block::new(&self.separator)
+                            .with_style(&self.separator_style)
+                            .with_kind(BlockKind::Separator),
+                    );
+                }
+                acc.extend(blocks);
+                acc
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TakeFirst;
+    use crate::{
+        block::{Or, Text},
+        BlockProducer, Environment,
+    };
+
+    #[test]
+    fn only_first_non_empty_producers_are_shown() {
+        let text = |s: &str| BlockProducer::Text(Text::new(s));
+        let take_first = TakeFirst::new(
+            2,
+            [
+                BlockProducer::Or(Or::default()),
+                text("a"),
+                text("b"),
+                text("c"),
+            ],
+        )
+        .with_separator(" ");
+        let texts = take_first
+            .produce(&Environment::new(None))
+            .into_iter()
+            .map(|block| block.text)
+            .collect::<Vec<_>>();
+        assert_eq!(texts, ["a", " ", "b"]);
+    }
+}
@@ -0,0 +1,160 @@
+// Copyright (C) 2020 Stephane Raux. Distributed under the MIT license.
+
+use crate::{symbol, Block, Environment, Pattern, Style, Symbol};
+use serde::{Deserialize, Serialize};
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+/// Shows the current Terraform or OpenTofu workspace in Terraform directories
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Terraform {
+    #[serde(default)]
+    style: Style,
+    #[serde(default = "default_prefix")]
+    prefix: Symbol,
+    #[serde(default)]
+    rules: Vec<WorkspaceStyle>,
+}
+
+impl Terraform {
+    pub fn new() -> Self {
+        Terraform {
+            style: Default::default(),
+            prefix: default_prefix(),
+            rules: Vec::new(),
+        }
+    }
+
+    pub fn with_style<T>(self, style: T) -> Self
+    where
+        T: Into<Style>,
+    {
+        Self {
+            style: style.into(),
+            ..self
+        }
+    }
+
+    pub fn with_prefix<T>(self, prefix: T) -> Self
+    where
+        T: Into<Symbol>,
+    {
+        Self {
+            prefix: prefix.into(),
+            ..self
+        }
+    }
+
+    /// Uses `style` for workspaces matching `pattern`, e.g. production workspaces. The first
+    /// matching rule applies.
+    pub fn with_rule<T>(mut self, pattern: Pattern, style: T) -> Self
+    where
+        T: Into<Style>,
+    {
+        self.rules.push(WorkspaceStyle {
+            pattern,
+            style: style.into(),
+        });
+        self
+    }
+
+    pub fn produce(&self, environment: &Environment) -> Vec<Block> {
+        let Some(dir) = environment.working_dir() else {
+            return Vec::new();
+        };
+        let data_dir = env::var_os("TF_DATA_DIR")
+            .map(|d| dir.join(d))
+            .unwrap_or_else(|| dir.join(".terraform"));
+        if !data_dir.is_dir() && !has_configuration(dir) {
+            return Vec::new();
+        }
+        let workspace = workspace(&data_dir);
+        let style = self
+            .rules
+            .iter()
+            .find(|rule| rule.pattern.is_match(&workspace))
+            .map_or(&self.style, |rule| &rule.style);
+        vec![
+            Block::new(self.prefix.get(environment.symbols())).with_style(style),
+            Block::new(workspace).with_style(style),
+        ]
+    }
+}
+
+impl Default for Terraform {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+struct WorkspaceStyle {
+    pattern: Pattern,
+    style: Style,
+}
+
+/// Indicates if `dir` contains Terraform or OpenTofu configuration files.
+fn has_configuration(dir: &Path) -> bool {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return false;
+    };
+    entries.filter_map(Result::ok).any(|entry| {
+        let path = PathBuf::from(entry.file_name());
+        path.extension()
+            .is_some_and(|ext| ext == "tf" || ext == "tofu")
+    })
+}
+
+/// Returns the selected workspace, from `TF_WORKSPACE` or the `environment` file of the data
+/// directory.
+fn workspace(data_dir: &Path) -> String {
+    env::var("TF_WORKSPACE")
+        .ok()
+        .or_else(|| fs::read_to_string(data_dir.join("environment")).ok())
+        .map(|w| w.trim().to_owned())
+        .filter(|w| !w.is_empty())
+        .unwrap_or_else(|| "default".into())
+}
+
+fn default_prefix() -> Symbol {
+    symbol::TERRAFORM
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Terraform;
+    use crate::{color, Environment, Pattern, Style};
+    use std::{env, fs};
+
+    #[test]
+    fn workspace_follows_variables_and_rules() {
+        let dir = tempfile::tempdir().unwrap();
+        let environment = Environment::new(Some(dir.path().to_owned()));
+        let prod = Style::new().with_fg(color::CRIMSON);
+        let terraform = Terraform::new()
+            .with_rule(Pattern::new("^prod").unwrap(), prod.clone())
+            .with_rule(Pattern::new("").unwrap(), Style::new());
+        let workspace = || {
+            let blocks = terraform.produce(&environment);
+            blocks.get(1).map(|b| (b.text.clone(), b.style.clone()))
+        };
+        env::remove_var("TF_WORKSPACE");
+        env::remove_var("TF_DATA_DIR");
+        assert_eq!(workspace(), None);
+        fs::write(dir.path().join("main.tf"), "").unwrap();
+        assert_eq!(workspace(), Some(("default".into(), Style::new())));
+        fs::create_dir_all(dir.path().join(".terraform")).unwrap();
+        fs::write(dir.path().join(".terraform/environment"), "staging").unwrap();
+        fs::create_dir_all(dir.path().join("data")).unwrap();
+        fs::write(dir.path().join("data/environment"), "prod-eu\n").unwrap();
+        assert_eq!(workspace(), Some(("staging".into(), Style::new())));
+        env::set_var("TF_DATA_DIR", "data");
+        assert_eq!(workspace(), Some(("prod-eu".into(), prod)));
+        env::set_var("TF_WORKSPACE", "ci");
+        assert_eq!(workspace(), Some(("ci".into(), Style::new())));
+        env::remove_var("TF_WORKSPACE");
+        env::remove_var("TF_DATA_DIR");
+    }
+}
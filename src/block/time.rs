@@ -0,0 +1,135 @@
+// Copyright (C) 2020 Stephane Raux. Distributed under the MIT license.
+
+use crate::{symbol, Block, Environment, Style, Symbol};
+use jiff::{tz::TimeZone, Zoned};
+use serde::{Deserialize, Serialize};
+
+/// Shows the time at which the prompt was generated, e.g. `14:03:27`
+///
+/// Each prompt then records when the previous command finished.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Time {
+    #[serde(default)]
+    style: Style,
+    #[serde(default = "default_prefix")]
+    prefix: Symbol,
+    #[serde(default = "default_format")]
+    format: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    time_zone: Option<String>,
+}
+
+impl Time {
+    pub fn new() -> Self {
+        Time {
+            style: Default::default(),
+            prefix: default_prefix(),
+            format: default_format(),
+            time_zone: None,
+        }
+    }
+
+    pub fn with_style<T>(self, style: T) -> Self
+    where
+        T: Into<Style>,
+    {
+        Self {
+            style: style.into(),
+            ..self
+        }
+    }
+
+    pub fn with_prefix<T>(self, prefix: T) -> Self
+    where
+        T: Into<Symbol>,
+    {
+        Self {
+            prefix: prefix.into(),
+            ..self
+        }
+    }
+
+    /// Sets the strftime-style format of the time. Defaults to `%H:%M:%S`.
+    pub fn with_format<T>(self, format: T) -> Self
+    where
+        T: Into<String>,
+    {
+        Self {
+            format: format.into(),
+            ..self
+        }
+    }
+
+    /// Shows the time in the time zone with the given IANA name, e.g. `America/New_York`,
+    /// instead of the system time zone.
+    pub fn with_time_zone<T>(self, time_zone: T) -> Self
+    where
+        T: Into<String>,
+    {
+        Self {
+            time_zone: Some(time_zone.into()),
+            ..self
+        }
+    }
+
+    pub fn produce(&self, environment: &Environment) -> Vec<Block> {
+        let Some(time) = format_now(&self.format, self.time_zone.as_deref()) else {
+            return Vec::new();
+        };
+        vec![
+            Block::new(self.prefix.get(environment.symbols())).with_style(&self.style),
+            Block::new(time).with_style(&self.style),
+        ]
+    }
+}
+
+impl Default for Time {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Formats the current time with a strftime-style `format`, in the time zone named `time_zone`
+/// or in the system time zone.
+///
+/// Failures are logged.
+pub(super) fn format_now(format: &str, time_zone: Option<&str>) -> Option<String> {
+    let now = match time_zone.map(TimeZone::get).transpose() {
+        Ok(Some(tz)) => Zoned::now().with_time_zone(tz),
+        Ok(None) => Zoned::now(),
+        Err(e) => {
+            tracing::error!("Unknown time zone: {}", e);
+            return None;
+        }
+    };
+    match jiff::fmt::strtime::format(format, &now) {
+        Ok(time) => Some(time),
+        Err(e) => {
+            tracing::error!("Failed to format time: {}", e);
+            None
+        }
+    }
+}
+
+fn default_prefix() -> Symbol {
+    symbol::TIME
+}
+
+fn default_format() -> String {
+    "%H:%M:%S".into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::format_now;
+
+    #[test]
+    fn time_is_formatted_in_time_zone() {
+        assert_eq!(
+            format_now("%z", Some("Asia/Kolkata")).as_deref(),
+            Some("+0530")
+        );
+        assert_eq!(format_now("%z", Some("UTC")).as_deref(), Some("+0000"));
+        assert_eq!(format_now("%z", Some("Nowhere/Atlantis")), None);
+    }
+}
@@ -0,0 +1,77 @@
+// Copyright (C) 2020 Stephane Raux. Distributed under the MIT license.
+
+use crate::{Block, BlockProducer, Environment};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Shows the blocks of a producer if it completes in time, or a placeholder otherwise
+///
+/// The deadline applies on top of the prompt timeout. Past it, producers nested in the producer
+/// are skipped and programs they run are killed, so that one slow producer does not hold up the
+/// rest of the prompt. In-process work, e.g. computing a git status, cannot be interrupted.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Timeout {
+    #[serde(with = "humantime_serde")]
+    timeout: Duration,
+    producer: Box<BlockProducer>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    placeholder: Option<Box<BlockProducer>>,
+}
+
+impl Timeout {
+    pub fn new(timeout: Duration, producer: BlockProducer) -> Self {
+        Timeout {
+            timeout,
+            producer: Box::new(producer),
+            placeholder: None,
+        }
+    }
+
+    /// Shows the blocks of `placeholder` when the producer does not complete in time.
+    pub fn with_placeholder(self, placeholder: BlockProducer) -> Self {
+        Self {
+            placeholder: Some(Box::new(placeholder)),
+            ..self
+        }
+    }
+
+    pub fn produce(&self, environment: &Environment) -> Vec<Block> {
+        let cancellation = environment.cancellation().limited_to(self.timeout);
+        let blocks = environment
+            .with_cancellation_scope(cancellation.clone(), || self.producer.produce(environment));
+        if !cancellation.is_cancelled() || environment.is_cancelled() {
+            return blocks;
+        }
+        tracing::warn!("Producer timed out after {:?}", self.timeout);
+        match &self.placeholder {
+            Some(placeholder) => placeholder.produce(environment),
+            None => Vec::new(),
+        }
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::Timeout;
+    use crate::{
+        block::{Command, Text},
+        BlockProducer, Environment,
+    };
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn placeholder_is_shown_when_producer_is_too_slow() {
+        let environment = Environment::new(None);
+        let slow = Command::new("sleep")
+            .with_args(["5"])
+            .with_timeout(Duration::from_secs(10));
+        let timeout = Timeout::new(Duration::from_millis(50), BlockProducer::Command(slow))
+            .with_placeholder(BlockProducer::Text(Text::new("…")));
+        let start = Instant::now();
+        let blocks = timeout.produce(&environment);
+        assert!(start.elapsed() < Duration::from_secs(5));
+        assert_eq!(blocks.len(), 1);
+        assert_eq!(blocks[0].text, "…");
+        assert!(!environment.is_cancelled());
+    }
+}
@@ -0,0 +1,193 @@
+// Copyright (C) 2020 Stephane Raux. Distributed under the MIT license.
+
+use crate::{symbol, Block, Environment, Style, Symbol};
+use serde::{Deserialize, Serialize};
+use std::{env, fs, path::PathBuf};
+
+/// Shows the number of pending tasks from Taskwarrior or a `todo.txt` file
+///
+/// Counts are cached until the task data changes.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Todo {
+    #[serde(default)]
+    style: Style,
+    #[serde(default = "default_prefix")]
+    prefix: Symbol,
+    #[serde(default)]
+    source: TodoSource,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    filter: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    todo_file: Option<PathBuf>,
+}
+
+impl Todo {
+    pub fn new() -> Self {
+        Todo {
+            style: Default::default(),
+            prefix: default_prefix(),
+            source: Default::default(),
+            filter: None,
+            todo_file: None,
+        }
+    }
+
+    pub fn with_style<T>(self, style: T) -> Self
+    where
+        T: Into<Style>,
+    {
+        Self {
+            style: style.into(),
+            ..self
+        }
+    }
+
+    pub fn with_prefix<T>(self, prefix: T) -> Self
+    where
+        T: Into<Symbol>,
+    {
+        Self {
+            prefix: prefix.into(),
+            ..self
+        }
+    }
+
+    pub fn with_source(self, source: TodoSource) -> Self {
+        Self { source, ..self }
+    }
+
+    /// Only counts the tasks matching `filter`.
+    ///
+    /// For Taskwarrior, this is a filter as given to `task`, e.g. `project:work due:today`. For
+    /// `todo.txt`, tasks must contain every word of the filter, e.g. `+work @phone`.
+    pub fn with_filter<T>(self, filter: T) -> Self
+    where
+        T: Into<String>,
+    {
+        Self {
+            filter: Some(filter.into()),
+            ..self
+        }
+    }
+
+    /// Sets the path of the `todo.txt` file. Defaults to `$TODO_FILE`, or `todo.txt` in the home
+    /// directory.
+    pub fn with_todo_file<T>(self, path: T) -> Self
+    where
+        T: Into<PathBuf>,
+    {
+        Self {
+            todo_file: Some(path.into()),
+            ..self
+        }
+    }
+
+    pub fn produce(&self, environment: &Environment) -> Vec<Block> {
+        let filter = self.filter.as_deref().unwrap_or_default();
+        let task = match self.source {
+            TodoSource::Auto | TodoSource::Taskwarrior => crate::process::find_program("task"),
+            TodoSource::TodoTxt => None,
+        };
+        let count = match task {
+            Some(task) => {
+                let data_dir = env::var_os("TASKDATA")
+                    .map(PathBuf::from)
+                    .or_else(|| Some(dirs::home_dir()?.join(".task")))
+                    .unwrap_or_default();
+                let inputs = [
+                    task.as_path(),
+                    &data_dir.join("pending.data"),
+                    &data_dir.join("taskchampion.sqlite3"),
+                ];
+                let name = format!("taskwarrior-count:{}", filter);
+                crate::process::cached_probe_with(environment, &name, &inputs, || {
+                    crate::process::output(
+                        environment
+                            .command(&task)
+                            .args(["rc.verbose=nothing", "rc.gc=off"])
+                            .args(filter.split_whitespace())
+                            .args(["status:pending", "count"]),
+                        &environment.cancellation(),
+                    )
+                })
+            }
+            None if self.source == TodoSource::Taskwarrior => None,
+            None => {
+                let Some(path) = self.todo_file() else {
+                    return Vec::new();
+                };
+                let name = format!("todo-txt-count:{}", filter);
+                crate::process::cached_probe_with(environment, &name, &[&path], || {
+                    let contents = fs::read_to_string(&path).ok()?;
+                    Some(count_todo_txt(&contents, filter).to_string())
+                })
+            }
+        };
+        let Some(count) = count.filter(|count| !matches!(count.as_str(), "" | "0")) else {
+            return Vec::new();
+        };
+        vec![
+            Block::new(self.prefix.get(environment.symbols())).with_style(&self.style),
+            Block::new(count).with_style(&self.style),
+        ]
+    }
+
+    fn todo_file(&self) -> Option<PathBuf> {
+        self.todo_file
+            .clone()
+            .or_else(|| env::var_os("TODO_FILE").map(PathBuf::from))
+            .or_else(|| Some(dirs::home_dir()?.join("todo.txt")))
+    }
+}
+
+impl Default for Todo {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Source of tasks
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TodoSource {
+    /// Taskwarrior if installed, `todo.txt` otherwise
+    #[default]
+    Auto,
+    Taskwarrior,
+    TodoTxt,
+}
+
+/// Counts the pending tasks of a `todo.txt` file containing every word of `filter`.
+fn count_todo_txt(contents: &str, filter: &str) -> usize {
+    contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with("x "))
+        .filter(|line| {
+            let words = line.split_whitespace().collect::<Vec<_>>();
+            filter.split_whitespace().all(|term| words.contains(&term))
+        })
+        .count()
+}
+
+fn default_prefix() -> Symbol {
+    symbol::TODO
+}
+
+#[cfg(test)]
+mod tests {
+    use super::count_todo_txt;
+
+    #[test]
+    fn pending_todo_txt_tasks_are_counted() {
+        let contents = "(A) Call mom @phone\n\
+            x 2024-01-02 Pay rent +home\n\
+            \n\
+            Fix bug +work @desk\n\
+            Review PR +work\n";
+        assert_eq!(count_todo_txt(contents, ""), 3);
+        assert_eq!(count_todo_txt(contents, "+work"), 2);
+        assert_eq!(count_todo_txt(contents, "+work @desk"), 1);
+        assert_eq!(count_todo_txt(contents, "+home"), 0);
+    }
+}
@@ -0,0 +1,245 @@
+// Copyright (C) 2020 Stephane Raux. Distributed under the MIT license.
+
+use crate::{Block, Environment, Pattern, Style, Symbol};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Shows the version of a tool described entirely by the configuration
+///
+/// This allows showing versions for ecosystems without a dedicated block.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ToolVersion {
+    #[serde(default)]
+    style: Style,
+    #[serde(default)]
+    prefix: Symbol,
+    #[serde(default)]
+    project_files: Vec<String>,
+    source: VersionSource,
+    #[serde(default = "default_pattern")]
+    pattern: Pattern,
+    #[serde(default = "default_resolve_shims")]
+    resolve_shims: bool,
+}
+
+/// Where the version of a tool is read from
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub enum VersionSource {
+    /// Standard output of a command, given as the program followed by its arguments, e.g.
+    /// `["node", "--version"]`
+    Command(Vec<String>),
+    /// File found in the working directory or one of its ancestors, e.g. `.nvmrc`
+    File(String),
+}
+
+impl ToolVersion {
+    pub fn new(source: VersionSource) -> Self {
+        ToolVersion {
+            style: Default::default(),
+            prefix: Symbol::default(),
+            project_files: Vec::new(),
+            source,
+            pattern: default_pattern(),
+            resolve_shims: default_resolve_shims(),
+        }
+    }
+
+    pub fn with_style<T>(self, style: T) -> Self
+    where
+        T: Into<Style>,
+    {
+        Self {
+            style: style.into(),
+            ..self
+        }
+    }
+
+    pub fn with_prefix<T>(self, prefix: T) -> Self
+    where
+        T: Into<Symbol>,
+    {
+        Self {
+            prefix: prefix.into(),
+            ..self
+        }
+    }
+
+    /// Only shows the version if one of `files` is found in the working directory or one of its
+    /// ancestors.
+    pub fn with_project_files<I>(self, files: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        Self {
+            project_files: files.into_iter().map(Into::into).collect(),
+            ..self
+        }
+    }
+
+    /// Sets the regular expression extracting the version. Its first group is the version if it
+    /// has groups, otherwise the whole match is.
+    pub fn with_pattern(self, pattern: Pattern) -> Self {
+        Self { pattern, ..self }
+    }
+
+    /// Indicates if the version selected by pyenv, rbenv, nodenv or nvm should be found from
+    /// their files instead of running the command, which is slow for shims. This only applies to
+    /// commands querying the version of the interpreter itself, e.g. `["python", "--version"]`.
+    /// Defaults to `true`.
+    pub fn resolve_shims(self, yes: bool) -> Self {
+        Self {
+            resolve_shims: yes,
+            ..self
+        }
+    }
+
+    pub fn produce(&self, environment: &Environment) -> Vec<Block> {
+        let Some(dir) = environment.working_dir() else {
+            return Vec::new();
+        };
+        let is_project = self.project_files.is_empty()
+            || dir
+                .ancestors()
+                .any(|dir| self.project_files.iter().any(|f| dir.join(f).is_file()));
+        if !is_project {
+            return Vec::new();
+        }
+        let text = match &self.source {
+            VersionSource::Command(command) => {
+                command_output(environment, command, self.resolve_shims)
+            }
+            VersionSource::File(name) => read_file(dir, name).map(VersionText::Output),
+        };
+        let Some(version) = text.as_ref().and_then(|t| t.version(&self.pattern)) else {
+            return Vec::new();
+        };
+        vec![
+            Block::new(self.prefix.get(environment.symbols())).with_style(&self.style),
+            Block::new(version).with_style(&self.style),
+        ]
+    }
+}
+
+/// Text the version is read from
+#[derive(Debug)]
+enum VersionText {
+    /// Output of the command or contents of the file, which the pattern applies to
+    Output(String),
+    /// Version selected by a version manager
+    Resolved(String),
+}
+
+impl VersionText {
+    fn version(&self, pattern: &Pattern) -> Option<&str> {
+        match self {
+            VersionText::Output(text) => extract_version(pattern, text),
+            VersionText::Resolved(version) => Some(version),
+        }
+    }
+}
+
+fn command_output(
+    environment: &Environment,
+    command: &[String],
+    resolve_shims: bool,
+) -> Option<VersionText> {
+    let (program, args) = command.split_first()?;
+    let path = if program.contains(std::path::is_separator) {
+        PathBuf::from(program)
+    } else {
+        crate::process::find_program(program)?
+    };
+    let resolved = environment
+        .working_dir()
+        .filter(|_| resolve_shims && is_version_query(args))
+        .and_then(|dir| crate::shim::resolved_version(&path, dir));
+    if let Some(version) = resolved {
+        return Some(VersionText::Resolved(version));
+    }
+    let name = format!("tool-version:{}", command.join(" "));
+    crate::process::cached_probe(environment, &name, &path, || {
+        crate::process::output(
+            environment.command(&path).args(args),
+            &environment.cancellation(),
+        )
+    })
+    .map(VersionText::Output)
+}
+
+/// Indicates if `args` only ask a program for its version, e.g. `--version`.
+fn is_version_query(args: &[String]) -> bool {
+    matches!(args, [arg] if ["--version", "-v", "-V"].contains(&arg.as_str()))
+}
+
+fn read_file(dir: &Path, name: &str) -> Option<String> {
+    let path = dir
+        .ancestors()
+        .map(|dir| dir.join(name))
+        .find(|path| path.is_file())?;
+    match fs::read_to_string(&path) {
+        Ok(contents) => Some(contents),
+        Err(e) => {
+            tracing::error!("Failed to read {}: {}", path.display(), e);
+            None
+        }
+    }
+}
+
+fn extract_version<'a>(pattern: &Pattern, text: &'a str) -> Option<&'a str> {
+    let captures = pattern.captures(text)?;
+    captures
+        .get(1)
+        .or_else(|| captures.get(0))
+        .map(|m| m.as_str())
+}
+
+fn default_resolve_shims() -> bool {
+    true
+}
+
+fn default_pattern() -> Pattern {
+    Pattern::new(r"\d+(?:\.\d+)*").expect("Default version pattern is valid")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{default_pattern, extract_version, is_version_query, VersionText};
+    use crate::Pattern;
+
+    #[test]
+    fn version_is_extracted() {
+        assert_eq!(
+            extract_version(&default_pattern(), "v20.11.1\n"),
+            Some("20.11.1")
+        );
+        let pattern = Pattern::new(r"ruby (\S+)").unwrap();
+        assert_eq!(
+            extract_version(&pattern, "ruby 3.3.0p0 (2023-12-25)"),
+            Some("3.3.0p0")
+        );
+        assert_eq!(extract_version(&default_pattern(), "none"), None);
+    }
+
+    #[test]
+    fn only_version_queries_are_resolved() {
+        let args = |args: &[&str]| args.iter().map(|&a| a.to_owned()).collect::<Vec<_>>();
+        assert!(is_version_query(&args(&["--version"])));
+        assert!(is_version_query(&args(&["-v"])));
+        assert!(!is_version_query(&args(&[])));
+        assert!(!is_version_query(&args(&["-c", "import sys"])));
+        assert!(!is_version_query(&args(&["--version", "--verbose"])));
+    }
+
+    #[test]
+    fn pattern_only_applies_to_output() {
+        let pattern = Pattern::new(r"ruby (\S+)").unwrap();
+        let output = VersionText::Output("ruby 3.3.0p0".into());
+        assert_eq!(output.version(&pattern), Some("3.3.0p0"));
+        let resolved = VersionText::Resolved("3.3.0".into());
+        assert_eq!(resolved.version(&pattern), Some("3.3.0"));
+    }
+}
@@ -0,0 +1,118 @@
+// Copyright (C) 2020 Stephane Raux. Distributed under the MIT license.
+
+use crate::{block::BlockKind, Block, BlockProducer, Environment, Pattern};
+use serde::{Deserialize, Serialize};
+
+/// Applies text transformations to the blocks of a producer
+///
+/// Transformations apply in order to the text of each content block. Separators, spaces and
+/// newlines are left unchanged.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Transform {
+    producer: Box<BlockProducer>,
+    transforms: Vec<TextTransform>,
+}
+
+impl Transform {
+    pub fn new<I>(producer: BlockProducer, transforms: I) -> Self
+    where
+        I: IntoIterator<Item = TextTransform>,
+    {
+        Transform {
+            producer: Box::new(producer),
+            transforms: transforms.into_iter().collect(),
+        }
+    }
+
+    pub fn produce(&self, environment: &Environment) -> Vec<Block> {
+        self.producer
+            .produce(environment)
+            .into_iter()
+            .map(|block| match block.kind {
+                BlockKind::Content => self.apply(block),
+                _ => block,
+            })
+            .collect()
+    }
+
+    fn apply(&self, block: Block) -> Block {
+        let text = self
+            .transforms
+            .iter()
+            .fold(block.text.clone(), |text, transform| transform.apply(text));
+        if text == block.text {
+            return block;
+        }
+        // Break hints point into the original text.
+        Block {
+            text,
+            break_hints: Vec::new(),
+            ..block
+        }
+    }
+}
+
+/// Transformation of the text of a block
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub enum TextTransform {
+    Uppercase,
+    Lowercase,
+    /// Replaces all matches of the pattern. The replacement can refer to capture groups, e.g.
+    /// `$1`.
+    Replace {
+        pattern: Pattern,
+        replacement: String,
+    },
+    /// Removes the prefix if the text starts with it.
+    StripPrefix(String),
+    /// Removes the suffix if the text ends with it.
+    StripSuffix(String),
+}
+
+impl TextTransform {
+    pub fn apply(&self, text: String) -> String {
+        match self {
+            TextTransform::Uppercase => text.to_uppercase(),
+            TextTransform::Lowercase => text.to_lowercase(),
+            TextTransform::Replace {
+                pattern,
+                replacement,
+            } => pattern
+                .replace_all(&text, replacement.as_str())
+                .into_owned(),
+            TextTransform::StripPrefix(prefix) => match text.strip_prefix(prefix.as_str()) {
+                Some(rest) => rest.to_owned(),
+                None => text,
+            },
+            TextTransform::StripSuffix(suffix) => match text.strip_suffix(suffix.as_str()) {
+                Some(rest) => rest.to_owned(),
+                None => text,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TextTransform;
+    use crate::Pattern;
+
+    #[test]
+    fn transforms_apply_in_order() {
+        let transforms = [
+            TextTransform::StripPrefix("feature/".into()),
+            TextTransform::Replace {
+                pattern: Pattern::new(r"^(\w+)-(\d+)").unwrap(),
+                replacement: "$1#$2".into(),
+            },
+            TextTransform::Uppercase,
+        ];
+        let apply = |text: &str| {
+            transforms
+                .iter()
+                .fold(text.to_owned(), |text, t| t.apply(text))
+        };
+        assert_eq!(apply("feature/proj-123-login"), "PROJ#123-LOGIN");
+        assert_eq!(apply("main"), "MAIN");
+    }
+}
@@ -0,0 +1,125 @@
+// Copyright (C) 2020 Stephane Raux. Distributed under the MIT license.
+
+use crate::{symbol, Block, BlockProducer, Environment, Symbol};
+use serde::{Deserialize, Serialize};
+use unicode_width::UnicodeWidthStr;
+
+/// Shortens the blocks of a producer so that together they fit in a maximum width
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Truncate {
+    producer: Box<BlockProducer>,
+    max_width: usize,
+    #[serde(default = "default_ellipsis")]
+    ellipsis: Symbol,
+    #[serde(default)]
+    side: TruncationSide,
+}
+
+impl Truncate {
+    pub fn new(producer: BlockProducer, max_width: usize) -> Self {
+        Truncate {
+            producer: Box::new(producer),
+            max_width,
+            ellipsis: default_ellipsis(),
+            side: Default::default(),
+        }
+    }
+
+    pub fn with_ellipsis<T>(self, ellipsis: T) -> Self
+    where
+        T: Into<Symbol>,
+    {
+        Self {
+            ellipsis: ellipsis.into(),
+            ..self
+        }
+    }
+
+    /// Sets the side from which text is removed. Defaults to the end.
+    pub fn with_side(self, side: TruncationSide) -> Self {
+        Self { side, ..self }
+    }
+
+    pub fn produce(&self, environment: &Environment) -> Vec<Block> {
+        let blocks = self.producer.produce(environment);
+        let ellipsis = self.ellipsis.get(environment.symbols());
+        truncate(blocks, self.max_width, ellipsis, self.side)
+    }
+}
+
+/// Side of the text that is removed when it is too wide
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TruncationSide {
+    Start,
+    #[default]
+    End,
+}
+
+/// Removes text from `side` of `blocks` so that their combined width fits in `max_width` columns.
+///
+/// Blocks fitting entirely are kept, the first one that does not is cut and marked with
+/// `ellipsis`, and the ones beyond it are dropped.
+fn truncate(
+    mut blocks: Vec<Block>,
+    max_width: usize,
+    ellipsis: &str,
+    side: TruncationSide,
+) -> Vec<Block> {
+    let total = blocks.iter().map(Block::display_width).sum::<usize>();
+    if total <= max_width {
+        return blocks;
+    }
+    if side == TruncationSide::Start {
+        blocks.reverse();
+    }
+    let mut budget = max_width.saturating_sub(UnicodeWidthStr::width(ellipsis));
+    let mut truncated = Vec::new();
+    for block in blocks {
+        let width = block.display_width();
+        if width > budget {
+            truncated.push(match side {
+                TruncationSide::Start => block.cut_start(budget, ellipsis),
+                TruncationSide::End => block.cut_end(budget, ellipsis),
+            });
+            break;
+        }
+        budget -= width;
+        truncated.push(block);
+    }
+    if side == TruncationSide::Start {
+        truncated.reverse();
+    }
+    truncated
+}
+
+fn default_ellipsis() -> Symbol {
+    symbol::ELLIPSIS
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{truncate, TruncationSide};
+    use crate::Block;
+
+    fn texts(blocks: &[Block]) -> Vec<&str> {
+        blocks.iter().map(|b| b.text.as_str()).collect()
+    }
+
+    #[test]
+    fn combined_width_is_truncated() {
+        let blocks = || {
+            vec![
+                Block::new("\u{e725} "),
+                Block::new("feature/very-long-branch-name").with_break_hints_at("/"),
+                Block::new(" ↑2"),
+            ]
+        };
+        let end = truncate(blocks(), 12, "…", TruncationSide::End);
+        assert_eq!(texts(&end), ["\u{e725} ", "feature…"]);
+        let start = truncate(blocks(), 12, "…", TruncationSide::Start);
+        assert_eq!(texts(&start), ["…nch-name", " ↑2"]);
+        let short = truncate(blocks(), 40, "…", TruncationSide::End);
+        assert_eq!(texts(&short).len(), 3);
+    }
+}
@@ -1,14 +1,19 @@
 // Copyright (C) 2020 Stephane Raux. Distributed under the MIT license.
 
-use crate::{Block, Environment, Style};
+use crate::{Block, Environment, Style, Symbol, Template};
 use serde::{Deserialize, Serialize};
+use std::env;
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct Username {
     #[serde(default)]
     style: Style,
     #[serde(default = "default_prefix")]
-    prefix: String,
+    prefix: Symbol,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    format: Option<Template>,
+    #[serde(default = "default_env_vars")]
+    env_vars: Vec<String>,
 }
 
 impl Username {
@@ -16,6 +21,8 @@ impl Username {
         Username {
             style: Default::default(),
             prefix: default_prefix(),
+            format: None,
+            env_vars: default_env_vars(),
         }
     }
 
@@ -31,7 +38,7 @@ impl Username {
 
     pub fn with_prefix<T>(self, prefix: T) -> Self
     where
-        T: Into<String>,
+        T: Into<Symbol>,
     {
         Self {
             prefix: prefix.into(),
@@ -39,11 +46,39 @@ impl Username {
         }
     }
 
-    pub fn produce(&self, _: &Environment) -> Vec<Block> {
-        vec![
-            Block::new(&self.prefix).with_style(&self.style),
-            Block::new(whoami::username()).with_style(&self.style),
-        ]
+    pub fn with_format(self, format: Template) -> Self {
+        Self {
+            format: Some(format),
+            ..self
+        }
+    }
+
+    /// Sets the environment variables to take the username from, in order of preference, before
+    /// falling back to the actual username.
+    pub fn with_env_vars<I>(self, vars: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        Self {
+            env_vars: vars.into_iter().map(Into::into).collect(),
+            ..self
+        }
+    }
+
+    pub fn produce(&self, environment: &Environment) -> Vec<Block> {
+        let username = self
+            .env_vars
+            .iter()
+            .find_map(|var| env::var(var).ok().filter(|v| !v.is_empty()))
+            .unwrap_or_else(whoami::username);
+        match &self.format {
+            Some(format) => vec![Block::new(format.render(&username)).with_style(&self.style)],
+            None => vec![
+                Block::new(self.prefix.get(environment.symbols())).with_style(&self.style),
+                Block::new(username).with_style(&self.style),
+            ],
+        }
     }
 }
 
@@ -53,6 +88,39 @@ impl Default for Username {
     }
 }
 
-fn default_prefix() -> String {
+fn default_prefix() -> Symbol {
     "".into()
 }
+
+fn default_env_vars() -> Vec<String> {
+    vec!["ELIPROMPT_USER".into()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Username;
+    use crate::Environment;
+    use std::env;
+
+    #[test]
+    fn first_non_empty_variable_overrides_username() {
+        env::set_var("ELIPROMPT_TEST_USER_EMPTY", "");
+        env::set_var("ELIPROMPT_TEST_USER", "alice");
+        let username = |vars: &[&str]| {
+            Username::new()
+                .with_env_vars(vars.iter().copied())
+                .produce(&Environment::new(None))[1]
+                .text
+                .clone()
+        };
+        assert_eq!(
+            username(&[
+                "ELIPROMPT_TEST_USER_UNSET",
+                "ELIPROMPT_TEST_USER_EMPTY",
+                "ELIPROMPT_TEST_USER"
+            ]),
+            "alice"
+        );
+        assert_eq!(username(&["ELIPROMPT_TEST_USER_EMPTY"]), whoami::username());
+    }
+}
@@ -0,0 +1,80 @@
+// Copyright (C) 2020 Stephane Raux. Distributed under the MIT license.
+
+use crate::{symbol, Block, Environment, Style, Symbol};
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct VcsHead {
+    #[serde(default)]
+    style: Style,
+    #[serde(default = "default_prefix")]
+    prefix: Symbol,
+    #[serde(default)]
+    show_name: bool,
+}
+
+impl VcsHead {
+    pub fn new() -> Self {
+        VcsHead {
+            style: Default::default(),
+            prefix: default_prefix(),
+            show_name: false,
+        }
+    }
+
+    pub fn with_style<T>(self, style: T) -> Self
+    where
+        T: Into<Style>,
+    {
+        Self {
+            style: style.into(),
+            ..self
+        }
+    }
+
+    pub fn with_prefix<T>(self, prefix: T) -> Self
+    where
+        T: Into<Symbol>,
+    {
+        Self {
+            prefix: prefix.into(),
+            ..self
+        }
+    }
+
+    /// Shows the name of the version control system before the head, e.g. `git:main`.
+    pub fn show_name(self, yes: bool) -> Self {
+        Self {
+            show_name: yes,
+            ..self
+        }
+    }
+
+    pub fn produce(&self, environment: &Environment) -> Vec<Block> {
+        let Some(vcs) = environment.vcs() else {
+            return Vec::new();
+        };
+        let Some(head) = vcs.head() else {
+            return Vec::new();
+        };
+        let text = if self.show_name {
+            format!("{}:{}", vcs.name(), head)
+        } else {
+            head
+        };
+        vec![
+            Block::new(self.prefix.get(environment.symbols())).with_style(&self.style),
+            Block::new(text).with_style(&self.style),
+        ]
+    }
+}
+
+impl Default for VcsHead {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn default_prefix() -> Symbol {
+    symbol::VCS_HEAD
+}
@@ -0,0 +1,195 @@
+// Copyright (C) 2020 Stephane Raux. Distributed under the MIT license.
+
+use crate::{symbol, Block, Environment, Pattern, Style, Symbol};
+use serde::{Deserialize, Serialize};
+
+/// Shows an indicator when a VPN is active, and optionally another one when it is not
+///
+/// A VPN is considered active when a network interface whose name matches `interfaces` has an
+/// address, or, if `command` is set, when the command succeeds.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Vpn {
+    #[serde(default)]
+    style: Style,
+    #[serde(default = "default_prefix")]
+    prefix: Symbol,
+    #[serde(default = "default_interfaces")]
+    interfaces: Pattern,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    command: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    disconnected_contents: Option<String>,
+    #[serde(default)]
+    disconnected_style: Style,
+}
+
+impl Vpn {
+    pub fn new() -> Self {
+        Vpn {
+            style: Default::default(),
+            prefix: default_prefix(),
+            interfaces: default_interfaces(),
+            command: None,
+            disconnected_contents: None,
+            disconnected_style: Default::default(),
+        }
+    }
+
+    pub fn with_style<T>(self, style: T) -> Self
+    where
+        T: Into<Style>,
+    {
+        Self {
+            style: style.into(),
+            ..self
+        }
+    }
+
+    pub fn with_prefix<T>(self, prefix: T) -> Self
+    where
+        T: Into<Symbol>,
+    {
+        Self {
+            prefix: prefix.into(),
+            ..self
+        }
+    }
+
+    /// Sets the pattern matching the names of VPN interfaces.
+    pub fn with_interfaces(self, pattern: Pattern) -> Self {
+        Self {
+            interfaces: pattern,
+            ..self
+        }
+    }
+
+    /// Sets the program and arguments of a command succeeding when the VPN is active. Its output,
+    /// if any, is shown after the prefix.
+    pub fn with_command<I>(self, command: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        Self {
+            command: Some(command.into_iter().map(Into::into).collect()),
+            ..self
+        }
+    }
+
+    /// Sets the text shown when no VPN is active.
+    pub fn with_disconnected_contents<T>(self, contents: T) -> Self
+    where
+        T: Into<String>,
+    {
+        Self {
+            disconnected_contents: Some(contents.into()),
+            ..self
+        }
+    }
+
+    pub fn with_disconnected_style<T>(self, style: T) -> Self
+    where
+        T: Into<Style>,
+    {
+        Self {
+            disconnected_style: style.into(),
+            ..self
+        }
+    }
+
+    pub fn produce(&self, environment: &Environment) -> Vec<Block> {
+        let active = match &self.command {
+            Some(command) => self.run_command(command, environment),
+            None => super::local_ip::interface_addresses()
+                .into_iter()
+                .map(|(name, _)| name)
+                .find(|name| self.interfaces.is_match(name)),
+        };
+        match (active, &self.disconnected_contents) {
+            (Some(name), _) => {
+                let mut blocks = vec![
+                    Block::new(self.prefix.get(environment.symbols())).with_style(&self.style)
+                ];
+                if !name.is_empty() {
+                    blocks.push(Block::new(name).with_style(&self.style));
+                }
+                blocks
+            }
+            (None, Some(contents)) => {
+                vec![Block::new(contents).with_style(&self.disconnected_style)]
+            }
+            (None, None) => Vec::new(),
+        }
+    }
+
+    /// Runs the configured command and returns its output if it succeeds.
+    fn run_command(&self, command: &[String], environment: &Environment) -> Option<String> {
+        let Some((program, args)) = command.split_first() else {
+            tracing::error!("VPN command is empty");
+            return None;
+        };
+        let output = crate::process::run(
+            environment.command(program).args(args),
+            &environment.cancellation(),
+        );
+        match output {
+            Ok(output) if output.status.success() => {
+                Some(String::from_utf8_lossy(&output.stdout).trim().to_owned())
+            }
+            Ok(_) => None,
+            Err(e) => {
+                tracing::error!("Failed to run {}: {}", program, e);
+                None
+            }
+        }
+    }
+}
+
+impl Default for Vpn {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn default_prefix() -> Symbol {
+    symbol::VPN
+}
+
+fn default_interfaces() -> Pattern {
+    Pattern::new(r"^(tun|tap|wg|ppp|ipsec|gpd)\d*$").expect("Default VPN pattern is valid")
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::Vpn;
+    use crate::{Environment, Pattern};
+
+    fn texts(vpn: Vpn) -> Vec<String> {
+        vpn.with_prefix("vpn")
+            .with_disconnected_contents("off")
+            .produce(&Environment::new(None))
+            .into_iter()
+            .map(|b| b.text)
+            .collect()
+    }
+
+    #[test]
+    fn vpn_is_detected_from_interfaces() {
+        let vpn = Vpn::new().with_interfaces(Pattern::new("^lo0?$").unwrap());
+        let active = texts(vpn);
+        assert_eq!(active[0], "vpn");
+        assert!(active[1].starts_with("lo"));
+        assert_eq!(
+            texts(Vpn::new().with_interfaces(Pattern::new("^$").unwrap())),
+            ["off"]
+        );
+    }
+
+    #[test]
+    fn vpn_is_detected_with_command() {
+        let vpn = Vpn::new().with_command(["sh", "-c", "echo corp"]);
+        assert_eq!(texts(vpn), ["vpn", "corp"]);
+        assert_eq!(texts(Vpn::new().with_command(["true"])), ["vpn"]);
+        assert_eq!(texts(Vpn::new().with_command(["false"])), ["off"]);
+    }
+}
@@ -0,0 +1,165 @@
+// Copyright (C) 2020 Stephane Raux. Distributed under the MIT license.
+
+use crate::{
+    cache,
+    process::{fetch_in_background, file_age},
+    Block, Environment, Style, Symbol,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+/// Shows the current weather, as reported by [wttr.in](https://wttr.in) by default
+///
+/// Conditions are fetched in the background with `curl` and cached, so that the prompt never
+/// waits for the network. The cached conditions are shown until they are refreshed, and nothing
+/// is shown before they are first fetched or if caching is disabled.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Weather {
+    #[serde(default)]
+    style: Style,
+    #[serde(default)]
+    prefix: Symbol,
+    #[serde(default)]
+    location: String,
+    #[serde(default = "default_url")]
+    url: String,
+    #[serde(with = "humantime_serde", default = "default_cache_duration")]
+    cache_duration: Duration,
+}
+
+impl Weather {
+    pub fn new() -> Self {
+        Weather {
+            style: Default::default(),
+            prefix: Symbol::default(),
+            location: String::new(),
+            url: default_url(),
+            cache_duration: default_cache_duration(),
+        }
+    }
+
+    pub fn with_style<T>(self, style: T) -> Self
+    where
+        T: Into<Style>,
+    {
+        Self {
+            style: style.into(),
+            ..self
+        }
+    }
+
+    pub fn with_prefix<T>(self, prefix: T) -> Self
+    where
+        T: Into<Symbol>,
+    {
+        Self {
+            prefix: prefix.into(),
+            ..self
+        }
+    }
+
+    /// Sets the location, e.g. `Paris` or an airport code. Defaults to the location guessed by
+    /// the service.
+    pub fn with_location<T>(self, location: T) -> Self
+    where
+        T: Into<String>,
+    {
+        Self {
+            location: location.into(),
+            ..self
+        }
+    }
+
+    /// Sets the URL of the service responding with the conditions as plain text. `{location}` is
+    /// replaced with the location. Defaults to `https://wttr.in/{location}?format=%c%t`.
+    pub fn with_url<T>(self, url: T) -> Self
+    where
+        T: Into<String>,
+    {
+        Self {
+            url: url.into(),
+            ..self
+        }
+    }
+
+    /// Sets how long the conditions are cached before being fetched again.
+    pub fn with_cache_duration(self, cache_duration: Duration) -> Self {
+        Self {
+            cache_duration,
+            ..self
+        }
+    }
+
+    pub fn produce(&self, environment: &Environment) -> Vec<Block> {
+        let Some(dir) = environment.cache_dir() else {
+            return Vec::new();
+        };
+        let url = self
+            .url
+            .replace("{location}", &encode_location(&self.location));
+        let path = dir
+            .join("weather")
+            .join(PathBuf::from(cache::file_name(Path::new(&url))).with_extension("txt"));
+        let expired = file_age(&path).is_none_or(|age| age >= self.cache_duration);
+        if expired && !environment.is_local_only() {
+            fetch_in_background(&url, &path);
+        }
+        let conditions = fs::read_to_string(&path).unwrap_or_default();
+        let conditions = conditions.lines().next().unwrap_or_default().trim();
+        if conditions.is_empty() {
+            return Vec::new();
+        }
+        let blocks = vec![
+            Block::new(self.prefix.get(environment.symbols())).with_style(&self.style),
+            Block::new(conditions).with_style(&self.style),
+        ];
+        if expired {
+            environment.annotate_local_only(blocks)
+        } else {
+            blocks
+        }
+    }
+}
+
+impl Default for Weather {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Encodes `location` for use in a URL path, e.g. `New York` becomes `New+York`.
+fn encode_location(location: &str) -> String {
+    location
+        .trim()
+        .bytes()
+        .map(|b| match b {
+            b' ' => "+".to_owned(),
+            b if b.is_ascii_alphanumeric() || b"-_.~,".contains(&b) => char::from(b).to_string(),
+            b => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+fn default_url() -> String {
+    "https://wttr.in/{location}?format=%c%t".into()
+}
+
+fn default_cache_duration() -> Duration {
+    Duration::from_secs(30 * 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::encode_location;
+
+    #[test]
+    fn locations_are_encoded() {
+        assert_eq!(encode_location(""), "");
+        assert_eq!(encode_location(" New York "), "New+York");
+        assert_eq!(encode_location("São Paulo"), "S%C3%A3o+Paulo");
+    }
+}
@@ -0,0 +1,99 @@
+// Copyright (C) 2020 Stephane Raux. Distributed under the MIT license.
+
+use crate::{symbol, Block, Environment, Style, Symbol};
+use jiff::civil;
+use serde::{Deserialize, Serialize};
+
+/// Shows the ISO week number and the day of the year, e.g. `W42 D289`
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct WeekNumber {
+    #[serde(default)]
+    style: Style,
+    #[serde(default = "default_prefix")]
+    prefix: Symbol,
+    #[serde(default = "default_show_day_of_year")]
+    show_day_of_year: bool,
+}
+
+impl WeekNumber {
+    pub fn new() -> Self {
+        WeekNumber {
+            style: Default::default(),
+            prefix: default_prefix(),
+            show_day_of_year: default_show_day_of_year(),
+        }
+    }
+
+    pub fn with_style<T>(self, style: T) -> Self
+    where
+        T: Into<Style>,
+    {
+        Self {
+            style: style.into(),
+            ..self
+        }
+    }
+
+    pub fn with_prefix<T>(self, prefix: T) -> Self
+    where
+        T: Into<Symbol>,
+    {
+        Self {
+            prefix: prefix.into(),
+            ..self
+        }
+    }
+
+    /// Indicates if the day of the year is shown after the week number. Defaults to `true`.
+    pub fn show_day_of_year(self, yes: bool) -> Self {
+        Self {
+            show_day_of_year: yes,
+            ..self
+        }
+    }
+
+    pub fn produce(&self, environment: &Environment) -> Vec<Block> {
+        vec![
+            Block::new(self.prefix.get(environment.symbols())).with_style(&self.style),
+            Block::new(self.text(jiff::Zoned::now().date())).with_style(&self.style),
+        ]
+    }
+
+    fn text(&self, date: civil::Date) -> String {
+        let week = date.iso_week_date().week();
+        if self.show_day_of_year {
+            format!("W{:02} D{:03}", week, date.day_of_year())
+        } else {
+            format!("W{:02}", week)
+        }
+    }
+}
+
+impl Default for WeekNumber {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn default_prefix() -> Symbol {
+    symbol::DATE
+}
+
+fn default_show_day_of_year() -> bool {
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::WeekNumber;
+    use jiff::civil::date;
+
+    #[test]
+    fn weeks_follow_iso_numbering() {
+        let block = WeekNumber::new();
+        assert_eq!(block.text(date(2021, 1, 1)), "W53 D001");
+        assert_eq!(block.text(date(2024, 12, 30)), "W01 D365");
+        let block = block.show_day_of_year(false);
+        assert_eq!(block.text(date(2026, 10, 16)), "W42");
+    }
+}
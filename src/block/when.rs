@@ -0,0 +1,120 @@
+// Copyright (C) 2020 Stephane Raux. Distributed under the MIT license.
+
+use crate::{Block, BlockProducer, Environment};
+use serde::{Deserialize, Serialize};
+use std::env;
+
+/// Shows the blocks of a producer if a condition holds, or of another producer otherwise
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct When {
+    condition: Condition,
+    producer: Box<BlockProducer>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    otherwise: Option<Box<BlockProducer>>,
+}
+
+impl When {
+    pub fn new(condition: Condition, producer: BlockProducer) -> Self {
+        When {
+            condition,
+            producer: Box::new(producer),
+            otherwise: None,
+        }
+    }
+
+    /// Shows the blocks of `producer` when the condition does not hold.
+    pub fn with_otherwise(self, producer: BlockProducer) -> Self {
+        Self {
+            otherwise: Some(Box::new(producer)),
+            ..self
+        }
+    }
+
+    pub fn produce(&self, environment: &Environment) -> Vec<Block> {
+        if self.condition.holds(environment) {
+            self.producer.produce(environment)
+        } else if let Some(otherwise) = &self.otherwise {
+            otherwise.produce(environment)
+        } else {
+            Vec::new()
+        }
+    }
+}
+
+/// Condition tested by [`When`]
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub enum Condition {
+    /// The previous command exited with one of the codes.
+    ExitCode(Vec<i32>),
+    /// The previous command failed.
+    Failed,
+    /// The working directory contains a file matching one of the glob patterns, e.g. `*.py`.
+    Files(Vec<String>),
+    /// The environment variable is set to a non-empty value.
+    EnvVar(String),
+    /// The working directory is in a git repository.
+    GitRepo,
+    /// The condition does not hold.
+    Not(Box<Condition>),
+    /// All the conditions hold.
+    All(Vec<Condition>),
+    /// Any of the conditions holds.
+    Any(Vec<Condition>),
+}
+
+impl Condition {
+    pub fn holds(&self, environment: &Environment) -> bool {
+        match self {
+            Condition::ExitCode(codes) => codes.contains(&environment.prev_exit_code()),
+            Condition::Failed => environment.prev_exit_code() != 0,
+            Condition::Files(patterns) => environment.working_dir().is_some_and(|dir| {
+                patterns
+                    .iter()
+                    .any(|p| has_match(&dir.to_string_lossy(), p))
+            }),
+            Condition::EnvVar(name) => env::var_os(name).is_some_and(|v| !v.is_empty()),
+            Condition::GitRepo => environment.git().is_some(),
+            Condition::Not(condition) => !condition.holds(environment),
+            Condition::All(conditions) => conditions.iter().all(|c| c.holds(environment)),
+            Condition::Any(conditions) => conditions.iter().any(|c| c.holds(environment)),
+        }
+    }
+}
+
+/// Indicates if a path relative to `dir` matches the glob `pattern`.
+///
+/// Invalid patterns are logged.
+fn has_match(dir: &str, pattern: &str) -> bool {
+    let pattern = format!("{}/{}", glob::Pattern::escape(dir), pattern);
+    match glob::glob(&pattern) {
+        Ok(mut paths) => paths.any(|path| path.is_ok()),
+        Err(e) => {
+            tracing::error!("Invalid glob pattern {}: {}", pattern, e);
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Condition;
+    use crate::Environment;
+    use std::fs;
+
+    #[test]
+    fn conditions_are_evaluated() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("setup.py"), "").unwrap();
+        let environment = Environment::new(Some(dir.path().to_owned())).with_prev_exit_code(2);
+        let files = |pattern: &str| Condition::Files(vec![pattern.into()]);
+        assert!(files("*.py").holds(&environment));
+        assert!(!files("*.rs").holds(&environment));
+        assert!(Condition::ExitCode(vec![1, 2]).holds(&environment));
+        let condition = Condition::All(vec![
+            Condition::Failed,
+            Condition::Not(Box::new(files("Cargo.toml"))),
+        ]);
+        assert!(condition.holds(&environment));
+        assert!(!Condition::Any(Vec::new()).holds(&environment));
+    }
+}
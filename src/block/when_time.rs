@@ -0,0 +1,228 @@
+// Copyright (C) 2020 Stephane Raux. Distributed under the MIT license.
+
+use crate::{Block, BlockProducer, Environment};
+use jiff::civil::{self, DateTime};
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt::{self, Display},
+    str::FromStr,
+};
+use thiserror::Error;
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct WhenTime {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    ranges: Vec<TimeRange>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    days: Vec<Weekday>,
+    #[serde(default)]
+    invert: bool,
+    producer: Box<BlockProducer>,
+}
+
+impl WhenTime {
+    pub fn new(producer: BlockProducer) -> Self {
+        WhenTime {
+            ranges: Vec::new(),
+            days: Vec::new(),
+            invert: false,
+            producer: Box::new(producer),
+        }
+    }
+
+    pub fn with_ranges<I>(self, ranges: I) -> Self
+    where
+        I: IntoIterator<Item = TimeRange>,
+    {
+        Self {
+            ranges: ranges.into_iter().collect(),
+            ..self
+        }
+    }
+
+    pub fn with_days<I>(self, days: I) -> Self
+    where
+        I: IntoIterator<Item = Weekday>,
+    {
+        Self {
+            days: days.into_iter().collect(),
+            ..self
+        }
+    }
+
+    /// Renders the producer outside of the configured times instead.
+    pub fn invert(self, yes: bool) -> Self {
+        Self {
+            invert: yes,
+            ..self
+        }
+    }
+
+    pub fn produce(&self, environment: &Environment) -> Vec<Block> {
+        let now = jiff::Zoned::now().datetime();
+        if self.is_active(now) {
+            self.producer.produce(environment)
+        } else {
+            Vec::new()
+        }
+    }
+
+    fn is_active(&self, now: DateTime) -> bool {
+        let day_matches = self.days.is_empty() || self.days.contains(&Weekday::from(now.weekday()));
+        let minute = TimeOfDay::from(now.time());
+        let time_matches = self.ranges.is_empty() || self.ranges.iter().any(|r| r.contains(minute));
+        (day_matches && time_matches) != self.invert
+    }
+}
+
+/// Day of the week
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Weekday {
+    Mon,
+    Tue,
+    Wed,
+    Thu,
+    Fri,
+    Sat,
+    Sun,
+}
+
+impl From<civil::Weekday> for Weekday {
+    fn from(day: civil::Weekday) -> Self {
+        match day {
+            civil::Weekday::Monday => Weekday::Mon,
+            civil::Weekday::Tuesday => Weekday::Tue,
+            civil::Weekday::Wednesday => Weekday::Wed,
+            civil::Weekday::Thursday => Weekday::Thu,
+            civil::Weekday::Friday => Weekday::Fri,
+            civil::Weekday::Saturday => Weekday::Sat,
+            civil::Weekday::Sunday => Weekday::Sun,
+        }
+    }
+}
+
+/// Range of times of day, e.g. `09:00-17:00`
+///
+/// The end is excluded. A range ending before it starts spans midnight, e.g. `22:00-06:00`.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct TimeRange {
+    start: TimeOfDay,
+    end: TimeOfDay,
+}
+
+impl TimeRange {
+    pub fn new(start: TimeOfDay, end: TimeOfDay) -> Self {
+        TimeRange { start, end }
+    }
+
+    pub(super) fn contains(&self, time: TimeOfDay) -> bool {
+        if self.start <= self.end {
+            self.start <= time && time < self.end
+        } else {
+            self.start <= time || time < self.end
+        }
+    }
+}
+
+impl FromStr for TimeRange {
+    type Err = InvalidTimeRange;
+
+    fn from_str(s: &str) -> Result<Self, InvalidTimeRange> {
+        let err = || InvalidTimeRange(s.to_owned());
+        let (start, end) = s.split_once('-').ok_or_else(err)?;
+        let parse = |t: &str| t.trim().parse::<TimeOfDay>().map_err(|_| err());
+        Ok(TimeRange::new(parse(start)?, parse(end)?))
+    }
+}
+
+impl TryFrom<String> for TimeRange {
+    type Error = InvalidTimeRange;
+
+    fn try_from(s: String) -> Result<Self, InvalidTimeRange> {
+        s.parse()
+    }
+}
+
+impl From<TimeRange> for String {
+    fn from(range: TimeRange) -> Self {
+        range.to_string()
+    }
+}
+
+impl Display for TimeRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-{}", self.start, self.end)
+    }
+}
+
+#[derive(Debug, Error)]
+#[error("Invalid time range {0:?}, expected e.g. 09:00-17:00")]
+pub struct InvalidTimeRange(String);
+
+/// Time of day with minute precision
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub struct TimeOfDay {
+    minutes: u16,
+}
+
+impl TimeOfDay {
+    /// Returns `None` if the time is out of range.
+    pub fn new(hour: u8, minute: u8) -> Option<Self> {
+        (hour <= 24 && minute < 60 && (hour < 24 || minute == 0)).then(|| TimeOfDay {
+            minutes: u16::from(hour) * 60 + u16::from(minute),
+        })
+    }
+}
+
+impl From<civil::Time> for TimeOfDay {
+    fn from(time: civil::Time) -> Self {
+        TimeOfDay {
+            minutes: time.hour() as u16 * 60 + time.minute() as u16,
+        }
+    }
+}
+
+impl FromStr for TimeOfDay {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, ()> {
+        let (hour, minute) = s.split_once(':').ok_or(())?;
+        TimeOfDay::new(hour.parse().map_err(drop)?, minute.parse().map_err(drop)?).ok_or(())
+    }
+}
+
+impl Display for TimeOfDay {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:02}:{:02}", self.minutes / 60, self.minutes % 60)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{TimeRange, Weekday, WhenTime};
+    use crate::{block::Text, BlockProducer};
+    use jiff::civil::date;
+
+    #[test]
+    fn producer_is_shown_outside_working_hours() {
+        let when = WhenTime::new(BlockProducer::Text(Text::new("off")))
+            .with_ranges(["09:00-17:00".parse::<TimeRange>().unwrap()])
+            .with_days([
+                Weekday::Mon,
+                Weekday::Tue,
+                Weekday::Wed,
+                Weekday::Thu,
+                Weekday::Fri,
+            ])
+            .invert(true);
+        // 2024-03-15 is a Friday.
+        assert!(!when.is_active(date(2024, 3, 15).at(9, 0, 0, 0)));
+        assert!(when.is_active(date(2024, 3, 15).at(17, 0, 0, 0)));
+        assert!(when.is_active(date(2024, 3, 16).at(12, 0, 0, 0)));
+        let night = "22:00-06:00".parse::<TimeRange>().unwrap();
+        assert!(night.contains("23:30".parse().unwrap()));
+        assert!(!night.contains("12:00".parse().unwrap()));
+    }
+}
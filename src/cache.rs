@@ -0,0 +1,190 @@
+// Copyright (C) 2020 Stephane Raux. Distributed under the MIT license.
+
+//! Results of expensive computations persisted across prompts.
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fs,
+    hash::Hasher,
+    io,
+    path::{Path, PathBuf},
+    process,
+};
+
+/// Values stored in a file and valid as long as the state they were computed from is unchanged
+///
+/// The whole cache is tied to a key describing that state, e.g. the commit HEAD points to. If the
+/// key in the file differs from the current one, all values are discarded. Each value also has its
+/// own key for the inputs it depends on that the cache key does not cover.
+#[derive(Debug)]
+pub struct Cache {
+    path: PathBuf,
+    contents: CacheContents,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+struct CacheContents {
+    key: String,
+    entries: BTreeMap<String, CacheEntry>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct CacheEntry {
+    key: String,
+    value: serde_json::Value,
+}
+
+impl Cache {
+    /// Opens the cache stored at `path` if it is valid for `key`, or starts an empty one.
+    pub fn open(path: PathBuf, key: String) -> Self {
+        let contents = match fs::read(&path) {
+            Ok(bytes) => serde_json::from_slice::<CacheContents>(&bytes).unwrap_or_else(|e| {
+                tracing::warn!("Ignoring invalid cache {}: {}", path.display(), e);
+                Default::default()
+            }),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Default::default(),
+            Err(e) => {
+                tracing::warn!("Failed to read cache {}: {}", path.display(), e);
+                Default::default()
+            }
+        };
+        let contents = if contents.key == key {
+            contents
+        } else {
+            CacheContents {
+                key,
+                entries: BTreeMap::new(),
+            }
+        };
+        Cache { path, contents }
+    }
+
+    /// Returns the value named `name` if it was computed for `key`.
+    pub fn get<T>(&self, name: &str, key: &str) -> Option<T>
+    where
+        T: DeserializeOwned,
+    {
+        let entry = self.contents.entries.get(name).filter(|e| e.key == key)?;
+        serde_json::from_value(entry.value.clone()).ok()
+    }
+
+    /// Stores `value` under `name` and saves the cache.
+    pub fn insert<T>(&mut self, name: &str, key: &str, value: &T)
+    where
+        T: Serialize,
+    {
+        let value = match serde_json::to_value(value) {
+            Ok(value) => value,
+            Err(e) => {
+                tracing::error!("Failed to serialize cached value {}: {}", name, e);
+                return;
+            }
+        };
+        let entry = CacheEntry {
+            key: key.to_owned(),
+            value,
+        };
+        self.contents.entries.insert(name.to_owned(), entry);
+        if let Err(e) = self.save() {
+            tracing::error!("Failed to write cache {}: {}", self.path.display(), e);
+        }
+    }
+
+    /// Removes the values for which `f` returns `false` or which cannot be read as `T`.
+    ///
+    /// The cache is saved by the next insertion.
+    pub fn retain<T, F>(&mut self, mut f: F)
+    where
+        T: DeserializeOwned,
+        F: FnMut(&str, T) -> bool,
+    {
+        self.contents.entries.retain(|name, entry| {
+            serde_json::from_value(entry.value.clone()).is_ok_and(|value| f(name, value))
+        });
+    }
+
+    /// Returns the value named `name` computed for `key`, computing it with `f` if needed.
+    pub fn get_or_try_insert_with<T, E, F>(&mut self, name: &str, key: &str, f: F) -> Result<T, E>
+    where
+        T: Serialize + DeserializeOwned,
+        F: FnOnce() -> Result<T, E>,
+    {
+        if let Some(value) = self.get(name, key) {
+            return Ok(value);
+        }
+        let value = f()?;
+        self.insert(name, key, &value);
+        Ok(value)
+    }
+
+    fn save(&self) -> io::Result<()> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let bytes = serde_json::to_vec(&self.contents)?;
+        // Other shells may use the same cache concurrently, so it is replaced atomically.
+        let tmp_path = self.path.with_extension(format!("{}.tmp", process::id()));
+        fs::write(&tmp_path, bytes)?;
+        fs::rename(&tmp_path, &self.path)
+    }
+}
+
+/// Returns the default directory where caches are stored.
+pub fn default_dir() -> Option<PathBuf> {
+    Some(dirs::cache_dir()?.join("eliprompt"))
+}
+
+/// Returns a file name identifying `path`, suitable to store a cache about it.
+pub fn file_name(path: &Path) -> String {
+    let mut hasher = StableHasher::default();
+    hasher.write(path.to_string_lossy().as_bytes());
+    format!("{:016x}.json", hasher.finish())
+}
+
+/// FNV-1a hasher, stable across builds unlike `DefaultHasher`, so that hashes can be persisted
+#[derive(Debug)]
+pub(crate) struct StableHasher(u64);
+
+impl Default for StableHasher {
+    fn default() -> Self {
+        StableHasher(0xcbf29ce484222325)
+    }
+}
+
+impl Hasher for StableHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        self.0 = bytes.iter().fold(self.0, |hash, &b| {
+            (hash ^ u64::from(b)).wrapping_mul(0x100000001b3)
+        });
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Cache;
+
+    #[test]
+    fn values_are_discarded_when_key_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("cache.json");
+        let mut cache = Cache::open(path.clone(), "a".into());
+        cache.insert("answer", "", &42);
+        assert_eq!(
+            Cache::open(path.clone(), "a".into()).get("answer", ""),
+            Some(42)
+        );
+        assert_eq!(
+            Cache::open(path.clone(), "a".into()).get::<i32>("answer", "x"),
+            None
+        );
+        assert_eq!(
+            Cache::open(path.clone(), "b".into()).get::<i32>("answer", ""),
+            None
+        );
+    }
+}
@@ -0,0 +1,108 @@
+// Copyright (C) 2020 Stephane Raux. Distributed under the MIT license.
+
+//! Cooperative cancellation of prompt generation.
+
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex, PoisonError,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+/// Token telling producers to stop early
+///
+/// Producers are skipped once the token is cancelled, and programs they run are killed, so that
+/// a timed-out prompt keeps the blocks produced so far and leaves no work running behind it.
+#[derive(Clone, Debug, Default)]
+pub struct Cancellation {
+    cancelled: Arc<AtomicBool>,
+    deadline: Option<Instant>,
+}
+
+impl Cancellation {
+    /// Creates a token that is only cancelled by calling [`Cancellation::cancel`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a token cancelled after `timeout` from now.
+    pub fn with_timeout(timeout: Duration) -> Self {
+        Cancellation {
+            deadline: Instant::now().checked_add(timeout),
+            ..Self::new()
+        }
+    }
+
+    /// Returns a token cancelled along with this one, or after `timeout` from now if earlier.
+    pub fn limited_to(&self, timeout: Duration) -> Self {
+        let deadline = Instant::now().checked_add(timeout);
+        Cancellation {
+            cancelled: self.cancelled.clone(),
+            deadline: self.deadline.into_iter().chain(deadline).min(),
+        }
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed) || self.deadline.is_some_and(|d| Instant::now() >= d)
+    }
+
+    /// Blocks until the token is cancelled, checking it every `interval`.
+    pub fn wait(&self, interval: Duration) {
+        while !self.is_cancelled() {
+            let interval = match self.deadline {
+                Some(d) => interval.min(d.saturating_duration_since(Instant::now())),
+                None => interval,
+            };
+            thread::sleep(interval);
+        }
+    }
+}
+
+/// Token shared between holders that must all follow it when it is replaced, e.g. by a
+/// cancellation scope
+#[derive(Clone, Debug, Default)]
+pub(crate) struct SharedCancellation(Arc<Mutex<Cancellation>>);
+
+impl SharedCancellation {
+    pub(crate) fn new(cancellation: Cancellation) -> Self {
+        SharedCancellation(Arc::new(Mutex::new(cancellation)))
+    }
+
+    pub(crate) fn get(&self) -> Cancellation {
+        self.0
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    /// Replaces the shared token, returning the previous one.
+    pub(crate) fn replace(&self, cancellation: Cancellation) -> Cancellation {
+        let mut current = self.0.lock().unwrap_or_else(PoisonError::into_inner);
+        std::mem::replace(&mut current, cancellation)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Cancellation;
+    use std::time::Duration;
+
+    #[test]
+    fn clones_share_cancellation() {
+        let token = Cancellation::new();
+        let clone = token.clone();
+        assert!(!clone.is_cancelled());
+        token.cancel();
+        assert!(clone.is_cancelled());
+        assert!(Cancellation::with_timeout(Duration::ZERO).is_cancelled());
+        assert!(Cancellation::new()
+            .limited_to(Duration::ZERO)
+            .is_cancelled());
+    }
+}
@@ -1,5 +1,6 @@
 // Copyright (C) 2020 Stephane Raux. Distributed under the MIT license.
 
+use palette::{color_difference::Wcag21RelativeContrast, FromColor};
 use rgb::RGB8;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::{
@@ -25,6 +26,29 @@ impl Color {
         self.inner
     }
 
+    /// Returns the contrast ratio between this color and `other`, from 1 to 21, as defined by
+    /// WCAG 2.1.
+    pub fn contrast_with(&self, other: &Color) -> f32 {
+        self.to_srgb().relative_contrast(other.to_srgb())
+    }
+
+    /// Returns this color with its lightness pushed away from the middle and its saturation
+    /// increased, making it stand out more against most backgrounds.
+    pub fn boosted(&self) -> Color {
+        let hsl = palette::Hsl::from_color(self.to_srgb());
+        let lightness = if hsl.lightness >= 0.5 {
+            (hsl.lightness + 1.0) / 2.0
+        } else {
+            hsl.lightness / 2.0
+        };
+        let hsl = palette::Hsl::new(hsl.hue, (hsl.saturation * 1.25).min(1.0), lightness);
+        palette::Srgb::from_color(hsl).into_format::<u8>().into()
+    }
+
+    fn to_srgb(&self) -> palette::Srgb<f32> {
+        palette::Srgb::new(self.inner.r, self.inner.g, self.inner.b).into_format()
+    }
+
     const fn named(s: &'static str, value: palette::Srgb<u8>) -> Self {
         Color {
             inner: RGB8 {
@@ -182,4 +206,12 @@ mod tests {
     fn rgb_color_is_printed_as_hex() {
         assert_eq!(Color::from(RGB8::new(255, 0, 0)).to_string(), "#ff0000");
     }
+
+    #[test]
+    fn boosted_colors_contrast_more() {
+        let gray = Color::new(0x60, 0x60, 0x60);
+        let black = super::BLACK;
+        assert!(gray.boosted().contrast_with(&super::WHITE) > gray.contrast_with(&super::WHITE));
+        assert!(black.contrast_with(&super::WHITE) > 20.0);
+    }
 }
@@ -1,7 +1,11 @@
 // Copyright (C) 2020 Stephane Raux. Distributed under the MIT license.
 
-use crate::{Block, BlockProducer, Environment, Style};
+use crate::{
+    block::BlockKind, symbol, AlertTheme, Block, BlockProducer, CursorShapes, Environment, Pattern,
+    Style, SymbolSet,
+};
 use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
 use std::time::Duration;
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -10,8 +14,28 @@ pub struct Config {
     pub prompt: BlockProducer,
     #[serde(default)]
     pub alternative_prompt: Option<BlockProducer>,
+    #[serde(default)]
+    pub ssh_prompt: Option<BlockProducer>,
+    #[serde(default)]
+    pub right_prompt: Option<BlockProducer>,
+    #[serde(default)]
+    pub local_only_over_ssh: bool,
     #[serde(with = "humantime_serde", default = "default_timeout")]
     pub timeout: Duration,
+    #[serde(default)]
+    pub symbols: SymbolSet,
+    #[serde(default = "default_collapse_separators")]
+    pub collapse_separators: bool,
+    #[serde(default = "default_cache_git")]
+    pub cache_git: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub alert: Option<AlertTheme>,
+    #[serde(default)]
+    pub strict: bool,
+    #[serde(default)]
+    pub high_contrast: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cursor_shape: Option<CursorShapes>,
 }
 
 impl Config {
@@ -19,7 +43,17 @@ impl Config {
         Config {
             prompt,
             alternative_prompt: None,
+            ssh_prompt: None,
+            right_prompt: None,
+            local_only_over_ssh: false,
             timeout: default_timeout(),
+            symbols: Default::default(),
+            collapse_separators: default_collapse_separators(),
+            cache_git: default_cache_git(),
+            alert: None,
+            strict: false,
+            high_contrast: false,
+            cursor_shape: None,
         }
     }
 
@@ -27,8 +61,82 @@ impl Config {
         Config {
             prompt: default_pretty_prompt(),
             alternative_prompt: Some(default_alternative_prompt()),
+            ssh_prompt: None,
+            right_prompt: None,
+            local_only_over_ssh: false,
             timeout: default_timeout(),
+            symbols: Default::default(),
+            collapse_separators: default_collapse_separators(),
+            cache_git: default_cache_git(),
+            alert: None,
+            strict: false,
+            high_contrast: false,
+            cursor_shape: None,
+        }
+    }
+
+    /// Reads a configuration from JSON.
+    pub fn from_json(json: &[u8]) -> Result<Self, serde_json::Error> {
+        Self::from_json_layers([json])
+    }
+
+    /// Parses configuration made of layers, e.g. a configuration shared by all users followed by
+    /// a user configuration.
+    ///
+    /// Fields set in a layer replace those set in previous layers. Fields are replaced as a whole,
+    /// e.g. a layer setting `prompt` replaces the whole prompt definition. Host overlays are then
+    /// applied the same way.
+    ///
+    /// Unknown fields are logged as warnings, unless `strict` is set, in which case they are
+    /// errors.
+    pub fn from_json_layers<'a, I>(layers: I) -> Result<Self, serde_json::Error>
+    where
+        I: IntoIterator<Item = &'a [u8]>,
+    {
+        Self::from_json_layers_with(layers, |field| {
+            tracing::warn!(field, "Ignoring unknown configuration field")
+        })
+    }
+
+    /// Parses configuration like [`Config::from_json_layers`], calling `on_unknown_field` with the
+    /// path of each unknown field (e.g. `prompt.Text.style.foregound`) when `strict` is not set.
+    pub fn from_json_layers_with<'a, I, F>(
+        layers: I,
+        mut on_unknown_field: F,
+    ) -> Result<Self, serde_json::Error>
+    where
+        I: IntoIterator<Item = &'a [u8]>,
+        F: FnMut(&str),
+    {
+        let mut value = Value::Object(Default::default());
+        for layer in layers {
+            match (&mut value, serde_json::from_slice(layer)?) {
+                (Value::Object(fields), Value::Object(layer)) => fields.extend(layer),
+                (value, layer) => *value = layer,
+            }
+        }
+        if let Value::Object(fields) = &mut value {
+            apply_host_overlays(fields, &whoami::hostname())?;
+        }
+        let strict = value
+            .get("strict")
+            .map(bool::deserialize)
+            .transpose()?
+            .unwrap_or_default();
+        let mut unknown_fields = Vec::new();
+        let config = serde_ignored::deserialize(value.clone(), |path| {
+            unknown_fields.push(json_path(&value, &path).0.join("."))
+        })?;
+        if strict && !unknown_fields.is_empty() {
+            return Err(serde::de::Error::custom(format_args!(
+                "Unknown fields: {}",
+                unknown_fields.join(", ")
+            )));
         }
+        unknown_fields
+            .iter()
+            .for_each(|field| on_unknown_field(field));
+        Ok(config)
     }
 
     pub fn with_alternative(self, prompt: BlockProducer) -> Self {
@@ -38,17 +146,129 @@ impl Config {
         }
     }
 
+    pub fn with_ssh_prompt(self, prompt: BlockProducer) -> Self {
+        Self {
+            ssh_prompt: Some(prompt),
+            ..self
+        }
+    }
+
+    /// Sets the prompt shown on the right of the first line, e.g. in zsh's `RPROMPT`.
+    pub fn with_right_prompt(self, prompt: BlockProducer) -> Self {
+        Self {
+            right_prompt: Some(prompt),
+            ..self
+        }
+    }
+
+    /// Restricts producers to local information in SSH sessions, e.g. so that the prompt never
+    /// waits for the network.
+    pub fn local_only_over_ssh(self, yes: bool) -> Self {
+        Self {
+            local_only_over_ssh: yes,
+            ..self
+        }
+    }
+
+    pub fn collapse_separators(self, yes: bool) -> Self {
+        Self {
+            collapse_separators: yes,
+            ..self
+        }
+    }
+
+    pub fn cache_git(self, yes: bool) -> Self {
+        Self {
+            cache_git: yes,
+            ..self
+        }
+    }
+
+    pub fn with_alert(self, alert: AlertTheme) -> Self {
+        Self {
+            alert: Some(alert),
+            ..self
+        }
+    }
+
+    /// Indicates if the colors of the prompt should be remapped for stronger contrast.
+    pub fn high_contrast(self, yes: bool) -> Self {
+        Self {
+            high_contrast: yes,
+            ..self
+        }
+    }
+
+    /// Sets the cursor shapes to set with the prompt depending on the keymap.
+    pub fn with_cursor_shape(self, shapes: CursorShapes) -> Self {
+        Self {
+            cursor_shape: Some(shapes),
+            ..self
+        }
+    }
+
     pub fn with_timeout(self, timeout: Duration) -> Self {
         Self { timeout, ..self }
     }
 
     pub fn produce(&self, environment: &Environment) -> Vec<Block> {
         let use_alternative = environment.alternative_prompt_is_used();
-        let producer = match &self.alternative_prompt {
-            Some(p) if use_alternative => p,
+        let over_ssh = environment.is_ssh_session();
+        if over_ssh && self.local_only_over_ssh {
+            environment.restrict_to_local();
+        }
+        let producer = match (&self.alternative_prompt, &self.ssh_prompt) {
+            (Some(p), _) if use_alternative => p,
+            (_, Some(p)) if over_ssh => p,
             _ => &self.prompt,
         };
-        producer.produce(environment)
+        // Symbols looked up while producing blocks come from the configured set too.
+        let blocks = self.symbols.scope(|| producer.produce(environment));
+        let blocks = match &self.alert {
+            Some(alert) if alert.is_active(environment) => alert.apply(blocks, environment),
+            _ => blocks,
+        };
+        let blocks = self.finish(blocks, environment);
+        crate::block::fill_lines(blocks, environment.terminal_width())
+    }
+
+    /// Produces the right prompt, without newlines. Nothing is produced if there is none.
+    pub fn produce_right(&self, environment: &Environment) -> Vec<Block> {
+        let Some(producer) = &self.right_prompt else {
+            return Vec::new();
+        };
+        if environment.is_ssh_session() && self.local_only_over_ssh {
+            environment.restrict_to_local();
+        }
+        let blocks = self
+            .symbols
+            .scope(|| producer.produce(environment))
+            .into_iter()
+            .filter(|block| block.kind != BlockKind::Newline)
+            .collect();
+        self.finish(blocks, environment)
+    }
+
+    /// Applies the styling and cleanup shared by all prompts.
+    fn finish(&self, blocks: Vec<Block>, environment: &Environment) -> Vec<Block> {
+        let blocks = if self.high_contrast || environment.high_contrast_requested() {
+            blocks
+                .into_iter()
+                .map(|block| Block {
+                    style: block.style.high_contrast(),
+                    ..block
+                })
+                .collect()
+        } else {
+            blocks
+        };
+        // Items are shrunk before separators left around dropped ones are collapsed.
+        let blocks = crate::block::shrink_lines(blocks, environment.terminal_width());
+        if self.collapse_separators {
+            crate::block::collapse_separators(blocks)
+        } else {
+            blocks
+        }
     }
 }
 
@@ -56,6 +276,14 @@ fn default_timeout() -> Duration {
     Duration::from_secs(1)
 }
 
+fn default_collapse_separators() -> bool {
+    false
+}
+
+fn default_cache_git() -> bool {
+    true
+}
+
 pub fn default_pretty_prompt() -> BlockProducer {
     let id = vec![
         BlockProducer::Username(crate::block::Username::new()),
@@ -78,9 +306,11 @@ pub fn default_pretty_prompt() -> BlockProducer {
         BlockProducer::Separated(separated),
         BlockProducer::Newline(crate::block::Newline),
         BlockProducer::ExitStatusSymbol(
-            crate::block::ExitStatusSymbol::new("→")
+            crate::block::ExitStatusSymbol::new(symbol::PROMPT)
                 .with_style(crate::color::DODGERBLUE)
-                .with_error_style(crate::color::CRIMSON),
+                .with_error_style(crate::color::CRIMSON)
+                .with_root_contents("#")
+                .with_root_style(crate::color::CRIMSON),
         ),
         BlockProducer::Space(crate::block::Space),
     ]));
@@ -116,9 +346,11 @@ pub fn default_alternative_prompt() -> BlockProducer {
         BlockProducer::Separated(separated),
         BlockProducer::Newline(crate::block::Newline),
         BlockProducer::ExitStatusSymbol(
-            crate::block::ExitStatusSymbol::new("→")
+            crate::block::ExitStatusSymbol::new(symbol::PROMPT)
                 .with_style(crate::color::DODGERBLUE)
-                .with_error_style(crate::color::CRIMSON),
+                .with_error_style(crate::color::CRIMSON)
+                .with_root_contents("#")
+                .with_root_style(crate::color::CRIMSON),
         ),
         BlockProducer::Space(crate::block::Space),
     ]));
@@ -138,3 +370,133 @@ pub fn fallback_prompt() -> BlockProducer {
         BlockProducer::Space(crate::block::Space),
     ]))
 }
+
+/// Replaces the `hosts` field with the overlays whose hostname pattern matches `hostname`.
+///
+/// Overlays are applied in the order they appear in.
+fn apply_host_overlays(
+    fields: &mut Map<String, Value>,
+    hostname: &str,
+) -> Result<(), serde_json::Error> {
+    let Some(hosts) = fields.remove("hosts") else {
+        return Ok(());
+    };
+    let hosts = Map::<String, Value>::deserialize(hosts)?;
+    for (pattern, overlay) in hosts {
+        let pattern = Pattern::new(&pattern).map_err(serde::de::Error::custom)?;
+        if pattern.is_match(hostname) {
+            fields.extend(Map::<String, Value>::deserialize(overlay)?);
+        }
+    }
+    Ok(())
+}
+
+/// Returns the components of `path` in `value`, along with the value it refers to.
+///
+/// Unlike the `Display` implementation of `serde_ignored::Path`, this names enum variants, e.g.
+/// producers.
+fn json_path<'a>(
+    value: &'a Value,
+    path: &serde_ignored::Path<'_>,
+) -> (Vec<String>, Option<&'a Value>) {
+    use serde_ignored::Path;
+
+    match path {
+        Path::Root => (Vec::new(), Some(value)),
+        Path::Seq { parent, index } => {
+            let (mut components, parent) = json_path(value, parent);
+            components.push(index.to_string());
+            (components, parent.and_then(|v| v.get(index)))
+        }
+        Path::Map { parent, key } => {
+            let (mut components, parent) = json_path(value, parent);
+            components.push(key.clone());
+            (components, parent.and_then(|v| v.get(key)))
+        }
+        Path::Some { parent } | Path::NewtypeStruct { parent } => json_path(value, parent),
+        Path::NewtypeVariant { parent } => {
+            let (mut components, parent) = json_path(value, parent);
+            let variant = parent
+                .and_then(Value::as_object)
+                .filter(|fields| fields.len() == 1)
+                .and_then(|fields| fields.iter().next());
+            match variant {
+                Some((name, value)) => {
+                    components.push(name.clone());
+                    (components, Some(value))
+                }
+                None => {
+                    components.push("?".into());
+                    (components, None)
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{apply_host_overlays, Config};
+    use crate::{
+        block::{Newline, Sequence, Text},
+        BlockProducer, Environment,
+    };
+    use serde_json::{json, Value};
+    use std::time::Duration;
+
+    #[test]
+    fn right_prompt_has_no_newlines() {
+        let environment = Environment::new(None);
+        let config = Config::from_json(br#"{"prompt": {"Text": {"contents": "$"}}}"#).unwrap();
+        assert!(config.produce_right(&environment).is_empty());
+        let config = config.with_right_prompt(BlockProducer::Sequence(Sequence(vec![
+            BlockProducer::Text(Text::new("a")),
+            BlockProducer::Newline(Newline),
+            BlockProducer::Text(Text::new("b")),
+        ])));
+        let texts = config
+            .produce_right(&environment)
+            .into_iter()
+            .map(|block| block.text)
+            .collect::<Vec<_>>();
+        assert_eq!(texts, ["a", "b"]);
+    }
+
+    #[test]
+    fn later_layers_replace_fields() {
+        let system = br#"{"timeout": "5s", "local_only_over_ssh": true}"#;
+        let user = br#"{"timeout": "1s"}"#;
+        let config = Config::from_json_layers([&system[..], &user[..]]).unwrap();
+        assert_eq!(config.timeout, Duration::from_secs(1));
+        assert!(config.local_only_over_ssh);
+    }
+
+    #[test]
+    fn unknown_fields_are_reported() {
+        let json = br#"{"prompt": {"Text": {"contents": "", "style": {"foregound": "red"}}}}"#;
+        let mut unknown = Vec::new();
+        Config::from_json_layers_with([&json[..]], |f| unknown.push(f.to_owned())).unwrap();
+        assert_eq!(unknown, ["prompt.Text.style.foregound"]);
+        let strict = br#"{"strict": true}"#;
+        assert!(Config::from_json_layers([&json[..], &strict[..]]).is_err());
+    }
+
+    #[test]
+    fn matching_host_overlays_are_applied() {
+        let Value::Object(mut fields) = json!({
+            "timeout": "1s",
+            "hosts": {
+                "^prod-db$": { "timeout": "3s" },
+                "^laptop$": { "timeout": "5s" },
+                "^prod-": { "timeout": "2s", "local_only_over_ssh": true },
+            },
+        }) else {
+            unreachable!()
+        };
+        apply_host_overlays(&mut fields, "prod-db").unwrap();
+        assert_eq!(
+            Value::Object(fields),
+            json!({ "timeout": "2s", "local_only_over_ssh": true })
+        );
+    }
+}
@@ -0,0 +1,71 @@
+// Copyright (C) 2020 Stephane Raux. Distributed under the MIT license.
+
+//! Cursor shape set along with the prompt.
+
+use serde::{Deserialize, Serialize};
+
+/// Cursor shape, as set by the DECSCUSR escape sequence
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CursorShape {
+    /// Shape configured in the terminal
+    Default,
+    BlinkingBlock,
+    Block,
+    BlinkingUnderline,
+    Underline,
+    BlinkingBar,
+    Bar,
+}
+
+impl CursorShape {
+    /// Returns the escape sequence setting this shape.
+    pub fn escape_sequence(self) -> &'static str {
+        match self {
+            CursorShape::Default => "\x1b[0 q",
+            CursorShape::BlinkingBlock => "\x1b[1 q",
+            CursorShape::Block => "\x1b[2 q",
+            CursorShape::BlinkingUnderline => "\x1b[3 q",
+            CursorShape::Underline => "\x1b[4 q",
+            CursorShape::BlinkingBar => "\x1b[5 q",
+            CursorShape::Bar => "\x1b[6 q",
+        }
+    }
+}
+
+/// Cursor shapes to set depending on the line editor keymap
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct CursorShapes {
+    /// Shape when inserting text, i.e. in zsh's `viins`, `main` and `emacs` keymaps
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub insert: Option<CursorShape>,
+    /// Shape in vi normal mode, i.e. in zsh's `vicmd`, `viopp` and `visual` keymaps
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub normal: Option<CursorShape>,
+}
+
+impl CursorShapes {
+    /// Returns the shape to set for `keymap`, or for the insert keymap if it is unknown.
+    pub fn for_keymap(&self, keymap: Option<&str>) -> Option<CursorShape> {
+        match keymap {
+            Some("vicmd" | "viopp" | "visual") => self.normal,
+            _ => self.insert,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CursorShape, CursorShapes};
+
+    #[test]
+    fn shape_depends_on_keymap() {
+        let shapes = CursorShapes {
+            insert: Some(CursorShape::Bar),
+            normal: Some(CursorShape::Block),
+        };
+        assert_eq!(shapes.for_keymap(Some("vicmd")), Some(CursorShape::Block));
+        assert_eq!(shapes.for_keymap(Some("viins")), Some(CursorShape::Bar));
+        assert_eq!(shapes.for_keymap(None), Some(CursorShape::Bar));
+    }
+}
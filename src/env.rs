@@ -1,20 +1,59 @@
 // Copyright (C) 2020 Stephane Raux. Distributed under the MIT license.
 
+use crate::{
+    block::{ShownNote, StickyBlocks},
+    cache::{self, Cache},
+    cancel::{Cancellation, SharedCancellation},
+    git::{GitRepo, Head},
+    hg::HgRepo,
+    jj::JjRepo,
+    svn::SvnRepo,
+    symbol,
+    vcs::Vcs,
+    Block, SymbolSet,
+};
 use git2::Repository;
 use once_cell::sync::OnceCell;
 use std::{
+    cell::{Cell, RefCell, RefMut},
+    collections::BTreeSet,
     env,
+    ffi::OsStr,
     fmt::{self, Debug},
+    fs, io,
     path::{Path, PathBuf},
-    time::Duration,
+    process::Command,
+    time::{Duration, SystemTime},
 };
 
 pub struct Environment {
     working_dir: Option<PathBuf>,
     prev_exit_code: i32,
+    git: OnceCell<Option<GitRepo>>,
     repo: OnceCell<Option<Repository>>,
+    jj: once_cell::unsync::OnceCell<Option<JjRepo>>,
+    hg: once_cell::unsync::OnceCell<Option<HgRepo>>,
+    svn: once_cell::unsync::OnceCell<Option<SvnRepo>>,
+    kube_context: once_cell::unsync::OnceCell<Option<String>>,
     prev_cmd_duration: Option<Duration>,
+    prev_cmd_suspended: Duration,
+    marker: Option<String>,
+    toggled_groups: BTreeSet<String>,
+    named_dirs: Vec<(String, PathBuf)>,
+    terminal_width: Option<usize>,
     force_alternative_prompt: bool,
+    is_root: Option<bool>,
+    faults: FaultInjection,
+    // Shared with repositories running commands, which must follow cancellation scopes.
+    cancellation: SharedCancellation,
+    sticky_blocks: RefCell<StickyBlocks>,
+    shown_notes: RefCell<Vec<ShownNote>>,
+    cache_dir: Option<PathBuf>,
+    cache_git: bool,
+    local_only: Cell<bool>,
+    git_cache: once_cell::unsync::OnceCell<Option<RefCell<Cache>>>,
+    tool_cache: once_cell::unsync::OnceCell<Option<RefCell<Cache>>>,
+    workspace_cache: once_cell::unsync::OnceCell<Option<RefCell<Cache>>>,
 }
 
 impl Environment {
@@ -22,13 +61,59 @@ impl Environment {
         Environment {
             working_dir,
             prev_exit_code: 0,
+            git: OnceCell::new(),
             repo: OnceCell::new(),
+            jj: Default::default(),
+            hg: Default::default(),
+            svn: Default::default(),
+            kube_context: Default::default(),
             prev_cmd_duration: None,
+            prev_cmd_suspended: Duration::ZERO,
+            marker: None,
+            toggled_groups: BTreeSet::new(),
+            named_dirs: Vec::new(),
+            terminal_width: None,
             force_alternative_prompt: false,
+            is_root: None,
+            faults: Default::default(),
+            cancellation: Default::default(),
+            sticky_blocks: Default::default(),
+            shown_notes: Default::default(),
+            cache_dir: None,
+            cache_git: true,
+            local_only: Cell::new(false),
+            git_cache: Default::default(),
+            tool_cache: Default::default(),
+            workspace_cache: Default::default(),
         }
     }
+
+    /// Creates an environment for the current working directory.
+    ///
+    /// If the current directory was deleted, its former path is taken from `$PWD`.
     pub fn current() -> Self {
-        Self::new(env::current_dir().ok())
+        let dir = env::current_dir()
+            .ok()
+            .or_else(|| env::var_os("PWD").map(PathBuf::from));
+        Self::new(dir)
+    }
+
+    /// Creates an environment to show a prompt in `working_dir`, or the current working directory
+    /// if `None`, after a command exited with `exit_code` and ran for `duration` if known.
+    pub fn for_prompt(
+        working_dir: Option<PathBuf>,
+        exit_code: i32,
+        duration: Option<Duration>,
+    ) -> Self {
+        let environment = match working_dir {
+            Some(dir) => Self::new(Some(dir)),
+            None => Self::current(),
+        }
+        .with_prev_exit_code(exit_code);
+        match duration {
+            Some(d) => environment.with_prev_cmd_duration(d),
+            None => environment,
+        }
     }
 
     pub fn with_prev_exit_code(self, code: i32) -> Self {
@@ -45,6 +130,49 @@ impl Environment {
         }
     }
 
+    /// Sets how long the previous command was suspended, e.g. after Ctrl-Z.
+    pub fn with_prev_cmd_suspended(self, d: Duration) -> Self {
+        Self {
+            prev_cmd_suspended: d,
+            ..self
+        }
+    }
+
+    /// Sets the marker flagging the shell session, e.g. `DEMO`.
+    pub fn with_marker(self, marker: Option<String>) -> Self {
+        Self { marker, ..self }
+    }
+
+    /// Sets the groups whose visibility was flipped with `eliprompt toggle`.
+    pub fn with_toggled_groups<I>(self, groups: I) -> Self
+    where
+        I: IntoIterator<Item = String>,
+    {
+        Self {
+            toggled_groups: groups.into_iter().collect(),
+            ..self
+        }
+    }
+
+    /// Sets the named directories of the shell, e.g. from zsh's `hash -d`, as names and paths.
+    pub fn with_named_dirs<I>(self, dirs: I) -> Self
+    where
+        I: IntoIterator<Item = (String, PathBuf)>,
+    {
+        Self {
+            named_dirs: dirs.into_iter().collect(),
+            ..self
+        }
+    }
+
+    /// Sets the width of the terminal in columns, e.g. from the shell's `$COLUMNS`.
+    pub fn with_terminal_width(self, width: Option<usize>) -> Self {
+        Self {
+            terminal_width: width,
+            ..self
+        }
+    }
+
     pub fn force_alternative_prompt(self, yes: bool) -> Self {
         Self {
             force_alternative_prompt: yes,
@@ -52,6 +180,63 @@ impl Environment {
         }
     }
 
+    /// Overrides whether the effective user is root, which is otherwise detected.
+    pub fn force_root(self, yes: bool) -> Self {
+        Self {
+            is_root: Some(yes),
+            ..self
+        }
+    }
+
+    pub fn with_fault_injection(self, faults: FaultInjection) -> Self {
+        Self { faults, ..self }
+    }
+
+    /// Sets the token telling producers to stop early, e.g. when the prompt timed out.
+    pub fn with_cancellation(self, cancellation: Cancellation) -> Self {
+        Self {
+            cancellation: SharedCancellation::new(cancellation),
+            ..self
+        }
+    }
+
+    pub fn with_sticky_blocks(self, blocks: StickyBlocks) -> Self {
+        Self {
+            sticky_blocks: RefCell::new(blocks),
+            ..self
+        }
+    }
+
+    /// Sets the directory where results are cached across prompts. Nothing is cached by default.
+    pub fn with_cache_dir(self, dir: Option<PathBuf>) -> Self {
+        Self {
+            cache_dir: dir,
+            ..self
+        }
+    }
+
+    /// Indicates if results computed from the git repository should be cached. Defaults to `true`.
+    pub fn cache_git(self, yes: bool) -> Self {
+        Self {
+            cache_git: yes,
+            ..self
+        }
+    }
+
+    /// Indicates if high contrast colors are requested by setting `ELIPROMPT_HIGH_CONTRAST` to a
+    /// value other than `0`.
+    pub fn high_contrast_requested(&self) -> bool {
+        env::var_os("ELIPROMPT_HIGH_CONTRAST").is_some_and(|v| !v.is_empty() && v != "0")
+    }
+
+    /// Returns the symbol set that blocks use to show [`Symbol`](crate::Symbol)s, e.g. prefixes
+    /// or an operating system icon.
+    ///
+    /// This is the set selected by the configuration while it produces the prompt.
+    pub fn symbols(&self) -> SymbolSet {
+        SymbolSet::current()
+    }
+
     pub fn alternative_prompt_is_used(&self) -> bool {
         if self.force_alternative_prompt {
             return true;
@@ -59,25 +244,260 @@ impl Environment {
         let alternative_requested = env::var("ELIPROMPT_ALTERNATIVE_PROMPT").is_ok();
         let terms_using_alternative = ["linux"];
         let term_uses_alternative =
-            env::var("TERM").map_or(false, |term| terms_using_alternative.contains(&&*term));
+            env::var("TERM").is_ok_and(|term| terms_using_alternative.contains(&&*term));
         alternative_requested || term_uses_alternative
     }
 
+    /// Indicates if the shell runs in an SSH session, i.e. if `SSH_CONNECTION` or `SSH_TTY` is
+    /// set.
+    pub fn is_ssh_session(&self) -> bool {
+        env::var_os("SSH_CONNECTION").is_some() || env::var_os("SSH_TTY").is_some()
+    }
+
+    /// Returns the address of the SSH client, from `SSH_CONNECTION` or `SSH_CLIENT`.
+    pub fn ssh_client(&self) -> Option<String> {
+        ["SSH_CONNECTION", "SSH_CLIENT"]
+            .into_iter()
+            .filter_map(|name| env::var(name).ok())
+            .find_map(|value| ssh_client_address(&value).map(str::to_owned))
+    }
+
+    /// Returns the width of the terminal in columns, as set with
+    /// [`Environment::with_terminal_width`] or found in the `COLUMNS` environment variable.
+    pub fn terminal_width(&self) -> Option<usize> {
+        self.terminal_width.or_else(|| {
+            env::var("COLUMNS")
+                .ok()?
+                .parse()
+                .ok()
+                .filter(|&width| width > 0)
+        })
+    }
+
     pub fn working_dir(&self) -> Option<&Path> {
         self.working_dir.as_deref()
     }
 
+    /// Indicates if the working directory no longer exists, e.g. after `git worktree remove`.
+    pub fn working_dir_is_deleted(&self) -> bool {
+        self.working_dir.as_ref().is_some_and(|dir| {
+            fs::metadata(dir).is_err_and(|e| e.kind() == io::ErrorKind::NotFound)
+        })
+    }
+
+    /// Returns the git repository containing the working directory.
+    ///
+    /// If the working directory was deleted, the repository containing its closest existing
+    /// ancestor is returned. No repository is returned in a Jujutsu repository colocated with git,
+    /// since jj manages the git repository and leaves its HEAD detached.
+    pub fn git(&self) -> Option<&GitRepo> {
+        let git = self.git.get_or_init(|| {
+            let dir = self.working_dir.as_ref()?;
+            let dir = dir.ancestors().find(|d| d.exists())?;
+            let git = match GitRepo::discover(dir) {
+                Ok(git) => git?,
+                Err(e) => {
+                    tracing::error!("Failed to open git repository: {}", e);
+                    return None;
+                }
+            };
+            match self.jj() {
+                Some(jj) if jj.root().starts_with(git.root()) => None,
+                _ => Some(git),
+            }
+        });
+        git.as_ref()
+    }
+
+    /// Returns the git repository containing the working directory, opened with `git2`.
     pub fn repo(&self) -> Option<&Repository> {
+        let git = self.git()?;
+        if let Some(repo) = git.as_git2() {
+            return Some(repo);
+        }
+        let repo = self
+            .repo
+            .get_or_init(|| match Repository::open(git.git_dir()) {
+                Ok(repo) => Some(repo),
+                Err(e) => {
+                    tracing::error!("Failed to open git repository: {}", e);
+                    None
+                }
+            });
+        repo.as_ref()
+    }
+
+    /// Returns the Jujutsu repository containing the working directory.
+    pub fn jj(&self) -> Option<&JjRepo> {
+        self.discover(&self.jj, |dir| {
+            JjRepo::discover(dir).map(|repo| repo.with_cancellation(self.cancellation.clone()))
+        })
+    }
+
+    /// Returns the Mercurial repository containing the working directory.
+    pub fn hg(&self) -> Option<&HgRepo> {
+        self.discover(&self.hg, HgRepo::discover)
+    }
+
+    /// Returns the Subversion working copy containing the working directory.
+    pub fn svn(&self) -> Option<&SvnRepo> {
+        self.discover(&self.svn, |dir| {
+            SvnRepo::discover(dir).map(|repo| repo.with_cancellation(self.cancellation.clone()))
+        })
+    }
+
+    /// Returns the innermost repository containing the working directory, whatever its version
+    /// control system.
+    ///
+    /// If several systems share the same root, e.g. in a Jujutsu repository colocated with git,
+    /// Jujutsu is preferred over git, which is preferred over Mercurial and Subversion.
+    pub fn vcs(&self) -> Option<&dyn Vcs> {
+        let candidates: [Option<&dyn Vcs>; 4] = [
+            self.jj().map(|r| r as _),
+            self.git().map(|r| r as _),
+            self.hg().map(|r| r as _),
+            self.svn().map(|r| r as _),
+        ];
+        candidates
+            .into_iter()
+            .flatten()
+            .rev()
+            .max_by_key(|vcs| vcs.root().components().count())
+    }
+
+    fn discover<'a, T>(
+        &self,
+        cell: &'a once_cell::unsync::OnceCell<Option<T>>,
+        discover: impl FnOnce(&Path) -> Option<T>,
+    ) -> Option<&'a T> {
         let dir = self.working_dir.as_ref()?;
-        let repo = self.repo.get_or_init(|| match Repository::discover(dir) {
-            Ok(repo) => Some(repo),
-            Err(e) if e.code() == git2::ErrorCode::NotFound => None,
-            Err(e) => {
-                tracing::error!("Failed to open git repository: {}", e);
-                None
+        cell.get_or_init(|| discover(dir)).as_ref()
+    }
+
+    /// Returns the token telling producers to stop early.
+    pub fn cancellation(&self) -> Cancellation {
+        self.cancellation.get()
+    }
+
+    /// Indicates if producers should stop early, e.g. because the prompt timed out. Producers
+    /// are then skipped and programs run for them are killed.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancellation.get().is_cancelled()
+    }
+
+    /// Calls `f` with the cancellation token replaced with `cancellation`, e.g. to give part of
+    /// the prompt a shorter deadline.
+    pub fn with_cancellation_scope<F, T>(&self, cancellation: Cancellation, f: F) -> T
+    where
+        F: FnOnce() -> T,
+    {
+        struct Restore<'a>(&'a SharedCancellation, Cancellation);
+
+        impl Drop for Restore<'_> {
+            fn drop(&mut self) {
+                self.0.replace(std::mem::take(&mut self.1));
             }
+        }
+
+        let previous = self.cancellation.replace(cancellation);
+        let _restore = Restore(&self.cancellation, previous);
+        f()
+    }
+
+    /// Returns the current Kubernetes context.
+    pub fn kube_context(&self) -> Option<&str> {
+        self.kube_context
+            .get_or_init(crate::kube::current_context)
+            .as_deref()
+    }
+
+    /// Indicates if the effective user is root, or Administrator on systems other than Unix.
+    pub fn is_root(&self) -> bool {
+        if let Some(yes) = self.is_root {
+            return yes;
+        }
+        #[cfg(unix)]
+        {
+            // SAFETY: `geteuid` has no preconditions.
+            unsafe { libc::geteuid() == 0 }
+        }
+        #[cfg(not(unix))]
+        {
+            whoami::username() == "Administrator"
+        }
+    }
+
+    /// Restricts producers to information available locally, e.g. in SSH sessions.
+    ///
+    /// Producers that would otherwise reach the network show what they have cached instead, and
+    /// blocks based on information last fetched from the network, such as upstream git counts,
+    /// are marked with [`symbol::LOCAL_ONLY`].
+    pub fn restrict_to_local(&self) {
+        self.local_only.set(true);
+    }
+
+    /// Indicates if producers are restricted to information available locally.
+    pub fn is_local_only(&self) -> bool {
+        self.local_only.get()
+    }
+
+    /// Marks `blocks` as based on information that may be out of date because the network is not
+    /// reached, if producers are restricted to local information.
+    pub fn annotate_local_only(&self, mut blocks: Vec<Block>) -> Vec<Block> {
+        if !self.is_local_only() {
+            return blocks;
+        }
+        if let Some(style) = blocks.last().map(|block| block.style.clone()) {
+            blocks.push(Block::new(symbol::LOCAL_ONLY.get(self.symbols())).with_style(style));
+        }
+        blocks
+    }
+
+    /// Returns the directory where results are cached across prompts, if caching is enabled.
+    pub fn cache_dir(&self) -> Option<&Path> {
+        self.cache_dir.as_deref()
+    }
+
+    /// Returns the cache for results computed from the git repository, if caching is enabled.
+    ///
+    /// Cached values are discarded when HEAD or the index change.
+    pub fn git_cache(&self) -> Option<RefMut<'_, Cache>> {
+        let cache = self.git_cache.get_or_init(|| {
+            let dir = self.cache_dir.as_ref().filter(|_| self.cache_git)?;
+            let git = self.git()?;
+            let commit = git.head().ok()?.commit()?.to_owned();
+            let index_mtime = fs::metadata(git.git_dir().join("index"))
+                .and_then(|m| m.modified())
+                .ok()
+                .and_then(|t| t.duration_since(SystemTime::UNIX_EPOCH).ok())
+                .unwrap_or_default();
+            let key = format!("{}:{}", commit, index_mtime.as_nanos());
+            let path = dir.join("git").join(cache::file_name(git.git_dir()));
+            Some(RefCell::new(Cache::open(path, key)))
         });
-        repo.as_ref()
+        cache.as_ref().map(RefCell::borrow_mut)
+    }
+
+    /// Returns the cache for information about installed tools, e.g. their versions, if caching
+    /// is enabled.
+    pub fn tool_cache(&self) -> Option<RefMut<'_, Cache>> {
+        let cache = self.tool_cache.get_or_init(|| {
+            let path = self.cache_dir.as_ref()?.join("tools.json");
+            Some(RefCell::new(Cache::open(path, String::new())))
+        });
+        cache.as_ref().map(RefCell::borrow_mut)
+    }
+
+    /// Returns the cache for information about the git repositories in the working directory, if
+    /// caching git results is enabled.
+    pub fn workspace_cache(&self) -> Option<RefMut<'_, Cache>> {
+        let cache = self.workspace_cache.get_or_init(|| {
+            let dir = self.cache_dir.as_ref().filter(|_| self.cache_git)?;
+            let working_dir = self.working_dir()?;
+            let path = dir.join("workspaces").join(cache::file_name(working_dir));
+            Some(RefCell::new(Cache::open(path, String::new())))
+        });
+        cache.as_ref().map(RefCell::borrow_mut)
     }
 
     pub fn prev_exit_code(&self) -> i32 {
@@ -87,6 +507,129 @@ impl Environment {
     pub fn prev_cmd_duration(&self) -> Option<Duration> {
         self.prev_cmd_duration
     }
+
+    pub fn marker(&self) -> Option<&str> {
+        self.marker.as_deref()
+    }
+
+    /// Returns the named directories of the shell, as names and paths.
+    pub fn named_dirs(&self) -> &[(String, PathBuf)] {
+        &self.named_dirs
+    }
+
+    /// Indicates if the visibility of the named group was flipped.
+    pub fn group_is_toggled(&self, name: &str) -> bool {
+        self.toggled_groups.contains(name)
+    }
+
+    /// Returns how long the previous command was running, excluding time it was suspended.
+    pub fn prev_cmd_active_duration(&self) -> Option<Duration> {
+        Some(
+            self.prev_cmd_duration?
+                .saturating_sub(self.prev_cmd_suspended),
+        )
+    }
+
+    pub fn sticky_blocks(&self) -> RefMut<'_, StickyBlocks> {
+        self.sticky_blocks.borrow_mut()
+    }
+
+    /// Records a note to remove once the prompt is printed.
+    pub(crate) fn note_shown(&self, note: ShownNote) {
+        self.shown_notes.borrow_mut().push(note);
+    }
+
+    /// Returns the notes shown by [`Note`](crate::block::Note) producers, which the caller
+    /// removes once the prompt is printed.
+    pub fn take_shown_notes(&self) -> Vec<ShownNote> {
+        self.shown_notes.take()
+    }
+
+    /// Returns the blocks to persist for [`Sticky`](crate::block::Sticky) producers.
+    pub fn into_sticky_blocks(self) -> StickyBlocks {
+        self.sticky_blocks.into_inner()
+    }
+
+    /// Returns variables describing this environment, for use by shell functions.
+    ///
+    /// Variables without a value are meant to be unset.
+    pub fn variables(&self) -> Vec<(&'static str, Option<String>)> {
+        let git = self.git();
+        let head = git.and_then(|git| git.head().ok());
+        let commit = head
+            .as_ref()
+            .and_then(|head| Some(head.commit()?.to_owned()));
+        let branch = match head {
+            Some(Head::Branch { name, .. }) => Some(name),
+            _ => None,
+        };
+        vec![
+            (
+                "ELIPROMPT_PWD",
+                self.working_dir().map(|p| p.to_string_lossy().into_owned()),
+            ),
+            ("ELIPROMPT_EXIT_CODE", Some(self.prev_exit_code.to_string())),
+            (
+                "ELIPROMPT_DURATION_MS",
+                self.prev_cmd_duration.map(|d| d.as_millis().to_string()),
+            ),
+            (
+                "ELIPROMPT_GIT_ROOT",
+                git.and_then(|git| {
+                    let root = git.workdir()?.components();
+                    Some(root.as_path().to_string_lossy().into_owned())
+                }),
+            ),
+            ("ELIPROMPT_GIT_BRANCH", branch),
+            ("ELIPROMPT_GIT_COMMIT", commit),
+            (
+                "ELIPROMPT_GIT_OPERATION",
+                git.and_then(crate::block::operation_label),
+            ),
+        ]
+    }
+
+    /// Returns a command running `program` in the working directory, with the variables returned
+    /// by [`Environment::variables`] set, so that external programs can adapt to the context
+    /// without detecting it again.
+    pub fn command<S>(&self, program: S) -> Command
+    where
+        S: AsRef<OsStr>,
+    {
+        let mut command = Command::new(program);
+        if let Some(dir) = self.working_dir().filter(|dir| dir.is_dir()) {
+            command.current_dir(dir);
+        }
+        for (name, value) in self.variables() {
+            match value {
+                Some(value) => command.env(name, value),
+                None => command.env_remove(name),
+            };
+        }
+        command
+    }
+
+    /// Panics or stalls until cancelled if a fault is to be injected in the producer with the
+    /// given name.
+    pub fn inject_faults(&self, producer: &str) {
+        if self
+            .faults
+            .errors
+            .iter()
+            .any(|p| producer_matches(producer, p))
+        {
+            panic!("Injected error in {} producer", producer);
+        }
+        if self
+            .faults
+            .timeouts
+            .iter()
+            .any(|p| producer_matches(producer, p))
+        {
+            tracing::warn!("Injected timeout in {} producer", producer);
+            self.cancellation().wait(Duration::from_millis(10));
+        }
+    }
 }
 
 impl Debug for Environment {
@@ -95,6 +638,160 @@ impl Debug for Environment {
             .field("working_dir", &self.working_dir)
             .field("prev_exit_code", &self.prev_exit_code)
             .field("prev_cmd_duration", &self.prev_cmd_duration)
+            .field("prev_cmd_suspended", &self.prev_cmd_suspended)
+            .field("marker", &self.marker)
+            .field("toggled_groups", &self.toggled_groups)
+            .field("named_dirs", &self.named_dirs)
+            .field("terminal_width", &self.terminal_width)
+            .field("local_only", &self.local_only)
+            .field("faults", &self.faults)
+            .field("cancellation", &self.cancellation)
             .finish()
     }
 }
+
+/// Faults to inject in producers to check how the prompt behaves when they fail
+///
+/// Each pattern selects the producers whose name starts with it, ignoring case (e.g. `git`
+/// selects all git producers).
+#[derive(Clone, Debug, Default)]
+pub struct FaultInjection {
+    /// Producers that panic
+    pub errors: Vec<String>,
+    /// Producers that only finish when cancelled
+    pub timeouts: Vec<String>,
+}
+
+/// Returns the client address from the value of `SSH_CONNECTION` or `SSH_CLIENT`, e.g.
+/// `192.0.2.1 52144 22`.
+fn ssh_client_address(value: &str) -> Option<&str> {
+    value.split_whitespace().next()
+}
+
+fn producer_matches(producer: &str, pattern: &str) -> bool {
+    producer
+        .get(..pattern.len())
+        .is_some_and(|p| p.eq_ignore_ascii_case(pattern))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ssh_client_address, Environment, FaultInjection};
+    use crate::{git::fixture, symbol, Block, Cancellation};
+    use std::{
+        fs,
+        time::{Duration, Instant},
+    };
+
+    fn faults(errors: &[&str], timeouts: &[&str]) -> FaultInjection {
+        FaultInjection {
+            errors: errors.iter().map(|&p| p.to_owned()).collect(),
+            timeouts: timeouts.iter().map(|&p| p.to_owned()).collect(),
+        }
+    }
+
+    #[test]
+    fn environment_is_send() {
+        fn assert_send<T: Send>() {}
+        assert_send::<Environment>();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn jj_commands_follow_current_cancellation_scope() {
+        use crate::{
+            block::{JjChange, Timeout},
+            jj::JjRepo,
+            BlockProducer,
+        };
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir(dir.path().join(".jj")).unwrap();
+        let stub = dir.path().join("jj");
+        fs::write(&stub, "#!/bin/sh\nsleep 1\nprintf 'kmkuslsw\\n\\n'\n").unwrap();
+        fs::set_permissions(&stub, fs::Permissions::from_mode(0o755)).unwrap();
+        let environment = Environment::new(Some(dir.path().to_owned()));
+        let repo = JjRepo::discover(dir.path())
+            .unwrap()
+            .with_program(&stub)
+            .with_cancellation(environment.cancellation.clone());
+        environment.jj.set(Some(repo)).unwrap();
+        let change = BlockProducer::JjChange(JjChange::new());
+        let start = Instant::now();
+        let timeout = Timeout::new(Duration::from_millis(50), change.clone());
+        assert!(timeout.produce(&environment).is_empty());
+        assert!(start.elapsed() < Duration::from_secs(1));
+        let blocks = change.produce(&environment);
+        assert_eq!(blocks.last().unwrap().text, "kmkuslsw");
+    }
+
+    #[test]
+    fn git_is_ignored_in_colocated_jj_repositories() {
+        let dir = tempfile::tempdir().unwrap();
+        fixture::init(dir.path());
+        let inner = dir.path().join("inner");
+        fs::create_dir_all(inner.join(".jj")).unwrap();
+        assert!(Environment::new(Some(dir.path().to_owned()))
+            .git()
+            .is_some());
+        assert!(Environment::new(Some(inner.clone())).git().is_none());
+        fs::create_dir(dir.path().join(".jj")).unwrap();
+        assert!(Environment::new(Some(dir.path().to_owned()))
+            .git()
+            .is_none());
+        let outer = tempfile::tempdir().unwrap();
+        fs::create_dir(outer.path().join(".jj")).unwrap();
+        let nested = outer.path().join("nested");
+        fixture::init(&nested);
+        assert!(Environment::new(Some(nested)).git().is_some());
+    }
+
+    #[test]
+    fn injected_timeouts_stall_until_cancelled() {
+        let environment = Environment::new(None)
+            .with_fault_injection(faults(&[], &["git"]))
+            .with_cancellation(Cancellation::with_timeout(Duration::from_millis(50)));
+        let start = Instant::now();
+        environment.inject_faults("Hostname");
+        assert!(!environment.is_cancelled());
+        environment.inject_faults("GitHead");
+        assert!(environment.is_cancelled());
+        assert!(start.elapsed() >= Duration::from_millis(50));
+    }
+
+    #[test]
+    fn blocks_are_annotated_only_when_local_only() {
+        let environment = Environment::new(None);
+        let blocks = || vec![Block::new("a")];
+        assert_eq!(environment.annotate_local_only(blocks()).len(), 1);
+        environment.restrict_to_local();
+        let annotated = environment.annotate_local_only(blocks());
+        assert_eq!(
+            annotated[1].text,
+            symbol::LOCAL_ONLY.get(environment.symbols())
+        );
+        assert!(environment.annotate_local_only(Vec::new()).is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "Injected error in GitStatus producer")]
+    fn injected_errors_panic() {
+        Environment::new(None)
+            .with_fault_injection(faults(&["GITSTATUS"], &[]))
+            .inject_faults("GitStatus");
+    }
+
+    #[test]
+    fn ssh_client_is_first_field() {
+        assert_eq!(
+            ssh_client_address("192.0.2.1 52144 198.51.100.2 22"),
+            Some("192.0.2.1")
+        );
+        assert_eq!(
+            ssh_client_address("2001:db8::1 52144 22"),
+            Some("2001:db8::1")
+        );
+        assert_eq!(ssh_client_address(" "), None);
+    }
+}
@@ -0,0 +1,110 @@
+// Copyright (C) 2020 Stephane Raux. Distributed under the MIT license.
+
+//! C API to render prompts without spawning `eliprompt`.
+//!
+//! Build the shared library with `cargo build --lib --release --features ffi`.
+
+use crate::{
+    block::ShownNote,
+    cache,
+    shell::{self, Bash, GenericShell, ShellType, Zsh},
+    Cancellation, Config, Environment,
+};
+use serde::Deserialize;
+use std::{
+    ffi::{c_char, CStr, CString},
+    panic,
+    path::PathBuf,
+    ptr,
+    time::Duration,
+};
+
+/// Description of the environment to render a prompt in
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct EnvSpec {
+    working_dir: Option<PathBuf>,
+    exit_code: i32,
+    duration_ms: Option<u64>,
+    alternative_prompt: bool,
+    shell: ShellType,
+    columns: Option<usize>,
+}
+
+/// Renders a prompt and returns it as a string to free with [`eliprompt_free`].
+///
+/// `config_json` is a configuration as found in `eliprompt.json`, or null to use the default
+/// configuration. `env_json` is an object with the following optional fields: `working_dir`,
+/// `exit_code`, `duration_ms`, `alternative_prompt`, `shell` (`generic`, `bash` or `zsh`) and
+/// `columns` (terminal width). It can also be null to use the current environment.
+///
+/// Blocks not produced within the configured timeout are left out. Null is returned if an
+/// argument is invalid or rendering fails.
+///
+/// # Safety
+/// Non-null arguments must point to valid nul-terminated strings.
+#[no_mangle]
+pub unsafe extern "C" fn eliprompt_render(
+    config_json: *const c_char,
+    env_json: *const c_char,
+) -> *mut c_char {
+    let config_json = (!config_json.is_null()).then(|| CStr::from_ptr(config_json).to_bytes());
+    let env_json = (!env_json.is_null()).then(|| CStr::from_ptr(env_json).to_bytes());
+    match panic::catch_unwind(|| render(config_json, env_json)) {
+        Ok(Some(prompt)) => prompt.into_raw(),
+        Ok(None) => ptr::null_mut(),
+        Err(_) => {
+            tracing::error!("Prompt generation panicked");
+            ptr::null_mut()
+        }
+    }
+}
+
+/// Frees a string returned by [`eliprompt_render`].
+///
+/// # Safety
+/// `prompt` must be null or a pointer returned by [`eliprompt_render`] that was not freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn eliprompt_free(prompt: *mut c_char) {
+    if !prompt.is_null() {
+        drop(CString::from_raw(prompt));
+    }
+}
+
+fn render(config_json: Option<&[u8]>, env_json: Option<&[u8]>) -> Option<CString> {
+    let config = match config_json.map(Config::from_json).transpose() {
+        Ok(config) => config.unwrap_or_else(Config::default_pretty),
+        Err(e) => {
+            tracing::error!("Configuration is invalid: {}", e);
+            return None;
+        }
+    };
+    let spec = match env_json.map(serde_json::from_slice::<EnvSpec>).transpose() {
+        Ok(spec) => spec.unwrap_or_default(),
+        Err(e) => {
+            tracing::error!("Environment is invalid: {}", e);
+            return None;
+        }
+    };
+    let duration = spec.duration_ms.map(Duration::from_millis);
+    let environment = Environment::for_prompt(spec.working_dir, spec.exit_code, duration)
+        .force_alternative_prompt(spec.alternative_prompt)
+        .with_terminal_width(spec.columns)
+        .with_cancellation(Cancellation::with_timeout(config.timeout))
+        .with_cache_dir(cache::default_dir())
+        .cache_git(config.cache_git);
+    let blocks = config.produce(&environment);
+    let mut buffer = Vec::new();
+    let written = match spec.shell {
+        ShellType::Generic => shell::write_blocks(&mut GenericShell(&mut buffer), blocks),
+        ShellType::Bash => shell::write_blocks(&mut Bash(&mut buffer), blocks),
+        ShellType::Zsh => shell::write_blocks(&mut Zsh(&mut buffer), blocks),
+    };
+    written.expect("Writing to a vector cannot fail");
+    environment
+        .take_shown_notes()
+        .iter()
+        .for_each(ShownNote::remove);
+    // Text is sanitized so it cannot contain nul characters.
+    CString::new(buffer).ok()
+}
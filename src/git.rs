@@ -0,0 +1,343 @@
+// Copyright (C) 2020 Stephane Raux. Distributed under the MIT license.
+
+//! Git repository access independent of the library used to read it.
+//!
+//! Repositories are read with `git2` by default. Enabling the `gix` feature reads them with
+//! gitoxide instead. Producers needing information not available here (the status, submodules,
+//! tags and upstream of the repository) still go through
+//! [`Environment::repo`](crate::Environment::repo), which opens the repository with `git2` a
+//! second time when the `gix` feature is enabled.
+
+use std::path::Path;
+use thiserror::Error;
+
+/// Git repository opened with the selected backend
+pub struct GitRepo {
+    #[cfg(not(feature = "gix"))]
+    inner: git2::Repository,
+    #[cfg(feature = "gix")]
+    inner: gix::Repository,
+}
+
+/// What HEAD refers to
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Head {
+    /// HEAD is a branch. `commit` is `None` if the branch does not exist yet.
+    Branch {
+        name: String,
+        commit: Option<String>,
+    },
+    /// HEAD refers to a commit directly.
+    Detached(String),
+}
+
+impl Head {
+    pub fn commit(&self) -> Option<&str> {
+        match self {
+            Head::Branch { commit, .. } => commit.as_deref(),
+            Head::Detached(commit) => Some(commit),
+        }
+    }
+}
+
+/// Operation in progress in a repository
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Operation {
+    Merge,
+    Revert,
+    CherryPick,
+    Bisect,
+    Rebase,
+    ApplyMailbox,
+    ApplyMailboxOrRebase,
+}
+
+#[derive(Debug, Error)]
+#[error(transparent)]
+pub struct GitError(Box<dyn std::error::Error + Send + Sync>);
+
+#[cfg(not(feature = "gix"))]
+impl From<git2::Error> for GitError {
+    fn from(e: git2::Error) -> Self {
+        GitError(Box::new(e))
+    }
+}
+
+#[cfg(not(feature = "gix"))]
+impl GitRepo {
+    /// Looks for a repository containing `dir`, returning `None` if there is none.
+    pub fn discover(dir: &Path) -> Result<Option<Self>, GitError> {
+        match git2::Repository::discover(dir) {
+            Ok(inner) => Ok(Some(GitRepo { inner })),
+            Err(e) if e.code() == git2::ErrorCode::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Returns the path to the `.git` directory.
+    pub fn git_dir(&self) -> &Path {
+        self.inner.path()
+    }
+
+    pub fn workdir(&self) -> Option<&Path> {
+        self.inner.workdir()
+    }
+
+    pub fn is_bare(&self) -> bool {
+        self.inner.is_bare()
+    }
+
+    pub fn head(&self) -> Result<Head, GitError> {
+        let head = match self.inner.head() {
+            Ok(head) => head,
+            Err(e) if e.code() == git2::ErrorCode::UnbornBranch => {
+                let head = self.inner.find_reference("HEAD")?;
+                let name = head
+                    .symbolic_target()
+                    .map(|target| target.strip_prefix("refs/heads/").unwrap_or(target))
+                    .unwrap_or("master");
+                return Ok(Head::Branch {
+                    name: name.to_owned(),
+                    commit: None,
+                });
+            }
+            Err(e) => return Err(e.into()),
+        };
+        let commit = head.target().map(|oid| oid.to_string());
+        match commit {
+            Some(commit) if self.inner.head_detached()? => Ok(Head::Detached(commit)),
+            commit => Ok(Head::Branch {
+                name: String::from_utf8_lossy(head.shorthand_bytes()).into_owned(),
+                commit,
+            }),
+        }
+    }
+
+    /// Returns the number of paths with unresolved conflicts.
+    pub fn conflict_count(&self) -> Result<usize, GitError> {
+        let index = self.inner.index()?;
+        if !index.has_conflicts() {
+            return Ok(0);
+        }
+        let count = index.conflicts()?.filter(Result::is_ok).count();
+        Ok(count)
+    }
+
+    /// Returns the commit time of HEAD in seconds since the Unix epoch, or `None` if HEAD does
+    /// not point to a commit yet.
+    pub fn head_commit_time(&self) -> Result<Option<i64>, GitError> {
+        match self.inner.head().and_then(|head| head.peel_to_commit()) {
+            Ok(commit) => Ok(Some(commit.time().seconds())),
+            Err(e) if e.code() == git2::ErrorCode::UnbornBranch => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Returns the value of the configuration variable `key`, if it is set.
+    pub fn config_string(&self, key: &str) -> Result<Option<String>, GitError> {
+        match self.inner.config()?.get_string(key) {
+            Ok(value) => Ok(Some(value)),
+            Err(e) if e.code() == git2::ErrorCode::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Returns the fetch URL of the remote `name`, if it exists.
+    pub fn remote_url(&self, name: &str) -> Option<String> {
+        self.inner.find_remote(name).ok()?.url().map(str::to_owned)
+    }
+
+    /// Returns the operation in progress, if any.
+    pub fn operation(&self) -> Option<Operation> {
+        use git2::RepositoryState;
+
+        let operation = match self.inner.state() {
+            RepositoryState::Clean => return None,
+            RepositoryState::Merge => Operation::Merge,
+            RepositoryState::Revert | RepositoryState::RevertSequence => Operation::Revert,
+            RepositoryState::CherryPick | RepositoryState::CherryPickSequence => {
+                Operation::CherryPick
+            }
+            RepositoryState::Bisect => Operation::Bisect,
+            RepositoryState::Rebase
+            | RepositoryState::RebaseInteractive
+            | RepositoryState::RebaseMerge => Operation::Rebase,
+            RepositoryState::ApplyMailbox => Operation::ApplyMailbox,
+            RepositoryState::ApplyMailboxOrRebase => Operation::ApplyMailboxOrRebase,
+        };
+        Some(operation)
+    }
+
+    /// Returns the underlying `git2` repository.
+    pub(crate) fn as_git2(&self) -> Option<&git2::Repository> {
+        Some(&self.inner)
+    }
+}
+
+#[cfg(feature = "gix")]
+impl GitRepo {
+    /// Looks for a repository containing `dir`, returning `None` if there is none.
+    pub fn discover(dir: &Path) -> Result<Option<Self>, GitError> {
+        use gix::discover::{upwards, Error};
+
+        match gix::discover(dir) {
+            Ok(inner) => Ok(Some(GitRepo { inner })),
+            Err(Error::Discover(
+                upwards::Error::NoGitRepository { .. }
+                | upwards::Error::NoGitRepositoryWithinCeiling { .. }
+                | upwards::Error::NoGitRepositoryWithinFs { .. },
+            )) => Ok(None),
+            Err(e) => Err(GitError(Box::new(e))),
+        }
+    }
+
+    /// Returns the path to the `.git` directory.
+    pub fn git_dir(&self) -> &Path {
+        self.inner.git_dir()
+    }
+
+    pub fn workdir(&self) -> Option<&Path> {
+        self.inner.work_dir()
+    }
+
+    pub fn is_bare(&self) -> bool {
+        self.inner.is_bare()
+    }
+
+    pub fn head(&self) -> Result<Head, GitError> {
+        use gix::head::Kind;
+
+        let head = self.inner.head().map_err(|e| GitError(Box::new(e)))?;
+        let commit = head.id().map(|id| id.to_string());
+        let head = match head.kind {
+            Kind::Symbolic(reference) => Head::Branch {
+                name: reference.name.shorten().to_string(),
+                commit,
+            },
+            Kind::Unborn(name) => Head::Branch {
+                name: name.shorten().to_string(),
+                commit: None,
+            },
+            Kind::Detached { target, .. } => Head::Detached(target.to_string()),
+        };
+        Ok(head)
+    }
+
+    /// Returns the number of paths with unresolved conflicts.
+    pub fn conflict_count(&self) -> Result<usize, GitError> {
+        let index = self
+            .inner
+            .index_or_empty()
+            .map_err(|e| GitError(Box::new(e)))?;
+        let mut conflicted = index
+            .entries()
+            .iter()
+            .filter(|entry| entry.stage_raw() != 0)
+            .map(|entry| entry.path(&index))
+            .collect::<Vec<_>>();
+        conflicted.dedup();
+        Ok(conflicted.len())
+    }
+
+    /// Returns the commit time of HEAD in seconds since the Unix epoch, or `None` if HEAD does
+    /// not point to a commit yet.
+    pub fn head_commit_time(&self) -> Result<Option<i64>, GitError> {
+        let head = self.inner.head().map_err(|e| GitError(Box::new(e)))?;
+        if head.id().is_none() {
+            return Ok(None);
+        }
+        let time = self
+            .inner
+            .head_commit()
+            .map_err(|e| GitError(Box::new(e)))?
+            .time()
+            .map_err(|e| GitError(Box::new(e)))?;
+        Ok(Some(time.seconds))
+    }
+
+    /// Returns the value of the configuration variable `key`, if it is set.
+    pub fn config_string(&self, key: &str) -> Result<Option<String>, GitError> {
+        let value = self.inner.config_snapshot().string(key);
+        Ok(value.map(|value| value.to_string()))
+    }
+
+    /// Returns the fetch URL of the remote `name`, if it exists.
+    pub fn remote_url(&self, name: &str) -> Option<String> {
+        let remote = self.inner.find_remote(name).ok()?;
+        let url = remote.url(gix::remote::Direction::Fetch)?;
+        Some(url.to_bstring().to_string())
+    }
+
+    /// Returns the operation in progress, if any.
+    pub fn operation(&self) -> Option<Operation> {
+        use gix::state::InProgress;
+
+        let operation = match self.inner.state()? {
+            InProgress::Merge => Operation::Merge,
+            InProgress::Revert | InProgress::RevertSequence => Operation::Revert,
+            InProgress::CherryPick | InProgress::CherryPickSequence => Operation::CherryPick,
+            InProgress::Bisect => Operation::Bisect,
+            InProgress::Rebase | InProgress::RebaseInteractive => Operation::Rebase,
+            InProgress::ApplyMailbox => Operation::ApplyMailbox,
+            InProgress::ApplyMailboxRebase => Operation::ApplyMailboxOrRebase,
+        };
+        Some(operation)
+    }
+
+    /// Returns the underlying `git2` repository, which does not exist with this backend.
+    pub(crate) fn as_git2(&self) -> Option<&git2::Repository> {
+        None
+    }
+}
+
+/// Helpers creating git repositories in tests
+#[cfg(test)]
+pub(crate) mod fixture {
+    use git2::{Oid, Repository, Signature};
+    use std::{fs, path::Path};
+
+    /// Creates a repository with no commits in `dir`.
+    pub(crate) fn init(dir: &Path) -> Repository {
+        let repo = Repository::init(dir).unwrap();
+        let mut config = repo.config().unwrap();
+        config.set_str("user.name", "Test").unwrap();
+        config.set_str("user.email", "test@example.com").unwrap();
+        repo
+    }
+
+    /// Writes `contents` to `path` in the working tree of `repo` and stages it.
+    pub(crate) fn stage(repo: &Repository, path: &str, contents: &str) {
+        let workdir = repo.workdir().unwrap();
+        let file = workdir.join(path);
+        fs::create_dir_all(file.parent().unwrap()).unwrap();
+        fs::write(file, contents).unwrap();
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new(path)).unwrap();
+        index.write().unwrap();
+    }
+
+    /// Commits the index of `repo` on top of HEAD.
+    pub(crate) fn commit(repo: &Repository, message: &str) -> Oid {
+        let signature = Signature::now("Test", "test@example.com").unwrap();
+        let tree = repo
+            .find_tree(repo.index().unwrap().write_tree().unwrap())
+            .unwrap();
+        let parent = repo.head().ok().and_then(|head| head.peel_to_commit().ok());
+        let parents = parent.iter().collect::<Vec<_>>();
+        repo.commit(
+            Some("HEAD"),
+            &signature,
+            &signature,
+            message,
+            &tree,
+            &parents,
+        )
+        .unwrap()
+    }
+
+    /// Writes, stages and commits `path` with `contents`.
+    pub(crate) fn commit_file(repo: &Repository, path: &str, contents: &str) -> Oid {
+        stage(repo, path, contents);
+        commit(repo, &format!("Update {}", path))
+    }
+}
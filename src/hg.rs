@@ -0,0 +1,68 @@
+// Copyright (C) 2020 Stephane Raux. Distributed under the MIT license.
+
+//! [Mercurial](https://www.mercurial-scm.org) repository information.
+//!
+//! Information is read from the `.hg` directory, as running `hg` is slow.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Mercurial repository containing the working directory
+#[derive(Debug)]
+pub struct HgRepo {
+    root: PathBuf,
+}
+
+impl HgRepo {
+    /// Looks for a repository containing `dir`.
+    pub fn discover(dir: &Path) -> Option<Self> {
+        let root = crate::vcs::find_root(dir, ".hg")?;
+        Some(HgRepo {
+            root: root.to_owned(),
+        })
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Returns the name of the current branch.
+    pub fn branch(&self) -> String {
+        self.read(Path::new("branch"))
+            .unwrap_or_else(|| "default".into())
+    }
+
+    /// Returns the name of the active bookmark, if any.
+    pub fn bookmark(&self) -> Option<String> {
+        self.read(Path::new("bookmarks.current"))
+    }
+
+    fn read(&self, path: &Path) -> Option<String> {
+        let contents = fs::read_to_string(self.root.join(".hg").join(path)).ok()?;
+        let contents = contents.trim();
+        (!contents.is_empty()).then(|| contents.to_owned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::HgRepo;
+    use std::fs;
+
+    #[test]
+    fn branch_and_bookmark_are_read() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join(".hg")).unwrap();
+        fs::create_dir_all(dir.path().join("src")).unwrap();
+        let repo = HgRepo::discover(&dir.path().join("src")).unwrap();
+        assert_eq!(repo.root(), dir.path());
+        assert_eq!(repo.branch(), "default");
+        assert_eq!(repo.bookmark(), None);
+        fs::write(dir.path().join(".hg/branch"), "stable\n").unwrap();
+        fs::write(dir.path().join(".hg/bookmarks.current"), "feature").unwrap();
+        assert_eq!(repo.branch(), "stable");
+        assert_eq!(repo.bookmark().as_deref(), Some("feature"));
+    }
+}
@@ -0,0 +1,135 @@
+// Copyright (C) 2020 Stephane Raux. Distributed under the MIT license.
+
+//! [Jujutsu](https://github.com/martinvonz/jj) repository information.
+//!
+//! Jujutsu does not offer a stable library interface, so information is obtained by running `jj`.
+
+use crate::cancel::SharedCancellation;
+use once_cell::unsync::OnceCell;
+use std::{
+    ffi::OsString,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+/// Jujutsu repository containing the working directory
+#[derive(Debug)]
+pub struct JjRepo {
+    root: PathBuf,
+    program: OsString,
+    cancellation: SharedCancellation,
+    working_copy: OnceCell<Option<WorkingCopy>>,
+}
+
+/// Information about the working-copy change
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct WorkingCopy {
+    /// Shortest unique prefix of the change id, at least 8 characters long
+    pub change_id: String,
+    pub bookmarks: Vec<String>,
+    /// First line of the description
+    pub description: String,
+}
+
+const TEMPLATE: &str = concat!(
+    r#"change_id.shortest(8) ++ "\n" ++ "#,
+    r#"local_bookmarks.join(" ") ++ "\n" ++ "#,
+    r#"description.first_line()"#,
+);
+
+impl JjRepo {
+    /// Looks for a repository containing `dir`.
+    pub fn discover(dir: &Path) -> Option<Self> {
+        let root = crate::vcs::find_root(dir, ".jj")?;
+        Some(JjRepo {
+            root: root.to_owned(),
+            program: "jj".into(),
+            cancellation: Default::default(),
+            working_copy: OnceCell::new(),
+        })
+    }
+
+    /// Shares the token killing the commands run to get information once cancelled. The token is
+    /// read whenever a command runs, so that commands follow the current cancellation scope.
+    pub(crate) fn with_cancellation(self, cancellation: SharedCancellation) -> Self {
+        Self {
+            cancellation,
+            ..self
+        }
+    }
+
+    /// Runs `program` instead of `jj`, e.g. a stub.
+    #[cfg(test)]
+    pub(crate) fn with_program<T>(self, program: T) -> Self
+    where
+        T: Into<OsString>,
+    {
+        Self {
+            program: program.into(),
+            ..self
+        }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Returns information about the working-copy change, as of the last jj command.
+    ///
+    /// The working copy is not snapshotted, so that the prompt neither waits for it nor races
+    /// with other jj commands.
+    pub fn working_copy(&self) -> Option<&WorkingCopy> {
+        let working_copy = self
+            .working_copy
+            .get_or_try_init(|| {
+                let cancellation = self.cancellation.get();
+                let output = crate::process::output(
+                    Command::new(&self.program)
+                        .args([
+                            "log",
+                            "--no-graph",
+                            "--ignore-working-copy",
+                            "--color=never",
+                        ])
+                        .args(["-r", "@", "-T", TEMPLATE])
+                        .current_dir(&self.root),
+                    &cancellation,
+                );
+                // A command killed by a scoped deadline may succeed for a later producer.
+                match output {
+                    None if cancellation.is_cancelled() => Err(()),
+                    output => Ok(output.map(|output| parse_working_copy(&output))),
+                }
+            })
+            .ok()?;
+        working_copy.as_ref()
+    }
+}
+
+fn parse_working_copy(output: &str) -> WorkingCopy {
+    let mut lines = output.lines();
+    let mut next = || lines.next().unwrap_or_default().trim().to_owned();
+    WorkingCopy {
+        change_id: next(),
+        bookmarks: next().split_whitespace().map(ToOwned::to_owned).collect(),
+        description: next(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_working_copy, WorkingCopy};
+
+    #[test]
+    fn working_copy_is_parsed() {
+        assert_eq!(
+            parse_working_copy("kmkuslsw\nmain feature\nFix the thing"),
+            WorkingCopy {
+                change_id: "kmkuslsw".into(),
+                bookmarks: vec!["main".into(), "feature".into()],
+                description: "Fix the thing".into(),
+            }
+        );
+        assert_eq!(parse_working_copy("kmkuslsw\n\n").description, "");
+    }
+}
@@ -0,0 +1,114 @@
+// Copyright (C) 2020 Stephane Raux. Distributed under the MIT license.
+
+//! Kubernetes client configuration.
+
+use std::{env, fs, path::PathBuf};
+
+/// Returns the paths of the kubeconfig files, in order of precedence.
+pub fn config_paths() -> Vec<PathBuf> {
+    match env::var_os("KUBECONFIG") {
+        Some(paths) if !paths.is_empty() => env::split_paths(&paths).collect(),
+        _ => dirs::home_dir()
+            .map(|home| home.join(".kube").join("config"))
+            .into_iter()
+            .collect(),
+    }
+}
+
+/// Returns the current context, as found in the first kubeconfig file defining one.
+pub fn current_context() -> Option<String> {
+    config_paths().into_iter().find_map(|path| {
+        let contents = fs::read_to_string(path).ok()?;
+        parse_current_context(&contents)
+    })
+}
+
+/// Returns the namespace of `context`, as found in the first kubeconfig file defining the context.
+pub fn context_namespace(context: &str) -> Option<String> {
+    config_paths().into_iter().find_map(|path| {
+        let contents = fs::read_to_string(path).ok()?;
+        parse_context_namespace(&contents, context)
+    })?
+}
+
+/// Returns the top-level `current-context` value of a kubeconfig file, without a full YAML parser.
+fn parse_current_context(contents: &str) -> Option<String> {
+    let value = contents
+        .lines()
+        .find_map(|line| line.strip_prefix("current-context:"))?
+        .trim();
+    let value = value
+        .strip_prefix('"')
+        .and_then(|v| v.strip_suffix('"'))
+        .or_else(|| value.strip_prefix('\'').and_then(|v| v.strip_suffix('\'')))
+        .unwrap_or(value);
+    (!value.is_empty()).then(|| value.to_owned())
+}
+
+/// Returns the namespace of `context` in a kubeconfig file, without a full YAML parser.
+///
+/// The outer `Option` is `None` if the file does not define the context.
+fn parse_context_namespace(contents: &str, context: &str) -> Option<Option<String>> {
+    let lines = contents
+        .lines()
+        .skip_while(|line| line.trim_end() != "contexts:")
+        .skip(1)
+        .take_while(|line| line.starts_with([' ', '-']) || line.trim().is_empty());
+    let mut items = Vec::<Vec<&str>>::new();
+    let mut item_indent = None;
+    for line in lines {
+        let indent = line.len() - line.trim_start().len();
+        let is_item_start = line.trim_start().starts_with("- ");
+        if is_item_start && item_indent.is_none_or(|i| i == indent) {
+            item_indent = Some(indent);
+            items.push(Vec::new());
+        }
+        if let Some(item) = items.last_mut() {
+            item.push(line.trim_start().trim_start_matches("- ").trim());
+        }
+    }
+    let value = |line: &&str, key: &str| -> Option<String> {
+        let value = line.strip_prefix(key)?.strip_prefix(':')?.trim();
+        let value = value.trim_matches(|c| c == '"' || c == '\'');
+        (!value.is_empty()).then(|| value.to_owned())
+    };
+    let item = items.iter().find(|item| {
+        item.iter()
+            .any(|line| value(line, "name").as_deref() == Some(context))
+    })?;
+    Some(item.iter().find_map(|line| value(line, "namespace")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_context_namespace, parse_current_context};
+
+    #[test]
+    fn current_context_is_found() {
+        let config = "apiVersion: v1\ncontexts:\n- name: dev\ncurrent-context: \"prod-eu\"\n";
+        assert_eq!(parse_current_context(config).as_deref(), Some("prod-eu"));
+        assert_eq!(parse_current_context("current-context: \"\"\n"), None);
+    }
+
+    #[test]
+    fn context_namespace_is_found() {
+        let config = "\
+contexts:
+- context:
+    cluster: eu
+    namespace: payments
+    user: admin
+  name: prod-eu
+- name: dev
+  context:
+    cluster: local
+current-context: prod-eu
+";
+        assert_eq!(
+            parse_context_namespace(config, "prod-eu"),
+            Some(Some("payments".into()))
+        );
+        assert_eq!(parse_context_namespace(config, "dev"), Some(None));
+        assert_eq!(parse_context_namespace(config, "staging"), None);
+    }
+}
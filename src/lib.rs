@@ -4,16 +4,47 @@
 
 #![deny(warnings)]
 
+mod alert;
 pub mod block;
+pub mod cache;
+mod cancel;
 pub mod color;
 mod config;
+mod cursor;
 mod env;
 mod err;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+mod git;
+mod hg;
+mod jj;
+mod kube;
+mod mise;
+mod pattern;
+mod process;
+mod rustup;
+pub mod shell;
+mod shim;
 mod style;
+mod svn;
+pub mod symbol;
+mod template;
+mod vcs;
 
+pub use alert::{AlertCondition, AlertTheme, Palette};
 pub use block::{Block, BlockProducer};
+pub use cancel::Cancellation;
 pub use color::Color;
 pub use config::{default_alternative_prompt, default_pretty_prompt, fallback_prompt, Config};
-pub use env::Environment;
+pub use cursor::{CursorShape, CursorShapes};
+pub use env::{Environment, FaultInjection};
 pub use err::Error;
+pub use git::{GitError, GitRepo, Head, Operation};
+pub use hg::HgRepo;
+pub use jj::{JjRepo, WorkingCopy};
+pub use pattern::Pattern;
 pub use style::Style;
+pub use svn::SvnRepo;
+pub use symbol::{Symbol, SymbolSet};
+pub use template::{InvalidTemplate, Template};
+pub use vcs::Vcs;
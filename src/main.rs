@@ -3,21 +3,34 @@
 #![deny(warnings)]
 
 use clap::Parser;
-use eliprompt::{Block, Config, Environment};
+use eliprompt::{
+    block::{ShownNote, StickyBlocks},
+    cache,
+    shell::{Bash, GenericShell, Plain, Shell, ShellType, Zsh},
+    Block, BlockProducer, Cancellation, Config, CursorShape, Environment, FaultInjection,
+    SymbolSet,
+};
 use moniclock::Clock;
 use once_cell::sync::Lazy;
 use serde::{Deserialize, Serialize};
 use std::{
+    backtrace::Backtrace,
+    collections::BTreeSet,
     env,
     error::Error,
     fmt::{self, Display},
     fs,
-    io::{self, Write},
+    io::{self, Read, Write},
+    panic,
     path::{Path, PathBuf},
+    process,
     str::FromStr,
-    sync::mpsc::{sync_channel, RecvTimeoutError},
+    sync::{
+        mpsc::{sync_channel, RecvTimeoutError},
+        Mutex, PoisonError,
+    },
     thread,
-    time::{Duration, Instant},
+    time::{Duration, Instant, SystemTime},
 };
 use thiserror::Error;
 
@@ -26,11 +39,42 @@ use thiserror::Error;
 #[clap(author, version, about)]
 enum Command {
     Prompt(PromptCommand),
+    Preview(PreviewCommand),
     StartTimer(StartTimerCommand),
     StopTimer(StopTimerCommand),
+    PauseTimer(PauseTimerCommand),
+    ResumeTimer(ResumeTimerCommand),
     Install(InstallCommand),
+    Env(EnvCommand),
+    Mark(MarkCommand),
+    Toggle(ToggleCommand),
+    Refresh(RefreshCommand),
+    Cursor(CursorCommand),
     /// Prints default configuration
     PrintDefaultConfig,
+    Blocks(BlocksCommand),
+    #[clap(hide = true)]
+    EscapeCheck(EscapeCheckCommand),
+}
+
+/// Prints an empty line periodically until standard output is closed
+///
+/// This is meant to run in the background of an interactive shell, which redraws its right
+/// prompt on each line so that time-dependent blocks stay current without running commands. The
+/// zsh integration starts it with `eliprompt_start_refresh`.
+#[derive(Clone, Debug, Parser)]
+struct RefreshCommand {
+    /// Time between refreshes, e.g. 30s
+    #[clap(long, default_value = "30s", value_parser = parse_interval)]
+    interval: Duration,
+}
+
+/// Lists the available block producers with their configuration fields and default values
+#[derive(Clone, Debug, Parser)]
+struct BlocksCommand {
+    /// Prints the list as JSON
+    #[clap(long)]
+    json: bool,
 }
 
 /// Prints prompt
@@ -48,20 +92,82 @@ struct PromptCommand {
     /// Path to the configuration file
     #[clap(long = "config")]
     config_path: Option<PathBuf>,
+    /// Path to the system configuration file, which the user configuration is layered over
+    ///
+    /// Defaults to /etc/eliprompt/config.json, which is ignored if it does not exist.
+    #[clap(long = "system-config")]
+    system_config_path: Option<PathBuf>,
     /// Uses alternative prompt
     #[clap(long)]
     alternative_prompt: bool,
+    /// Prints the right prompt, e.g. for zsh's `RPROMPT`
+    #[clap(long)]
+    right: bool,
     /// Shell to generate prompt for
     #[clap(long, default_value_t)]
     shell: ShellType,
+    /// Prints the new application state on the first line, before the prompt
+    #[clap(long)]
+    print_state: bool,
+    /// Named directories, one `name=path` per line, e.g. from zsh's `hash -d`
+    #[clap(long)]
+    named_dirs: Option<String>,
+    /// Terminal capabilities
+    ///
+    /// `plain` prints ASCII text without colors and uses ASCII symbols by default, regardless of
+    /// the configuration. `auto` does so in dumb terminals, i.e. if TERM is `dumb` or in Emacs
+    /// shell and TRAMP buffers. `full` never does.
+    #[clap(long, default_value_t)]
+    terminal: TerminalMode,
+    /// Line editor keymap, e.g. zsh's `$KEYMAP`, selecting the cursor shape
+    #[clap(long)]
+    keymap: Option<String>,
+    /// Width of the terminal in columns, e.g. from the shell's `$COLUMNS`
+    ///
+    /// Defaults to the `COLUMNS` environment variable.
+    #[clap(long)]
+    columns: Option<usize>,
+    #[clap(skip)]
+    faults: FaultInjection,
+    /// Leaves notes in place once shown
+    #[clap(skip)]
+    keep_notes: bool,
 }
 
-#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, strum::Display, strum::EnumString)]
+/// Prints prompt with faults injected in some producers
+///
+/// This shows how the prompt behaves when producers fail or take too long, e.g. to check the
+/// fallback prompt and the timeout configuration. Producers are selected by a case-insensitive
+/// prefix of their name, e.g. `git` selects all git producers.
+#[derive(Clone, Debug, Parser)]
+struct PreviewCommand {
+    #[clap(flatten)]
+    prompt: PromptCommand,
+    /// Makes the selected producers fail
+    #[clap(long = "inject-error", value_name = "PRODUCER")]
+    inject_errors: Vec<String>,
+    /// Makes the selected producers stall until the prompt times out
+    #[clap(long = "inject-timeout", value_name = "PRODUCER")]
+    inject_timeouts: Vec<String>,
+}
+
+#[derive(Clone, Copy, Debug, Default, strum::Display, strum::EnumString)]
 #[strum(serialize_all = "kebab-case")]
-enum ShellType {
+enum TerminalMode {
     #[default]
-    Generic,
-    Zsh,
+    Auto,
+    Plain,
+    Full,
+}
+
+impl TerminalMode {
+    fn is_plain(self) -> bool {
+        match self {
+            TerminalMode::Auto => eliprompt::shell::is_dumb_terminal(),
+            TerminalMode::Plain => true,
+            TerminalMode::Full => false,
+        }
+    }
 }
 
 /// Starts timer and prints new state to stdout
@@ -70,6 +176,8 @@ struct StartTimerCommand {
     /// Application state as returned from a previous run
     #[clap(long, default_value_t)]
     state: State,
+    #[clap(flatten)]
+    clock: ClockArgs,
 }
 
 /// Stops timer and prints new state to stdout
@@ -81,6 +189,78 @@ struct StopTimerCommand {
     /// Exit code of the timed command
     #[clap(long)]
     exit_code: i32,
+    #[clap(flatten)]
+    clock: ClockArgs,
+}
+
+/// Pauses timer when the timed command is suspended and prints new state to stdout
+#[derive(Clone, Debug, Parser)]
+struct PauseTimerCommand {
+    /// Application state as returned from a previous run
+    #[clap(long)]
+    state: State,
+    /// Exit code reported for the suspended command
+    #[clap(long)]
+    exit_code: i32,
+    #[clap(flatten)]
+    clock: ClockArgs,
+}
+
+/// Resumes timer when the timed command is resumed and prints new state to stdout
+///
+/// The timer is started if it was not paused.
+#[derive(Clone, Debug, Parser)]
+struct ResumeTimerCommand {
+    /// Application state as returned from a previous run
+    #[clap(long)]
+    state: State,
+    #[clap(flatten)]
+    clock: ClockArgs,
+}
+
+/// Options selecting the time source of timer commands
+#[derive(Clone, Debug, Parser)]
+struct ClockArgs {
+    /// Uses the given time in milliseconds instead of reading the monotonic clock
+    ///
+    /// This lets shells that measure command durations themselves feed them directly, e.g. fish
+    /// can call `start-timer --now-ms 0` and then `stop-timer --now-ms $CMD_DURATION`. All timer
+    /// commands of a shell session must then use it, as its times are not comparable with the
+    /// monotonic clock.
+    #[clap(long = "now-ms", value_name = "MILLISECONDS")]
+    now_ms: Option<u64>,
+}
+
+impl ClockArgs {
+    fn time_source(&self) -> Box<dyn TimeSource> {
+        match self.now_ms {
+            Some(ms) => Box::new(FixedTime(Duration::from_millis(ms))),
+            None => Box::new(Clock::new()),
+        }
+    }
+}
+
+/// Source of the current time used to measure command durations
+///
+/// Times are durations since an arbitrary origin, which must be the same for all timer commands
+/// of a shell session.
+trait TimeSource {
+    fn now(&self) -> Duration;
+}
+
+impl TimeSource for Clock {
+    fn now(&self) -> Duration {
+        self.elapsed()
+    }
+}
+
+/// Time given by the shell
+struct FixedTime(Duration);
+
+impl TimeSource for FixedTime {
+    fn now(&self) -> Duration {
+        self.0
+    }
 }
 
 /// Generates configuration for the given shell
@@ -94,6 +274,97 @@ struct InstallCommand {
     shell: ShellType,
 }
 
+/// Prints shell commands exporting variables that describe the environment
+///
+/// The output can be `eval`'ed to reuse eliprompt's detection logic in shell functions, e.g. to
+/// set the terminal title. Variables that do not apply (e.g. the git branch outside of a git
+/// repository) are unset. For the generic shell, `NAME=value` lines are printed for the variables
+/// that are set.
+#[derive(Clone, Debug, Parser)]
+struct EnvCommand {
+    /// Working directory or current working directory if not specified.
+    #[clap(long)]
+    pwd: Option<PathBuf>,
+    /// Application state as returned from a previous run
+    #[clap(long, default_value_t)]
+    state: State,
+    /// Shell to generate commands for
+    #[clap(long, default_value_t)]
+    shell: ShellType,
+}
+
+/// Sets or clears the marker flagging the shell session and prints new state to stdout
+///
+/// The marker is shown by the `Marker` block, e.g. to flag a session used for a demo.
+#[derive(Clone, Debug, Parser)]
+struct MarkCommand {
+    #[clap(subcommand)]
+    action: MarkAction,
+    /// Application state as returned from a previous run
+    #[clap(long, default_value_t, global = true)]
+    state: State,
+}
+
+#[derive(Clone, Debug, clap::Subcommand)]
+enum MarkAction {
+    /// Sets the marker
+    Set {
+        /// Text of the marker
+        name: String,
+    },
+    /// Clears the marker
+    Clear,
+}
+
+/// Shows or hides a group of blocks and prints new state to stdout
+///
+/// Groups are defined in the configuration with the `Group` block. Their visibility is flipped
+/// for the shell session only.
+#[derive(Clone, Debug, Parser)]
+struct ToggleCommand {
+    /// Name of the group
+    group: String,
+    /// Application state as returned from a previous run
+    #[clap(long, default_value_t)]
+    state: State,
+}
+
+/// Prints the escape sequence setting the configured cursor shape
+///
+/// Nothing is printed if no cursor shape is configured. This lets the shell update the cursor when
+/// the line editor keymap changes without rendering the prompt again.
+#[derive(Clone, Debug, Parser)]
+struct CursorCommand {
+    /// Line editor keymap, e.g. zsh's `$KEYMAP`
+    #[clap(long)]
+    keymap: Option<String>,
+    /// Prints the sequence restoring the default shape instead, e.g. before running a command
+    #[clap(long, conflicts_with = "keymap")]
+    reset: bool,
+    /// Path to the configuration file
+    #[clap(long = "config")]
+    config_path: Option<PathBuf>,
+    /// Path to the system configuration file, which the user configuration is layered over
+    #[clap(long = "system-config")]
+    system_config_path: Option<PathBuf>,
+    /// Terminal capabilities
+    ///
+    /// Nothing is printed in plain mode.
+    #[clap(long, default_value_t)]
+    terminal: TerminalMode,
+}
+
+/// Escapes text read from stdin as it would be in a prompt
+///
+/// Control characters are replaced with a visible representation and characters that the shell
+/// would interpret are escaped. The text shown by the shell is printed to stderr.
+#[derive(Clone, Debug, Parser)]
+struct EscapeCheckCommand {
+    /// Shell to escape text for
+    #[clap(long, default_value_t)]
+    shell: ShellType,
+}
+
 const APP_NAME: &str = env!("CARGO_PKG_NAME");
 
 static DEFAULT_CONFIG_PATH: Lazy<Option<PathBuf>> = Lazy::new(|| {
@@ -102,14 +373,28 @@ static DEFAULT_CONFIG_PATH: Lazy<Option<PathBuf>> = Lazy::new(|| {
     Some(path)
 });
 
+static SYSTEM_CONFIG_PATH: Lazy<Option<PathBuf>> =
+    Lazy::new(|| cfg!(unix).then(|| ["/etc", APP_NAME, "config.json"].iter().collect()));
+
 fn run() -> Result<(), AppError> {
     let cmd = Command::parse();
+    install_panic_hook(&cmd);
     match cmd {
         Command::Prompt(cmd) => generate_prompt(cmd)?,
+        Command::Preview(cmd) => preview(cmd)?,
         Command::StartTimer(cmd) => start_timer(cmd),
         Command::StopTimer(cmd) => stop_timer(cmd),
+        Command::PauseTimer(cmd) => pause_timer(cmd),
+        Command::ResumeTimer(cmd) => resume_timer(cmd),
         Command::Install(cmd) => install(cmd)?,
+        Command::Env(cmd) => print_env(cmd),
+        Command::Mark(cmd) => mark(cmd),
+        Command::Toggle(cmd) => toggle(cmd),
+        Command::Refresh(cmd) => refresh(cmd),
+        Command::Cursor(cmd) => print_cursor(cmd)?,
         Command::PrintDefaultConfig => print_default_config(),
+        Command::Blocks(cmd) => print_blocks(cmd),
+        Command::EscapeCheck(cmd) => escape_check(cmd)?,
     }
     Ok(())
 }
@@ -117,7 +402,7 @@ fn run() -> Result<(), AppError> {
 fn main() {
     if let Err(e) = run() {
         print_error(&e);
-        std::process::exit(1);
+        process::exit(1);
     }
 }
 
@@ -132,11 +417,18 @@ fn print_error(mut e: &dyn Error) {
 fn generate_prompt(cmd: PromptCommand) -> Result<(), AppError> {
     let t0 = Instant::now();
     let mut buffer = Vec::<u8>::new();
-    match cmd.shell {
-        ShellType::Generic => print_or_fallback(&mut GenericShell(&mut buffer), &cmd)?,
-        ShellType::Zsh => print_or_fallback(&mut Zsh(&mut buffer), &cmd)?,
+    let res = match cmd.shell {
+        ShellType::Generic => print_or_fallback(&mut GenericShell(&mut buffer), &cmd),
+        ShellType::Bash => print_or_fallback(&mut Bash(&mut buffer), &cmd),
+        ShellType::Zsh => print_or_fallback(&mut Zsh(&mut buffer), &cmd),
+    };
+    if cmd.print_state {
+        print_state(res.as_ref().unwrap_or(&cmd.state));
+    }
+    // The prompt starts on a new line, unlike the right prompt, which is next to the command line.
+    if !cmd.right {
+        println!();
     }
-    println!();
     io::stdout().write_all(&buffer).map_err(AppError::Print)?;
     let elapsed = t0.elapsed();
     if cmd.test {
@@ -145,24 +437,137 @@ fn generate_prompt(cmd: PromptCommand) -> Result<(), AppError> {
             humantime::format_duration(elapsed)
         );
     }
-    Ok(())
+    res.map(|_| ())
 }
 
-fn print_or_fallback<S: Shell>(shell: &mut S, cmd: &PromptCommand) -> Result<(), AppError> {
-    let config = match (&cmd.config_path, &*DEFAULT_CONFIG_PATH) {
-        (Some(path), _) => read_config(path),
-        (_, Some(path)) => match read_config(path) {
-            Ok(config) => Ok(config),
-            Err(AppError::ReadingConfigFailed(e)) if e.kind() == io::ErrorKind::NotFound => {
-                Ok(Config::default_pretty())
-            }
-            e => e,
-        },
-        _ => Ok(Config::default_pretty()),
-    }?;
+/// Path of the diagnostic file written by the last panic
+static PANIC_DUMP: Mutex<Option<PathBuf>> = Mutex::new(None);
+
+/// Time given to producers to stop once the prompt times out, before the fallback prompt is shown
+const TIMEOUT_GRACE: Duration = Duration::from_millis(100);
+
+/// Number of diagnostic files kept in the cache directory
+const MAX_PANIC_DUMPS: usize = 10;
+
+/// Installs a panic hook writing a diagnostic file in the cache directory.
+///
+/// When printing a prompt, the default panic message is only printed in test mode, as it would
+/// clutter the terminal before each prompt.
+fn install_panic_hook(cmd: &Command) {
+    let prompt = match cmd {
+        Command::Prompt(cmd) => Some(cmd),
+        Command::Preview(cmd) => Some(&cmd.prompt),
+        _ => None,
+    };
+    let config_path = prompt
+        .and_then(|cmd| cmd.config_path.clone())
+        .or_else(|| DEFAULT_CONFIG_PATH.clone());
+    let working_dir = prompt.and_then(|cmd| cmd.pwd.clone());
+    let verbose = prompt.is_none_or(|cmd| cmd.test);
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        let dump = write_panic_dump(info, config_path.as_deref(), working_dir.as_deref());
+        *PANIC_DUMP.lock().unwrap_or_else(PoisonError::into_inner) = dump;
+        if verbose {
+            default_hook(info);
+        }
+    }));
+}
+
+fn write_panic_dump(
+    info: &panic::PanicHookInfo<'_>,
+    config_path: Option<&Path>,
+    working_dir: Option<&Path>,
+) -> Option<PathBuf> {
+    let dir = cache::default_dir()?.join("panics");
+    let time = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default();
+    let path = dir.join(format!("{}-{}.txt", time.as_secs(), process::id()));
+    let working_dir = working_dir
+        .map(Path::to_owned)
+        .or_else(|| env::current_dir().ok());
+    let display =
+        |path: Option<&Path>| path.map_or_else(|| "<NONE>".into(), |p| p.display().to_string());
+    let contents = format!(
+        "{} {}\nConfiguration: {}\nWorking directory: {}\nProducers: {}\n{}\n\nBacktrace:\n{}\n",
+        APP_NAME,
+        env!("CARGO_PKG_VERSION"),
+        display(config_path),
+        display(working_dir.as_deref()),
+        eliprompt::block::producer_stack().join(" > "),
+        info,
+        Backtrace::force_capture(),
+    );
+    let written = fs::create_dir_all(&dir).and_then(|_| fs::write(&path, contents));
+    prune_panic_dumps(&dir, MAX_PANIC_DUMPS);
+    written.ok().map(|_| path)
+}
+
+/// Removes the oldest diagnostic files in `dir` so that at most `keep` remain.
+fn prune_panic_dumps(dir: &Path, keep: usize) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    let mut dumps = entries
+        .filter_map(|entry| {
+            let entry = entry.ok()?;
+            let modified = entry.metadata().ok()?.modified().ok()?;
+            Some((modified, entry.path()))
+        })
+        .collect::<Vec<_>>();
+    dumps.sort_unstable_by(|a, b| b.cmp(a));
+    for (_, path) in dumps.into_iter().skip(keep) {
+        let _ = fs::remove_file(path);
+    }
+}
+
+fn take_panic_dump() -> Option<PathBuf> {
+    PANIC_DUMP
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .take()
+}
+
+fn panic_dump_note(path: Option<&Path>) -> String {
+    path.map(|p| format!(", diagnostics written to {}", p.display()))
+        .unwrap_or_default()
+}
+
+fn preview(cmd: PreviewCommand) -> Result<(), AppError> {
+    let faults = FaultInjection {
+        errors: cmd.inject_errors,
+        timeouts: cmd.inject_timeouts,
+    };
+    generate_prompt(PromptCommand {
+        faults,
+        keep_notes: true,
+        ..cmd.prompt
+    })
+}
+
+fn print_or_fallback<S: Shell>(shell: &mut S, cmd: &PromptCommand) -> Result<State, AppError> {
+    if cmd.terminal.is_plain() {
+        SymbolSet::Ascii.scope(|| print_or_fallback_to(&mut Plain(shell), cmd, true))
+    } else {
+        print_or_fallback_to(shell, cmd, false)
+    }
+}
+
+fn print_or_fallback_to<S: Shell>(
+    shell: &mut S,
+    cmd: &PromptCommand,
+    plain: bool,
+) -> Result<State, AppError> {
+    let config = load_config(
+        cmd.config_path.as_deref(),
+        cmd.system_config_path.as_deref(),
+        cmd.test,
+        plain,
+    )?;
     match print_prompt(shell, &config, cmd) {
-        Ok(()) => Ok(()),
-        Err(e) if cmd.test => Err(e),
+        Ok(state) => Ok(state),
+        Err(e) if cmd.test || cmd.right => Err(e),
         Err(e) => {
             let _ = print_fallback_prompt(shell);
             Err(e)
@@ -174,66 +579,109 @@ fn print_prompt<S: Shell>(
     shell: &mut S,
     config: &Config,
     cmd: &PromptCommand,
-) -> Result<(), AppError> {
+) -> Result<State, AppError> {
+    let cancellation = Cancellation::with_timeout(config.timeout);
     let (sender, receiver) = sync_channel(1);
-    let blocks = thread::spawn({
+    let prompt = thread::spawn({
         let config = config.clone();
         let cmd = cmd.clone();
+        let cancellation = cancellation.clone();
         move || {
-            let blocks = make_prompt(
-                &config,
-                cmd.pwd.as_deref(),
-                cmd.alternative_prompt,
-                &cmd.state,
-            );
+            let prompt = make_prompt(&config, &cmd, &cancellation);
             drop(sender);
-            blocks
+            prompt
         }
     });
-    let blocks = match receiver.recv_timeout(config.timeout) {
-        Ok(()) | Err(RecvTimeoutError::Disconnected) => blocks
+    // Producers stop at the timeout, except for work they cannot interrupt, e.g. a slow file
+    // system. The fallback prompt is shown if they are still running after a grace period.
+    let (blocks, state, notes) = match receiver.recv_timeout(config.timeout + TIMEOUT_GRACE) {
+        Ok(()) | Err(RecvTimeoutError::Disconnected) => prompt
             .join()
-            .map_err(|_| AppError::PromptGenerationPanicked),
-        Err(RecvTimeoutError::Timeout) => Err(AppError::PromptGenerationTimedOut),
+            .map_err(|_| AppError::PromptGenerationPanicked(take_panic_dump())),
+        Err(RecvTimeoutError::Timeout) => {
+            cancellation.cancel();
+            Err(AppError::PromptGenerationTimedOut)
+        }
     }?;
-    show_prompt(shell, blocks)
+    // Blocks produced before the timeout are shown, the others are left out.
+    show_prompt(shell, blocks)?;
+    if !cmd.keep_notes {
+        notes.iter().for_each(ShownNote::remove);
+    }
+    let cursor_shape = config
+        .cursor_shape
+        .as_ref()
+        .filter(|_| !cmd.right)
+        .and_then(|shapes| shapes.for_keymap(cmd.keymap.as_deref()));
+    if let Some(shape) = cursor_shape {
+        shell
+            .write_escape_sequence(shape.escape_sequence())
+            .map_err(AppError::Print)?;
+    }
+    if cancellation.is_cancelled() && cmd.test {
+        return Err(AppError::PromptGenerationTimedOut);
+    }
+    Ok(state)
 }
 
 fn show_prompt<S: Shell>(shell: &mut S, blocks: Vec<Block>) -> Result<(), AppError> {
-    let style = blocks
-        .into_iter()
-        .try_fold(ansi_term::Style::new(), |style, block| {
-            let s = block.render();
-            let style_diff = style.infix(*s.style_ref());
-            shell.write_color_escape(style_diff)?;
-            write!(shell, "{}", &*s)?;
-            Ok(*s.style_ref())
-        })
-        .map_err(AppError::Print)?;
-    shell
-        .write_color_escape(style.suffix())
-        .map_err(AppError::Print)?;
-    Ok(())
+    eliprompt::shell::write_blocks(shell, blocks).map_err(AppError::Print)
+}
+
+fn make_environment(working_dir: Option<&Path>, state: &State) -> Environment {
+    let duration = match state.prev_cmd_duration {
+        CmdDuration::Elapsed(d) => Some(d),
+        _ => None,
+    };
+    Environment::for_prompt(
+        working_dir.map(Path::to_owned),
+        state.prev_exit_code,
+        duration,
+    )
+    .with_prev_cmd_suspended(state.suspended)
+    .with_marker(state.marker.clone())
+    .with_toggled_groups(state.toggled_groups.iter().cloned())
 }
 
 fn make_prompt(
     config: &Config,
-    working_dir: Option<&Path>,
-    alternative_prompt: bool,
-    state: &State,
-) -> Vec<Block> {
-    let exit_code = state.prev_exit_code;
-    let environment = match working_dir {
-        Some(p) => Environment::new(Some(p.to_owned())),
-        None => Environment::current(),
+    cmd: &PromptCommand,
+    cancellation: &Cancellation,
+) -> (Vec<Block>, State, Vec<ShownNote>) {
+    let state = &cmd.state;
+    let environment = make_environment(cmd.pwd.as_deref(), state)
+        .force_alternative_prompt(cmd.alternative_prompt)
+        .with_fault_injection(cmd.faults.clone())
+        .with_cancellation(cancellation.clone())
+        .with_sticky_blocks(state.sticky.clone())
+        .with_cache_dir(cache::default_dir())
+        .cache_git(config.cache_git)
+        .with_named_dirs(parse_named_dirs(
+            cmd.named_dirs.as_deref().unwrap_or_default(),
+        ))
+        .with_terminal_width(cmd.columns);
+    let blocks = if cmd.right {
+        config.produce_right(&environment)
+    } else {
+        config.produce(&environment)
     };
-    let environment = environment.with_prev_exit_code(exit_code);
-    let environment = match state.prev_cmd_duration {
-        CmdDuration::Elapsed(d) => environment.with_prev_cmd_duration(d),
-        _ => environment,
+    let notes = environment.take_shown_notes();
+    let state = State {
+        sticky: environment.into_sticky_blocks(),
+        ..state.clone()
     };
-    let environment = environment.force_alternative_prompt(alternative_prompt);
-    config.produce(&environment)
+    (blocks, state, notes)
+}
+
+/// Parses named directories given as `name=path` lines.
+fn parse_named_dirs(s: &str) -> Vec<(String, PathBuf)> {
+    s.lines()
+        .filter_map(|line| {
+            let (name, path) = line.split_once('=')?;
+            Some((name.to_owned(), PathBuf::from(path)))
+        })
+        .filter(|(name, path)| !name.is_empty() && path.is_absolute())
+        .collect()
 }
 
 fn print_fallback_prompt<S: Shell>(shell: &mut S) -> Result<(), AppError> {
@@ -242,28 +690,113 @@ fn print_fallback_prompt<S: Shell>(shell: &mut S) -> Result<(), AppError> {
 }
 
 fn start_timer(cmd: StartTimerCommand) {
-    let state = State {
-        prev_cmd_duration: CmdDuration::StartedAt(Clock::new().elapsed()),
-        prev_exit_code: cmd.state.prev_exit_code,
-    };
-    print_state(&state);
+    let now = cmd.clock.time_source().now();
+    print_state(&started_timer(cmd.state, now));
+}
+
+fn started_timer(state: State, now: Duration) -> State {
+    State {
+        prev_cmd_duration: CmdDuration::StartedAt(now),
+        suspended: Duration::ZERO,
+        suspended_since: None,
+        ..state
+    }
 }
 
 fn stop_timer(cmd: StopTimerCommand) {
-    let duration = match cmd.state.prev_cmd_duration {
+    let now = cmd.clock.time_source().now();
+    print_state(&stopped_timer(cmd.state, cmd.exit_code, now));
+}
+
+fn stopped_timer(state: State, exit_code: i32, now: Duration) -> State {
+    let state = resumed_timer(state, now);
+    let duration = match state.prev_cmd_duration {
         CmdDuration::StartedAt(start) => {
-            let end = start.max(Clock::new().elapsed());
+            let end = start.max(now);
             CmdDuration::Elapsed(end - start)
         }
         CmdDuration::Unknown | CmdDuration::Elapsed(_) => CmdDuration::Unknown,
     };
-    let state = State {
-        prev_exit_code: cmd.exit_code,
+    State {
+        prev_exit_code: exit_code,
         prev_cmd_duration: duration,
+        ..state
+    }
+}
+
+fn pause_timer(cmd: PauseTimerCommand) {
+    let now = cmd.clock.time_source().now();
+    print_state(&paused_timer(cmd.state, cmd.exit_code, now));
+}
+
+fn paused_timer(state: State, exit_code: i32, now: Duration) -> State {
+    let suspended_since = match state.prev_cmd_duration {
+        CmdDuration::StartedAt(_) => state.suspended_since.or(Some(now)),
+        CmdDuration::Unknown | CmdDuration::Elapsed(_) => None,
+    };
+    State {
+        prev_exit_code: exit_code,
+        suspended_since,
+        ..state
+    }
+}
+
+fn resume_timer(cmd: ResumeTimerCommand) {
+    let now = cmd.clock.time_source().now();
+    let state = if cmd.state.suspended_since.is_some() {
+        resumed_timer(cmd.state, now)
+    } else {
+        started_timer(cmd.state, now)
+    };
+    print_state(&state);
+}
+
+fn resumed_timer(state: State, now: Duration) -> State {
+    match state.suspended_since {
+        Some(since) => State {
+            suspended: state.suspended + now.saturating_sub(since),
+            suspended_since: None,
+            ..state
+        },
+        None => state,
+    }
+}
+
+fn mark(cmd: MarkCommand) {
+    let marker = match cmd.action {
+        MarkAction::Set { name } => Some(name),
+        MarkAction::Clear => None,
     };
+    print_state(&State {
+        marker,
+        ..cmd.state
+    });
+}
+
+fn toggle(cmd: ToggleCommand) {
+    let mut state = cmd.state;
+    if !state.toggled_groups.remove(&cmd.group) {
+        state.toggled_groups.insert(cmd.group);
+    }
     print_state(&state);
 }
 
+fn print_env(cmd: EnvCommand) {
+    let environment = make_environment(cmd.pwd.as_deref(), &cmd.state);
+    for (name, value) in environment.variables() {
+        match cmd.shell {
+            ShellType::Generic => {
+                if let Some(value) = value {
+                    println!("{}={}", name, value);
+                }
+            }
+            ShellType::Bash | ShellType::Zsh => {
+                println!("{}", eliprompt::shell::export(name, value.as_deref()))
+            }
+        }
+    }
+}
+
 fn print_state(state: &State) {
     let state_str =
         bs58::encode(serde_json::to_string(&state).expect("Serializing state cannot fail"))
@@ -272,34 +805,156 @@ fn print_state(state: &State) {
     println!("{}", state_str);
 }
 
-fn read_config(path: &Path) -> Result<Config, AppError> {
-    serde_json::from_slice(&fs::read(path).map_err(AppError::ReadingConfigFailed)?)
-        .map_err(AppError::BadConfig)
+/// Loads the user configuration layered over the system configuration.
+///
+/// The symbol set is ASCII if `plain` is true, or the one requested by `ELIPROMPT_SYMBOLS` if
+/// any, whatever the configuration says.
+fn load_config(
+    config_path: Option<&Path>,
+    system_config_path: Option<&Path>,
+    test: bool,
+    plain: bool,
+) -> Result<Config, AppError> {
+    let system = match (system_config_path, &*SYSTEM_CONFIG_PATH) {
+        (Some(path), _) => Some(read_config(path)?),
+        (_, Some(path)) => read_optional_config(path)?,
+        _ => None,
+    };
+    let user = match (config_path, &*DEFAULT_CONFIG_PATH) {
+        (Some(path), _) => Some(read_config(path)?),
+        (_, Some(path)) => read_optional_config(path)?,
+        _ => None,
+    };
+    let symbols = if plain {
+        Some(SymbolSet::Ascii)
+    } else {
+        SymbolSet::requested()
+    };
+    if system.is_none() && user.is_none() {
+        return Ok(Config {
+            symbols: symbols.unwrap_or_default(),
+            ..Config::default_pretty()
+        });
+    }
+    let layers = system.iter().chain(&user).map(Vec::as_slice);
+    let config = if test {
+        Config::from_json_layers_with(layers, |field| {
+            eprintln!("Warning: Unknown configuration field {}", field)
+        })
+    } else {
+        Config::from_json_layers(layers)
+    };
+    let config = config.map_err(AppError::BadConfig)?;
+    // Applied once host overlays are merged, so that none of them can override it.
+    Ok(match symbols {
+        Some(symbols) => Config { symbols, ..config },
+        None => config,
+    })
+}
+
+fn read_config(path: &Path) -> Result<Vec<u8>, AppError> {
+    fs::read(path).map_err(AppError::ReadingConfigFailed)
+}
+
+fn read_optional_config(path: &Path) -> Result<Option<Vec<u8>>, AppError> {
+    match fs::read(path) {
+        Ok(config) => Ok(Some(config)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(AppError::ReadingConfigFailed(e)),
+    }
 }
 
 fn install(cmd: InstallCommand) -> Result<(), AppError> {
     let program = "eliprompt";
     match cmd.shell {
-        ShellType::Generic => Err(AppError::CannotInstallGenericShell),
+        ShellType::Generic | ShellType::Bash => Err(AppError::InstallationNotSupported(cmd.shell)),
         ShellType::Zsh => install_zsh(program),
     }
 }
 
 fn install_zsh(program: &str) -> Result<(), AppError> {
     let config = r####"
+zmodload -F zsh/parameter p:nameddirs
+
+eliprompt_render() {
+    local named_dirs=() name output keymap=()
+    for name in ${(k)nameddirs}; do
+        named_dirs+=("$name=${nameddirs[$name]}")
+    done
+    [[ -n $KEYMAP ]] && keymap=(--keymap $KEYMAP)
+    output=$(ELIPROMPT_EXE prompt --state "$ELIPROMPT_STATE" --shell zsh --print-state \
+        --named-dirs "${(F)named_dirs}" --columns $COLUMNS $keymap)
+    ELIPROMPT_STATE=${output%%$'\n'*}
+    PROMPT=${output#*$'\n'}
+    eliprompt_render_right
+}
+
+eliprompt_render_right() {
+    RPROMPT=$(ELIPROMPT_EXE prompt --right --state "$ELIPROMPT_STATE" --shell zsh \
+        --columns $COLUMNS)
+}
+
 eliprompt_precmd() {
     prev_status=$?
-    ELIPROMPT_STATE=$(ELIPROMPT_EXE stop-timer --state "$ELIPROMPT_STATE" --exit-code $prev_status)
-    PROMPT=$(ELIPROMPT_EXE prompt --state "$ELIPROMPT_STATE" --shell zsh)
+    local timer_cmd=stop-timer
+    # A status of 128 + SIGTSTP means the command was suspended, e.g. with Ctrl-Z.
+    (( prev_status == 127 + ${signals[(i)TSTP]} )) && timer_cmd=pause-timer
+    ELIPROMPT_STATE=$(ELIPROMPT_EXE $timer_cmd --state "$ELIPROMPT_STATE" --exit-code $prev_status)
+    eliprompt_render
 }
 
 eliprompt_preexec() {
-    ELIPROMPT_STATE=$(ELIPROMPT_EXE start-timer --state "$ELIPROMPT_STATE")
+    print -n -- "$ELIPROMPT_CURSOR_RESET"
+    local timer_cmd=start-timer
+    [[ ${1%% *} == (fg|%*) ]] && timer_cmd=resume-timer
+    ELIPROMPT_STATE=$(ELIPROMPT_EXE $timer_cmd --state "$ELIPROMPT_STATE")
+}
+
+eliprompt_keymap_select() {
+    print -n -- "$(ELIPROMPT_EXE cursor --keymap "$KEYMAP")"
+}
+
+eliprompt_mark() {
+    ELIPROMPT_STATE=$(ELIPROMPT_EXE mark "$@" --state "$ELIPROMPT_STATE")
+}
+
+eliprompt_toggle() {
+    ELIPROMPT_STATE=$(ELIPROMPT_EXE toggle "$@" --state "$ELIPROMPT_STATE")
+}
+
+eliprompt_refresh_handler() {
+    local line
+    if ! read -r -u $1 line; then
+        eliprompt_stop_refresh
+        return
+    fi
+    eliprompt_render_right
+    zle && zle reset-prompt
+}
+
+eliprompt_start_refresh() {
+    (( ${+ELIPROMPT_REFRESH_FD} )) && return
+    exec {ELIPROMPT_REFRESH_FD}< <(ELIPROMPT_EXE refresh --interval ${1:-30s})
+    zle -F $ELIPROMPT_REFRESH_FD eliprompt_refresh_handler
+}
+
+eliprompt_stop_refresh() {
+    (( ${+ELIPROMPT_REFRESH_FD} )) || return
+    zle -F $ELIPROMPT_REFRESH_FD
+    exec {ELIPROMPT_REFRESH_FD}<&-
+    unset ELIPROMPT_REFRESH_FD
 }
 
 [[ -v precmd_functions ]] || precmd_functions=()
 [[ ${precmd_functions[(ie)eliprompt_precmd]} -le ${#precmd_functions} ]] || precmd_functions+=(eliprompt_precmd)
 
+# The cursor shape only follows the keymap if it is configured when the shell starts.
+ELIPROMPT_CURSOR_RESET=$(ELIPROMPT_EXE cursor --reset)
+if [[ -n $ELIPROMPT_CURSOR_RESET ]]; then
+    autoload -Uz add-zle-hook-widget
+    add-zle-hook-widget keymap-select eliprompt_keymap_select
+fi
+
 [[ -v preexec_functions ]] || preexec_functions=()
 [[ ${preexec_functions[(ie)eliprompt_preexec]} -le ${#preexec_functions} ]] || preexec_functions+=(eliprompt_preexec)
 "####;
@@ -318,22 +973,37 @@ enum AppError {
     Print(#[source] io::Error),
     #[error("Error while building prompt")]
     Prompt(#[from] eliprompt::Error),
-    #[error("Prompt generation panicked")]
-    PromptGenerationPanicked,
+    #[error("Prompt generation panicked{}", panic_dump_note(.0.as_deref()))]
+    PromptGenerationPanicked(Option<PathBuf>),
     #[error("Prompt generation timed out")]
     PromptGenerationTimedOut,
     #[error("Failed to decode state")]
     DecodingStateFailed(#[source] bs58::decode::Error),
     #[error("Failed to parse state")]
     ParsingStateFailed(#[source] serde_json::Error),
-    #[error("Installation is not possible for generic shell")]
-    CannotInstallGenericShell,
+    #[error("Installation is not supported for {0} shell")]
+    InstallationNotSupported(ShellType),
+    #[error("Failed to read standard input")]
+    ReadingStdinFailed(#[source] io::Error),
 }
 
 #[derive(Clone, Debug, Default, Deserialize, Serialize)]
 struct State {
     prev_exit_code: i32,
     prev_cmd_duration: CmdDuration,
+    /// Time the timed command spent suspended
+    #[serde(default)]
+    suspended: Duration,
+    /// Time at which the timed command was suspended, if it still is
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    suspended_since: Option<Duration>,
+    #[serde(default)]
+    sticky: StickyBlocks,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    marker: Option<String>,
+    /// Groups whose visibility was flipped
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    toggled_groups: BTreeSet<String>,
 }
 
 impl Display for State {
@@ -362,68 +1032,180 @@ impl FromStr for State {
     }
 }
 
-#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
 pub enum CmdDuration {
+    #[default]
     Unknown,
     StartedAt(Duration),
     Elapsed(Duration),
 }
 
-impl Default for CmdDuration {
-    fn default() -> Self {
-        Self::Unknown
+fn print_default_config() {
+    println!(
+        "{}",
+        serde_json::to_string_pretty(&Config::default_pretty()).unwrap()
+    );
+}
+
+/// Parses the interval between refreshes, which must not be zero so as not to busy-loop.
+fn parse_interval(s: &str) -> Result<Duration, String> {
+    match humantime::parse_duration(s) {
+        Ok(interval) if interval.is_zero() => Err("Interval must be greater than zero".into()),
+        Ok(interval) => Ok(interval),
+        Err(e) => Err(e.to_string()),
     }
 }
 
-trait Shell: Write {
-    fn write_color_escape<T: Display>(&mut self, x: T) -> io::Result<()>;
+fn refresh(cmd: RefreshCommand) {
+    let mut stdout = io::stdout();
+    loop {
+        thread::sleep(cmd.interval);
+        // The shell closing its end means it no longer needs refreshes.
+        if writeln!(stdout).and_then(|_| stdout.flush()).is_err() {
+            return;
+        }
+    }
 }
 
-struct Zsh<W>(W);
+fn print_cursor(cmd: CursorCommand) -> Result<(), AppError> {
+    if cmd.terminal.is_plain() {
+        return Ok(());
+    }
+    let config = load_config(
+        cmd.config_path.as_deref(),
+        cmd.system_config_path.as_deref(),
+        false,
+        false,
+    )?;
+    let shape = match &config.cursor_shape {
+        Some(_) if cmd.reset => Some(CursorShape::Default),
+        Some(shapes) => shapes.for_keymap(cmd.keymap.as_deref()),
+        None => None,
+    };
+    if let Some(shape) = shape {
+        print!("{}", shape.escape_sequence());
+    }
+    Ok(())
+}
 
-impl<W: Write> Write for Zsh<W> {
-    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
-        buf.iter().copied().try_fold(0, |len, b| {
-            match b {
-                b'%' => self.0.write_all(b"%%")?,
-                _ => self.0.write_all(&[b])?,
+fn print_blocks(cmd: BlocksCommand) {
+    let descriptors = BlockProducer::descriptors();
+    if cmd.json {
+        println!("{}", serde_json::to_string_pretty(&descriptors).unwrap());
+        return;
+    }
+    for (i, descriptor) in descriptors.iter().enumerate() {
+        if i > 0 {
+            println!();
+        }
+        println!("{}", descriptor.name);
+        println!("    {}", descriptor.description);
+        for field in descriptor.fields {
+            match descriptor.defaults.as_ref().and_then(|d| d.get(field)) {
+                Some(default) => println!("    - {}: {}", field, default),
+                None => println!("    - {}", field),
             }
-            Ok(len + 1)
-        })
+        }
     }
+}
 
-    fn flush(&mut self) -> io::Result<()> {
-        self.0.flush()
-    }
+fn escape_check(cmd: EscapeCheckCommand) -> Result<(), AppError> {
+    let mut text = String::new();
+    io::stdin()
+        .read_to_string(&mut text)
+        .map_err(AppError::ReadingStdinFailed)?;
+    print!("{}", eliprompt::shell::escape(cmd.shell, &text));
+    eprint!("{}", eliprompt::shell::sanitize(&text));
+    Ok(())
 }
 
-impl<W: Write> Shell for Zsh<W> {
-    fn write_color_escape<T: Display>(&mut self, x: T) -> io::Result<()> {
-        write!(self.0, "%{{{}%}}", x)
+#[cfg(test)]
+mod tests {
+    use super::{
+        load_config, paused_timer, prune_panic_dumps, resumed_timer, started_timer, stopped_timer,
+        ClockArgs, CmdDuration, State,
+    };
+    use eliprompt::SymbolSet;
+    use std::{
+        fs::{self, File},
+        time::{Duration, SystemTime},
+    };
+
+    fn at(ms: u64) -> Duration {
+        ClockArgs { now_ms: Some(ms) }.time_source().now()
     }
-}
 
-struct GenericShell<W>(W);
+    #[test]
+    fn timer_measures_command_duration() {
+        let state = started_timer(State::default(), at(1_000));
+        let state = stopped_timer(state, 2, at(3_500));
+        assert_eq!(
+            state.prev_cmd_duration,
+            CmdDuration::Elapsed(Duration::from_millis(2_500))
+        );
+        assert_eq!(state.prev_exit_code, 2);
+        let state = stopped_timer(state, 0, at(4_000));
+        assert_eq!(state.prev_cmd_duration, CmdDuration::Unknown);
+    }
 
-impl<W: Write> Write for GenericShell<W> {
-    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
-        self.0.write(buf)
+    #[test]
+    fn suspended_time_is_measured() {
+        let state = started_timer(State::default(), at(1_000));
+        let state = paused_timer(state, 148, at(2_000));
+        let state = paused_timer(state, 148, at(2_500));
+        let state = resumed_timer(state, at(5_000));
+        let state = stopped_timer(state, 0, at(6_000));
+        assert_eq!(
+            state.prev_cmd_duration,
+            CmdDuration::Elapsed(Duration::from_millis(5_000))
+        );
+        assert_eq!(state.suspended, Duration::from_millis(3_000));
+        assert_eq!(state.suspended_since, None);
     }
 
-    fn flush(&mut self) -> io::Result<()> {
-        self.0.flush()
+    #[test]
+    fn timer_stopped_before_start_measures_nothing() {
+        let state = started_timer(State::default(), at(5_000));
+        let state = stopped_timer(state, 0, at(1_000));
+        assert_eq!(
+            state.prev_cmd_duration,
+            CmdDuration::Elapsed(Duration::ZERO)
+        );
     }
-}
 
-impl<W: Write> Shell for GenericShell<W> {
-    fn write_color_escape<T: Display>(&mut self, x: T) -> io::Result<()> {
-        write!(self.0, "{}", x)
+    #[test]
+    fn oldest_panic_dumps_are_pruned() {
+        let dir = tempfile::tempdir().unwrap();
+        let now = SystemTime::now();
+        for i in 0..4 {
+            let file = File::create(dir.path().join(format!("{}.txt", i))).unwrap();
+            file.set_modified(now - Duration::from_secs(60 * i))
+                .unwrap();
+        }
+        prune_panic_dumps(dir.path(), 2);
+        let mut names = fs::read_dir(dir.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect::<Vec<_>>();
+        names.sort();
+        assert_eq!(names, ["0.txt", "1.txt"]);
     }
-}
 
-fn print_default_config() {
-    println!(
-        "{}",
-        serde_json::to_string_pretty(&Config::default_pretty()).unwrap()
-    );
+    #[test]
+    fn plain_symbols_override_host_overlays() {
+        let dir = tempfile::tempdir().unwrap();
+        let system = dir.path().join("system.json");
+        let user = dir.path().join("user.json");
+        fs::write(&system, "{}").unwrap();
+        fs::write(
+            &user,
+            r#"{"prompt": {"Text": {"contents": "$"}}, "hosts": {"": {"symbols": "nerd"}}}"#,
+        )
+        .unwrap();
+        let config = |plain| load_config(Some(&user), Some(&system), false, plain).unwrap();
+        if SymbolSet::requested().is_none() {
+            assert_eq!(config(false).symbols, SymbolSet::Nerd);
+        }
+        assert_eq!(config(true).symbols, SymbolSet::Ascii);
+    }
 }
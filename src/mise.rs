@@ -0,0 +1,148 @@
+// Copyright (C) 2020 Stephane Raux. Distributed under the MIT license.
+
+//! Tool versions pinned with mise or asdf.
+
+use std::{
+    collections::BTreeMap,
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+/// Version of a tool pinned for a directory
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PinnedTool {
+    pub name: String,
+    pub version: String,
+}
+
+impl PinnedTool {
+    /// Indicates if the pinned version is installed by mise or asdf.
+    ///
+    /// Versions given as a prefix (e.g. `20` for node) are installed if a matching version is.
+    pub fn is_installed(&self) -> bool {
+        if self.version == "system" {
+            return true;
+        }
+        install_dirs().iter().any(|dir| {
+            let dir = dir.join(&self.name);
+            if dir.join(&self.version).exists() {
+                return true;
+            }
+            let Ok(entries) = fs::read_dir(&dir) else {
+                return false;
+            };
+            let prefix = format!("{}.", self.version);
+            entries.filter_map(Result::ok).any(|entry| {
+                let name = entry.file_name();
+                let name = name.to_string_lossy();
+                self.version == "latest" || name.starts_with(&prefix)
+            })
+        })
+    }
+}
+
+/// Configuration files read in each directory, by decreasing precedence
+const CONFIG_FILES: [&str; 3] = [".mise.toml", "mise.toml", ".tool-versions"];
+
+/// Returns the tools pinned for `dir`, by name.
+///
+/// Pins in a directory take precedence over those in its ancestors.
+pub fn pinned_tools(dir: &Path) -> Vec<PinnedTool> {
+    let mut tools = BTreeMap::new();
+    for path in dir
+        .ancestors()
+        .flat_map(|dir| CONFIG_FILES.iter().map(move |file| dir.join(file)))
+    {
+        let Ok(contents) = fs::read_to_string(&path) else {
+            continue;
+        };
+        let pins = if path.extension().is_some_and(|e| e == "toml") {
+            parse_mise_toml(&contents).unwrap_or_else(|e| {
+                tracing::error!("Failed to parse {}: {}", path.display(), e);
+                Vec::new()
+            })
+        } else {
+            parse_tool_versions(&contents)
+        };
+        for (name, version) in pins {
+            tools.entry(name).or_insert(version);
+        }
+    }
+    tools
+        .into_iter()
+        .map(|(name, version)| PinnedTool { name, version })
+        .collect()
+}
+
+/// Returns the directories where mise and asdf install tools.
+fn install_dirs() -> Vec<PathBuf> {
+    let home = dirs::home_dir();
+    let mise = env::var_os("MISE_DATA_DIR")
+        .map(PathBuf::from)
+        .or_else(|| Some(PathBuf::from(env::var_os("XDG_DATA_HOME")?).join("mise")))
+        .or_else(|| Some(home.as_ref()?.join(".local").join("share").join("mise")));
+    let asdf = env::var_os("ASDF_DATA_DIR")
+        .map(PathBuf::from)
+        .or_else(|| Some(home.as_ref()?.join(".asdf")));
+    mise.into_iter()
+        .chain(asdf)
+        .map(|dir| dir.join("installs"))
+        .collect()
+}
+
+/// Parses a `.tool-versions` file, keeping the first version of each tool.
+fn parse_tool_versions(contents: &str) -> Vec<(String, String)> {
+    contents
+        .lines()
+        .filter_map(|line| {
+            let line = line.split('#').next().unwrap_or_default();
+            let mut words = line.split_whitespace();
+            Some((words.next()?.to_owned(), words.next()?.to_owned()))
+        })
+        .collect()
+}
+
+/// Parses the `tools` table of a mise configuration file, keeping the first version of each tool.
+fn parse_mise_toml(contents: &str) -> Result<Vec<(String, String)>, toml::de::Error> {
+    fn version(value: &toml::Value) -> Option<String> {
+        match value {
+            toml::Value::String(s) => Some(s.clone()),
+            toml::Value::Array(versions) => version(versions.first()?),
+            toml::Value::Table(options) => version(options.get("version")?),
+            _ => None,
+        }
+    }
+
+    let config = toml::from_str::<toml::Table>(contents)?;
+    let Some(toml::Value::Table(tools)) = config.get("tools") else {
+        return Ok(Vec::new());
+    };
+    Ok(tools
+        .iter()
+        .filter_map(|(name, value)| Some((name.clone(), version(value)?)))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_mise_toml, parse_tool_versions};
+
+    #[test]
+    fn pins_are_parsed() {
+        let pins = |pins: &[(&str, &str)]| {
+            pins.iter()
+                .map(|&(name, version)| (name.to_owned(), version.to_owned()))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            parse_tool_versions("# tools\nnodejs 20.11.1 18.0.0\nruby 3.3.0 # latest\n\n"),
+            pins(&[("nodejs", "20.11.1"), ("ruby", "3.3.0")])
+        );
+        let toml = "[tools]\nnode = \"20\"\npython = [\"3.12\", \"3.11\"]\n\
+            go = { version = \"1.22\" }\n";
+        assert_eq!(
+            parse_mise_toml(toml).unwrap(),
+            pins(&[("go", "1.22"), ("node", "20"), ("python", "3.12")])
+        );
+    }
+}
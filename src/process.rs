@@ -0,0 +1,272 @@
+// Copyright (C) 2020 Stephane Raux. Distributed under the MIT license.
+
+//! Running external programs.
+
+use crate::{Cancellation, Environment};
+use serde::{Deserialize, Serialize};
+use std::{
+    env, fs,
+    io::{self, Read},
+    path::{Path, PathBuf},
+    process::{Child, Command, ExitStatus, Output, Stdio},
+    thread,
+    time::{Duration, SystemTime},
+};
+
+/// Runs `command` and returns its standard output, trimmed, if it succeeds.
+///
+/// Failures are logged.
+pub(crate) fn output(command: &mut Command, cancellation: &Cancellation) -> Option<String> {
+    let program = command.get_program().to_string_lossy().into_owned();
+    match run(command, cancellation) {
+        Ok(output) if output.status.success() => {
+            Some(String::from_utf8_lossy(&output.stdout).trim().to_owned())
+        }
+        Ok(output) => {
+            tracing::error!(
+                "{} failed: {}",
+                program,
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+            None
+        }
+        Err(e) => {
+            tracing::error!("Failed to run {}: {}", program, e);
+            None
+        }
+    }
+}
+
+/// Longest time between checks of a running program and of the cancellation token
+const MAX_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Runs `command` to completion and collects its output.
+///
+/// The program and the processes it started are killed if `cancellation` is cancelled first, in
+/// which case an error of kind [`io::ErrorKind::TimedOut`] is returned.
+pub(crate) fn run(command: &mut Command, cancellation: &Cancellation) -> io::Result<Output> {
+    command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    #[cfg(unix)]
+    std::os::unix::process::CommandExt::process_group(command, 0);
+    let mut child = command.spawn()?;
+    let stdout = child.stdout.take();
+    let stderr = child.stderr.take();
+    thread::scope(|scope| {
+        let stdout = scope.spawn(|| read_all(stdout));
+        let stderr = scope.spawn(|| read_all(stderr));
+        let status = wait(&mut child, cancellation);
+        let stdout = stdout.join().unwrap_or_default();
+        let stderr = stderr.join().unwrap_or_default();
+        Ok(Output {
+            status: status?,
+            stdout,
+            stderr,
+        })
+    })
+}
+
+fn wait(child: &mut Child, cancellation: &Cancellation) -> io::Result<ExitStatus> {
+    let mut interval = Duration::from_millis(1);
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok(status);
+        }
+        if cancellation.is_cancelled() {
+            kill(child);
+            let _ = child.wait();
+            return Err(io::Error::new(io::ErrorKind::TimedOut, "cancelled"));
+        }
+        thread::sleep(interval);
+        interval = (interval * 2).min(MAX_POLL_INTERVAL);
+    }
+}
+
+/// Kills `child` and the processes it started, so that none of them keeps its output open.
+fn kill(child: &mut Child) {
+    #[cfg(unix)]
+    if let Ok(pid) = libc::pid_t::try_from(child.id()) {
+        // SAFETY: Signaling a process group has no memory safety implications. The group is the
+        // one of the child, which cannot be reused before the child is waited for.
+        unsafe {
+            libc::kill(-pid, libc::SIGKILL);
+        }
+    }
+    let _ = child.kill();
+}
+
+fn read_all<R: Read>(pipe: Option<R>) -> Vec<u8> {
+    let mut contents = Vec::new();
+    if let Some(mut pipe) = pipe {
+        let _ = pipe.read_to_end(&mut contents);
+    }
+    contents
+}
+
+/// Returns the path of the program named `name` found in `$PATH`.
+pub(crate) fn find_program(name: &str) -> Option<PathBuf> {
+    env::split_paths(&env::var_os("PATH")?)
+        .map(|dir| dir.join(name))
+        .find(|path| path.is_file())
+}
+
+/// Time after which probed information is computed again even if the program is unchanged, e.g.
+/// when it is a shim whose target changed.
+const PROBE_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Returns information about `program` computed by `probe`, e.g. its version.
+///
+/// The result is cached as `name` until the program changes, as running programs is slow.
+pub(crate) fn cached_probe<F>(
+    environment: &Environment,
+    name: &str,
+    program: &Path,
+    probe: F,
+) -> Option<String>
+where
+    F: FnOnce() -> Option<String>,
+{
+    cached_probe_with(environment, name, &[program], probe)
+}
+
+/// Returns information computed by `probe` from the files at `inputs`, e.g. a program and the
+/// project file selecting its version.
+///
+/// The result is cached as `name` until one of the inputs changes or it is older than
+/// [`PROBE_TTL`]. Inputs that do not exist are part of the cache key as such.
+pub(crate) fn cached_probe_with<F>(
+    environment: &Environment,
+    name: &str,
+    inputs: &[&Path],
+    probe: F,
+) -> Option<String>
+where
+    F: FnOnce() -> Option<String>,
+{
+    let key = inputs
+        .iter()
+        .map(|path| {
+            let path = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+            let modified = fs::metadata(&path)
+                .and_then(|m| m.modified())
+                .ok()
+                .and_then(|t| t.duration_since(SystemTime::UNIX_EPOCH).ok())
+                .unwrap_or_default();
+            format!("{}:{}", path.display(), modified.as_nanos())
+        })
+        .collect::<Vec<_>>()
+        .join(";");
+    let now = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default();
+    let Some(mut cache) = environment.tool_cache() else {
+        return probe();
+    };
+    let cached = cache
+        .get::<Probed>(name, &key)
+        .filter(|probed| now.saturating_sub(Duration::from_secs(probed.at)) < PROBE_TTL);
+    if let Some(probed) = cached {
+        return Some(probed.value);
+    }
+    let value = probe()?;
+    let probed = Probed {
+        value,
+        at: now.as_secs(),
+    };
+    cache.insert(name, &key, &probed);
+    Some(probed.value)
+}
+
+/// Time after which a background fetch that did not complete is considered failed and is started
+/// again
+const FETCH_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Starts downloading `url` into `path` with `curl`, unless a download is already under way.
+///
+/// The download outlives the prompt, so that the prompt never waits for the network. `path` is
+/// only replaced once the download succeeds.
+pub(crate) fn fetch_in_background(url: &str, path: &Path) {
+    let pending = path.with_extension("pending");
+    if file_age(&pending).is_some_and(|age| age < FETCH_TIMEOUT) {
+        return;
+    }
+    if let Err(e) =
+        fs::create_dir_all(path.parent().unwrap_or(path)).and_then(|_| fs::write(&pending, ""))
+    {
+        tracing::error!("Failed to create {}: {}", pending.display(), e);
+        return;
+    }
+    let script = r#"curl -fsS --max-time 10 "$1" > "$2.tmp" && mv "$2.tmp" "$2"; rm -f "$3""#;
+    let mut command = Command::new("sh");
+    command
+        .args(["-c", script, "sh", url])
+        .arg(path)
+        .arg(&pending)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+    // The fetch outlives the prompt and must not be interrupted along with the shell's foreground
+    // job.
+    #[cfg(unix)]
+    std::os::unix::process::CommandExt::process_group(&mut command, 0);
+    if let Err(e) = command.spawn() {
+        tracing::error!("Failed to fetch {}: {}", url, e);
+        let _ = fs::remove_file(&pending);
+    }
+}
+
+/// Returns the time elapsed since the file at `path` was modified.
+pub(crate) fn file_age(path: &Path) -> Option<Duration> {
+    let modified = fs::metadata(path).and_then(|m| m.modified()).ok()?;
+    Some(
+        SystemTime::now()
+            .duration_since(modified)
+            .unwrap_or_default(),
+    )
+}
+
+/// Cached result of a probe
+#[derive(Deserialize, Serialize)]
+struct Probed {
+    value: String,
+    /// Time of the probe in seconds since the Unix epoch
+    at: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{cached_probe_with, run};
+    use crate::{Cancellation, Environment};
+    use std::{
+        fs,
+        process::Command,
+        time::{Duration, Instant},
+    };
+
+    #[cfg(unix)]
+    #[test]
+    fn running_child_is_killed_on_cancellation() {
+        let t0 = Instant::now();
+        let cancellation = Cancellation::with_timeout(Duration::from_millis(50));
+        let res = run(Command::new("sleep").arg("10"), &cancellation);
+        assert!(res.is_err());
+        assert!(t0.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn probes_are_cached_until_inputs_change() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("tool");
+        fs::write(&input, "1").unwrap();
+        let probe = |value: &str| {
+            let environment = Environment::new(None).with_cache_dir(Some(dir.path().to_owned()));
+            cached_probe_with(&environment, "tool", &[&input], || Some(value.into()))
+        };
+        assert_eq!(probe("a").as_deref(), Some("a"));
+        assert_eq!(probe("b").as_deref(), Some("a"));
+        fs::remove_file(&input).unwrap();
+        assert_eq!(probe("c").as_deref(), Some("c"));
+    }
+}
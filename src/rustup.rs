@@ -0,0 +1,150 @@
+// Copyright (C) 2020 Stephane Raux. Distributed under the MIT license.
+
+//! Rust toolchain selection, following the rules of [rustup](https://rust-lang.github.io/rustup/).
+//!
+//! The rustup settings and toolchain files are read directly, as running `rustup` is slow.
+
+use serde::Deserialize;
+use std::{
+    collections::BTreeMap,
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+/// Toolchain selected for a directory
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Toolchain {
+    pub name: String,
+    pub source: ToolchainSource,
+}
+
+/// What selected a toolchain
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ToolchainSource {
+    /// `$RUSTUP_TOOLCHAIN`
+    Environment,
+    /// Directory override set with `rustup override set`
+    Override(PathBuf),
+    /// `rust-toolchain.toml` or `rust-toolchain` file
+    File(PathBuf),
+    /// Default toolchain
+    Default,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct Settings {
+    default_toolchain: Option<String>,
+    #[serde(default)]
+    overrides: BTreeMap<PathBuf, String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ToolchainFile {
+    toolchain: ToolchainSection,
+}
+
+#[derive(Debug, Deserialize)]
+struct ToolchainSection {
+    channel: Option<String>,
+    path: Option<PathBuf>,
+}
+
+/// Returns the rustup home directory.
+pub fn home() -> Option<PathBuf> {
+    match env::var_os("RUSTUP_HOME") {
+        Some(home) if !home.is_empty() => Some(home.into()),
+        _ => Some(dirs::home_dir()?.join(".rustup")),
+    }
+}
+
+/// Returns the toolchain rustup would use in `dir`.
+pub fn active_toolchain(dir: &Path) -> Option<Toolchain> {
+    if let Some(name) = env::var("RUSTUP_TOOLCHAIN").ok().filter(|n| !n.is_empty()) {
+        return Some(Toolchain {
+            name,
+            source: ToolchainSource::Environment,
+        });
+    }
+    let settings = settings();
+    for dir in dir.ancestors() {
+        if let Some(name) = settings.overrides.get(dir) {
+            return Some(Toolchain {
+                name: name.clone(),
+                source: ToolchainSource::Override(dir.to_owned()),
+            });
+        }
+        for file in ["rust-toolchain", "rust-toolchain.toml"] {
+            let path = dir.join(file);
+            let Ok(contents) = fs::read_to_string(&path) else {
+                continue;
+            };
+            return parse_toolchain_file(&contents).map(|name| Toolchain {
+                name,
+                source: ToolchainSource::File(path),
+            });
+        }
+    }
+    Some(Toolchain {
+        name: settings.default_toolchain?,
+        source: ToolchainSource::Default,
+    })
+}
+
+/// Returns the default toolchain.
+pub fn default_toolchain() -> Option<String> {
+    settings().default_toolchain
+}
+
+/// Removes the host triple from a toolchain name, e.g. `stable-x86_64-unknown-linux-gnu` becomes
+/// `stable`.
+pub fn short_name(name: &str) -> &str {
+    name.split_once(&format!("-{}-", env::consts::ARCH))
+        .map_or(name, |(channel, _)| channel)
+}
+
+fn settings() -> Settings {
+    let Some(path) = home().map(|home| home.join("settings.toml")) else {
+        return Settings::default();
+    };
+    let Ok(contents) = fs::read_to_string(&path) else {
+        return Settings::default();
+    };
+    toml::from_str(&contents).unwrap_or_else(|e| {
+        tracing::error!("Failed to parse {}: {}", path.display(), e);
+        Settings::default()
+    })
+}
+
+/// Parses a toolchain file, which either contains only a toolchain name or is in TOML format.
+fn parse_toolchain_file(contents: &str) -> Option<String> {
+    let contents = contents.trim();
+    if !contents.is_empty() && !contents.contains(['\n', '[', '=']) {
+        return Some(contents.to_owned());
+    }
+    let file = match toml::from_str::<ToolchainFile>(contents) {
+        Ok(file) => file,
+        Err(e) => {
+            tracing::error!("Failed to parse toolchain file: {}", e);
+            return None;
+        }
+    };
+    file.toolchain.channel.or_else(|| {
+        let path = file.toolchain.path?;
+        Some(path.file_name()?.to_string_lossy().into_owned())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_toolchain_file;
+
+    #[test]
+    fn toolchain_files_are_parsed() {
+        assert_eq!(
+            parse_toolchain_file("nightly\n").as_deref(),
+            Some("nightly")
+        );
+        let toml = "[toolchain]\nchannel = \"1.75.0\"\ncomponents = [\"clippy\"]\n";
+        assert_eq!(parse_toolchain_file(toml).as_deref(), Some("1.75.0"));
+    }
+}
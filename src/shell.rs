@@ -0,0 +1,495 @@
+// Copyright (C) 2020 Stephane Raux. Distributed under the MIT license.
+
+//! Shell-specific escaping of prompt text.
+//!
+//! Block text is arbitrary and may contain characters that a shell interprets when expanding its
+//! prompt (e.g. `%` for zsh, `\` and `$` for bash) or control characters that move the cursor
+//! without the shell knowing. The writers in this module make sure such text is displayed
+//! literally and that escape sequences are marked as zero-width so that the shell computes the
+//! prompt width correctly.
+
+use crate::Block;
+use serde::{Deserialize, Serialize};
+use std::{
+    borrow::Cow,
+    env,
+    fmt::Write as _,
+    io::{self, Write},
+};
+
+/// Shell to generate a prompt for
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    Default,
+    Deserialize,
+    Eq,
+    PartialEq,
+    Serialize,
+    strum::Display,
+    strum::EnumString,
+)]
+#[serde(rename_all = "kebab-case")]
+#[strum(serialize_all = "kebab-case")]
+pub enum ShellType {
+    /// Text is written verbatim except for control characters
+    #[default]
+    Generic,
+    /// Bash with the default `promptvars` option enabled
+    Bash,
+    Zsh,
+}
+
+/// Destination of prompt text that knows how to escape it for a given shell
+pub trait Shell {
+    /// Writes text that is meant to be displayed literally.
+    fn write_text(&mut self, text: &str) -> io::Result<()>;
+
+    /// Writes a terminal escape sequence that takes no room on screen.
+    fn write_escape_sequence(&mut self, sequence: &str) -> io::Result<()>;
+}
+
+impl<S: Shell + ?Sized> Shell for &mut S {
+    fn write_text(&mut self, text: &str) -> io::Result<()> {
+        (**self).write_text(text)
+    }
+
+    fn write_escape_sequence(&mut self, sequence: &str) -> io::Result<()> {
+        (**self).write_escape_sequence(sequence)
+    }
+}
+
+pub struct Zsh<W>(pub W);
+
+impl<W: Write> Zsh<W> {
+    fn write_escaped(&mut self, text: &str) -> io::Result<()> {
+        text.split('%').enumerate().try_for_each(|(i, s)| {
+            if i > 0 {
+                self.0.write_all(b"%%")?;
+            }
+            self.0.write_all(s.as_bytes())
+        })
+    }
+}
+
+impl<W: Write> Shell for Zsh<W> {
+    fn write_text(&mut self, text: &str) -> io::Result<()> {
+        self.write_escaped(&sanitize(text))
+    }
+
+    fn write_escape_sequence(&mut self, sequence: &str) -> io::Result<()> {
+        // Sequences may embed arbitrary text, e.g. hyperlink URLs.
+        self.0.write_all(b"%{")?;
+        self.write_escaped(sequence)?;
+        self.0.write_all(b"%}")
+    }
+}
+
+pub struct Bash<W>(pub W);
+
+impl<W: Write> Bash<W> {
+    fn write_escaped(&mut self, text: &str) -> io::Result<()> {
+        // Backslash escapes are decoded first, then the result undergoes parameter expansion and
+        // command substitution as if it were double-quoted.
+        text.chars().try_for_each(|c| match c {
+            '\\' => self.0.write_all(br"\\\\"),
+            '$' => self.0.write_all(br"\\$"),
+            '`' => self.0.write_all(br"\\`"),
+            _ => write!(self.0, "{}", c),
+        })
+    }
+}
+
+impl<W: Write> Shell for Bash<W> {
+    fn write_text(&mut self, text: &str) -> io::Result<()> {
+        self.write_escaped(&sanitize(text))
+    }
+
+    fn write_escape_sequence(&mut self, sequence: &str) -> io::Result<()> {
+        // Sequences may embed arbitrary text, e.g. hyperlink URLs.
+        self.0.write_all(br"\[")?;
+        self.write_escaped(sequence)?;
+        self.0.write_all(br"\]")
+    }
+}
+
+pub struct GenericShell<W>(pub W);
+
+impl<W: Write> Shell for GenericShell<W> {
+    fn write_text(&mut self, text: &str) -> io::Result<()> {
+        self.0.write_all(sanitize(text).as_bytes())
+    }
+
+    fn write_escape_sequence(&mut self, sequence: &str) -> io::Result<()> {
+        self.0.write_all(sequence.as_bytes())
+    }
+}
+
+/// Adapter writing plain ASCII text to another shell, for terminals that do not support escape
+/// sequences
+///
+/// Escape sequences are dropped and non-ASCII characters are replaced with `?`.
+pub struct Plain<S>(pub S);
+
+impl<S: Shell> Shell for Plain<S> {
+    fn write_text(&mut self, text: &str) -> io::Result<()> {
+        if text.is_ascii() {
+            self.0.write_text(text)
+        } else {
+            let text = text
+                .chars()
+                .map(|c| if c.is_ascii() { c } else { '?' })
+                .collect::<String>();
+            self.0.write_text(&text)
+        }
+    }
+
+    fn write_escape_sequence(&mut self, _: &str) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Indicates if the terminal is known not to support escape sequences or non-ASCII text
+///
+/// This is the case when `TERM` is `dumb` or in Emacs shell and TRAMP buffers, as indicated by
+/// `INSIDE_EMACS`.
+pub fn is_dumb_terminal() -> bool {
+    let term_is_dumb = env::var_os("TERM").is_some_and(|term| term == "dumb");
+    let in_emacs_buffer = env::var("INSIDE_EMACS").is_ok_and(|v| {
+        v.split(',')
+            .any(|p| p == "comint" || p.starts_with("tramp"))
+    });
+    term_is_dumb || in_emacs_buffer
+}
+
+/// Writes `blocks` to `shell` with their styles.
+///
+/// Styles are reset and the rest of the line is cleared before each line break, so that
+/// backgrounds do not bleed into the next line when the terminal scrolls.
+pub fn write_blocks<S, I>(shell: &mut S, blocks: I) -> io::Result<()>
+where
+    S: Shell + ?Sized,
+    I: IntoIterator<Item = Block>,
+{
+    let plain = ansi_term::Style::new();
+    let mut current = plain;
+    let mut link = None;
+    for block in blocks {
+        if block.link != link {
+            shell.write_escape_sequence(&hyperlink(block.link.as_deref()))?;
+            link = block.link.clone();
+        }
+        let style = *block.render().style_ref();
+        for (i, line) in block.text.split('\n').enumerate() {
+            if i > 0 {
+                write_style_change(shell, current, plain)?;
+                shell.write_escape_sequence(CLEAR_TO_END_OF_LINE)?;
+                shell.write_text("\n")?;
+                current = plain;
+            }
+            if !line.is_empty() {
+                write_style_change(shell, current, style)?;
+                shell.write_text(line)?;
+                current = style;
+            }
+        }
+    }
+    if link.is_some() {
+        shell.write_escape_sequence(&hyperlink(None))?;
+    }
+    write_style_change(shell, current, plain)
+}
+
+const CLEAR_TO_END_OF_LINE: &str = "\x1b[K";
+
+/// Returns the OSC 8 sequence starting a link to `url`, or ending the current link if `url` is
+/// `None`.
+///
+/// Characters not allowed in URLs are percent-encoded, so that the sequence contains no control
+/// characters.
+fn hyperlink(url: Option<&str>) -> String {
+    let url = url.unwrap_or_default();
+    let encoded = url.bytes().fold(String::new(), |mut acc, b| {
+        if b.is_ascii_graphic() {
+            acc.push(char::from(b));
+        } else {
+            let _ = write!(acc, "%{:02X}", b);
+        }
+        acc
+    });
+    format!("\x1b]8;;{}\x1b\\", encoded)
+}
+
+fn write_style_change<S>(
+    shell: &mut S,
+    from: ansi_term::Style,
+    to: ansi_term::Style,
+) -> io::Result<()>
+where
+    S: Shell + ?Sized,
+{
+    let change = if to == ansi_term::Style::new() {
+        from.suffix().to_string()
+    } else {
+        from.infix(to).to_string()
+    };
+    if change.is_empty() {
+        Ok(())
+    } else {
+        shell.write_escape_sequence(&change)
+    }
+}
+
+/// Returns `text` escaped so that `shell` displays it literally.
+pub fn escape(shell: ShellType, text: &str) -> String {
+    let mut buffer = Vec::new();
+    let written = match shell {
+        ShellType::Generic => GenericShell(&mut buffer).write_text(text),
+        ShellType::Bash => Bash(&mut buffer).write_text(text),
+        ShellType::Zsh => Zsh(&mut buffer).write_text(text),
+    };
+    written.expect("Writing to a vector cannot fail");
+    String::from_utf8(buffer).expect("Escaped text is valid UTF-8")
+}
+
+/// Returns a POSIX shell command setting variable `name` to `value`, or unsetting it if `value`
+/// is `None`.
+pub fn export(name: &str, value: Option<&str>) -> String {
+    match value {
+        Some(value) => format!("export {}='{}'", name, value.replace('\'', r"'\''")),
+        None => format!("unset {}", name),
+    }
+}
+
+/// Replaces control characters other than newline with a visible representation.
+///
+/// C0 control characters and DEL are shown in caret notation (e.g. `^[` for escape). Other
+/// control characters are replaced with U+FFFD.
+pub fn sanitize(text: &str) -> Cow<'_, str> {
+    if !text.chars().any(is_forbidden) {
+        return Cow::Borrowed(text);
+    }
+    let sanitized = text.chars().fold(String::new(), |mut acc, c| {
+        match c {
+            '\x00'..='\x1f' if c != '\n' => {
+                let _ = write!(acc, "^{}", char::from(c as u8 + b'@'));
+            }
+            '\x7f' => acc.push_str("^?"),
+            c if is_forbidden(c) => acc.push(char::REPLACEMENT_CHARACTER),
+            c => acc.push(c),
+        }
+        acc
+    });
+    Cow::Owned(sanitized)
+}
+
+fn is_forbidden(c: char) -> bool {
+    c.is_control() && c != '\n'
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{escape, sanitize, write_blocks, Bash, GenericShell, Plain, Shell, ShellType, Zsh};
+    use crate::{Block, Style};
+    use proptest::prelude::*;
+
+    const ESCAPE: &str = "\x1b[31m";
+
+    /// Interprets zsh prompt escapes, returning the visible text.
+    fn expand_zsh(s: &str) -> Result<String, TestCaseError> {
+        let mut visible = String::new();
+        let mut chars = s.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '%' => match chars.next() {
+                    Some('%') => visible.push('%'),
+                    Some('{') => {
+                        let rest = chars.as_str();
+                        let end = rest
+                            .find("%}")
+                            .ok_or_else(|| TestCaseError::fail("Unterminated %{"))?;
+                        chars = rest[end + 2..].chars();
+                    }
+                    other => {
+                        return Err(TestCaseError::fail(format!(
+                            "Unexpected escape %{:?}",
+                            other
+                        )))
+                    }
+                },
+                c => visible.push(c),
+            }
+        }
+        Ok(visible)
+    }
+
+    /// Interprets bash prompt escapes followed by expansion, returning the visible text.
+    fn expand_bash(s: &str) -> Result<String, TestCaseError> {
+        let mut decoded = String::new();
+        let mut chars = s.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => match chars.next() {
+                    Some('\\') => decoded.push('\\'),
+                    Some('[') => {
+                        let rest = chars.as_str();
+                        let end = rest
+                            .find("\\]")
+                            .ok_or_else(|| TestCaseError::fail("Unterminated \\["))?;
+                        chars = rest[end + 2..].chars();
+                    }
+                    other => {
+                        return Err(TestCaseError::fail(format!(
+                            "Unexpected escape \\{:?}",
+                            other
+                        )))
+                    }
+                },
+                c => decoded.push(c),
+            }
+        }
+        let mut visible = String::new();
+        let mut chars = decoded.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => match chars.next() {
+                    Some(c @ ('\\' | '$' | '`')) => visible.push(c),
+                    Some(c) => {
+                        visible.push('\\');
+                        visible.push(c);
+                    }
+                    None => visible.push('\\'),
+                },
+                '$' | '`' => {
+                    return Err(TestCaseError::fail(format!("Unescaped expansion {:?}", c)))
+                }
+                c => visible.push(c),
+            }
+        }
+        Ok(visible)
+    }
+
+    fn render<S: Shell>(mut shell: S, pieces: &[String]) {
+        for piece in pieces {
+            shell.write_escape_sequence(ESCAPE).unwrap();
+            shell.write_text(piece).unwrap();
+        }
+    }
+
+    fn text() -> impl Strategy<Value = String> {
+        prop_oneof![any::<String>(), "[%\\\\$`\n\t\x1b\x07\x7f\u{85}{}a-z ]*",]
+    }
+
+    #[test]
+    fn styles_are_reset_before_line_breaks() {
+        let style = Style::new().with_bg(crate::color::BLACK);
+        let blocks = vec![
+            Block::new("a").with_style(&style),
+            Block::new("\n"),
+            Block::new("b").with_style(&style),
+        ];
+        let mut buffer = Vec::new();
+        write_blocks(&mut GenericShell(&mut buffer), blocks).unwrap();
+        assert_eq!(
+            String::from_utf8(buffer).unwrap(),
+            "\x1b[48;2;0;0;0ma\x1b[0m\x1b[K\n\x1b[48;2;0;0;0mb\x1b[0m"
+        );
+    }
+
+    #[test]
+    fn plain_text_has_no_escape_sequences() {
+        let style = Style::new().with_fg(crate::color::BLACK);
+        let blocks = vec![
+            Block::new("\u{e0a0} main").with_style(&style),
+            Block::new("\n"),
+            Block::new("100%"),
+        ];
+        let mut buffer = Vec::new();
+        write_blocks(&mut Plain(Zsh(&mut buffer)), blocks).unwrap();
+        assert_eq!(String::from_utf8(buffer).unwrap(), "? main\n100%%");
+    }
+
+    #[test]
+    fn hyperlinks_are_escaped() {
+        let blocks = || {
+            vec![
+                Block::new("a").with_link("https://e.com/$a b%"),
+                Block::new("b"),
+            ]
+        };
+        let mut buffer = Vec::new();
+        write_blocks(&mut Zsh(&mut buffer), blocks()).unwrap();
+        assert_eq!(
+            String::from_utf8(buffer).unwrap(),
+            "%{\x1b]8;;https://e.com/$a%%20b%%\x1b\\%}a%{\x1b]8;;\x1b\\%}b"
+        );
+        let mut buffer = Vec::new();
+        write_blocks(&mut Bash(&mut buffer), blocks()).unwrap();
+        assert_eq!(
+            String::from_utf8(buffer).unwrap(),
+            "\\[\x1b]8;;https://e.com/\\\\$a%20b%\x1b\\\\\\\\\\]a\\[\x1b]8;;\x1b\\\\\\\\\\]b"
+        );
+    }
+
+    #[test]
+    fn control_characters_are_shown_in_caret_notation() {
+        assert_eq!(sanitize("a\x1b[0m\tb\x7f\n"), "a^[[0m^Ib^?\n");
+    }
+
+    proptest! {
+        #[test]
+        fn sanitized_text_has_no_control_characters_but_newlines(s in text()) {
+            prop_assert!(sanitize(&s).chars().all(|c| c == '\n' || !c.is_control()));
+        }
+
+        #[test]
+        fn sanitizing_is_idempotent(s in text()) {
+            let once = sanitize(&s).into_owned();
+            prop_assert_eq!(sanitize(&once), once.as_str());
+        }
+
+        #[test]
+        fn generic_escaping_only_sanitizes(s in text()) {
+            prop_assert_eq!(escape(ShellType::Generic, &s), sanitize(&s));
+        }
+
+        #[test]
+        fn zsh_escaped_text_expands_to_itself(s in text()) {
+            prop_assert_eq!(expand_zsh(&escape(ShellType::Zsh, &s))?, sanitize(&s));
+        }
+
+        #[test]
+        fn bash_escaped_text_expands_to_itself(s in text()) {
+            prop_assert_eq!(expand_bash(&escape(ShellType::Bash, &s))?, sanitize(&s));
+        }
+
+        #[test]
+        fn zsh_escape_sequences_take_no_room(pieces in prop::collection::vec(text(), 0..8)) {
+            let mut buffer = Vec::new();
+            render(Zsh(&mut buffer), &pieces);
+            let visible = expand_zsh(&String::from_utf8(buffer).unwrap())?;
+            let expected = pieces.concat();
+            prop_assert_eq!(visible, sanitize(&expected));
+        }
+
+        #[test]
+        fn bash_escape_sequences_take_no_room(pieces in prop::collection::vec(text(), 0..8)) {
+            let mut buffer = Vec::new();
+            render(Bash(&mut buffer), &pieces);
+            let visible = expand_bash(&String::from_utf8(buffer).unwrap())?;
+            let expected = pieces.concat();
+            prop_assert_eq!(visible, sanitize(&expected));
+        }
+
+        #[test]
+        fn generic_shell_writes_escape_sequences_verbatim(
+            pieces in prop::collection::vec(text(), 0..8)
+        ) {
+            let mut buffer = Vec::new();
+            render(GenericShell(&mut buffer), &pieces);
+            let expected = pieces.iter().fold(String::new(), |acc, p| acc + ESCAPE + &sanitize(p));
+            prop_assert_eq!(String::from_utf8(buffer).unwrap(), expected);
+        }
+    }
+}
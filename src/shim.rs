@@ -0,0 +1,142 @@
+// Copyright (C) 2020 Stephane Raux. Distributed under the MIT license.
+
+//! Versions selected by version managers, found without running their shims.
+//!
+//! Shims of managers like pyenv run a program of the selected version, but resolving that version
+//! is slow. The version files the shims read are read directly instead.
+
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+/// Version manager installing shims
+struct Manager {
+    /// Variable overriding the root directory of the manager
+    root_var: &'static str,
+    /// Root directory relative to the home directory
+    default_root: &'static str,
+    /// Variable selecting the version for the shell session
+    version_var: &'static str,
+    /// File selecting the version for a directory and its descendants
+    version_file: &'static str,
+    /// Interpreters whose shims run the selected version
+    interpreters: &'static [&'static str],
+}
+
+const MANAGERS: [Manager; 3] = [
+    Manager {
+        root_var: "PYENV_ROOT",
+        default_root: ".pyenv",
+        version_var: "PYENV_VERSION",
+        version_file: ".python-version",
+        interpreters: &["python", "python3"],
+    },
+    Manager {
+        root_var: "RBENV_ROOT",
+        default_root: ".rbenv",
+        version_var: "RBENV_VERSION",
+        version_file: ".ruby-version",
+        interpreters: &["ruby"],
+    },
+    Manager {
+        root_var: "NODENV_ROOT",
+        default_root: ".nodenv",
+        version_var: "NODENV_VERSION",
+        version_file: ".node-version",
+        interpreters: &["node"],
+    },
+];
+
+/// Returns the version of the interpreter at `program` when run in `dir`, if it is a shim of pyenv,
+/// rbenv or nodenv, or node installed by nvm.
+///
+/// `None` is returned if the version cannot be known without running the program, e.g. when the
+/// shim selects the system version, or if the program is another tool, e.g. `npm`.
+pub(crate) fn resolved_version(program: &Path, dir: &Path) -> Option<String> {
+    let home = dirs::home_dir();
+    let program_dir = program.parent()?;
+    let program_name = program.file_name()?.to_str()?;
+    for manager in &MANAGERS {
+        if !manager.interpreters.contains(&program_name) {
+            continue;
+        }
+        let root = env::var_os(manager.root_var)
+            .map(PathBuf::from)
+            .or_else(|| Some(home.as_ref()?.join(manager.default_root)));
+        let Some(root) = root.filter(|root| program_dir == root.join("shims")) else {
+            continue;
+        };
+        let version = env::var(manager.version_var)
+            .ok()
+            .filter(|v| !v.is_empty())
+            .or_else(|| selected_version(&root, dir, manager.version_file))?;
+        return (version != "system").then_some(version);
+    }
+    let nvm_dir = env::var_os("NVM_DIR")
+        .map(PathBuf::from)
+        .or_else(|| Some(home?.join(".nvm")))?;
+    nvm_version(&nvm_dir, program)
+}
+
+/// Returns the version selected by the version file closest to `dir`, or the global version
+/// stored in the root directory of the manager.
+fn selected_version(root: &Path, dir: &Path, version_file: &str) -> Option<String> {
+    dir.ancestors()
+        .map(|dir| dir.join(version_file))
+        .chain([root.join("version")])
+        .find_map(|path| {
+            let contents = fs::read_to_string(path).ok()?;
+            let version = contents.split_whitespace().next()?;
+            Some(version.to_owned())
+        })
+}
+
+/// Returns the version of node installed by nvm, from its path, e.g.
+/// `~/.nvm/versions/node/v20.11.1/bin/node`.
+fn nvm_version(nvm_dir: &Path, program: &Path) -> Option<String> {
+    if program.file_name()? != "node" {
+        return None;
+    }
+    let relative = program
+        .strip_prefix(nvm_dir.join("versions").join("node"))
+        .ok()?;
+    let version = relative.components().next()?.as_os_str().to_str()?;
+    Some(version.strip_prefix('v').unwrap_or(version).to_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{nvm_version, selected_version};
+    use std::{fs, path::Path};
+
+    #[test]
+    fn closest_version_file_is_used() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        let project = root.join("project");
+        fs::create_dir_all(project.join("src")).unwrap();
+        fs::write(root.join("version"), "3.11.7\n").unwrap();
+        assert_eq!(
+            selected_version(root, &project, ".python-version").as_deref(),
+            Some("3.11.7")
+        );
+        fs::write(project.join(".python-version"), "3.12.1\n3.11.7\n").unwrap();
+        assert_eq!(
+            selected_version(root, &project.join("src"), ".python-version").as_deref(),
+            Some("3.12.1")
+        );
+    }
+
+    #[test]
+    fn nvm_version_is_read_from_path() {
+        let program = Path::new("/home/u/.nvm/versions/node/v20.11.1/bin/node");
+        assert_eq!(
+            nvm_version(Path::new("/home/u/.nvm"), program).as_deref(),
+            Some("20.11.1")
+        );
+        assert_eq!(nvm_version(Path::new("/opt/nvm"), program), None);
+        let npm = program.with_file_name("npm");
+        assert_eq!(nvm_version(Path::new("/home/u/.nvm"), &npm), None);
+    }
+}
@@ -3,7 +3,7 @@
 use crate::Color;
 use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
 pub struct Style {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub foreground: Option<Color>,
@@ -64,6 +64,28 @@ impl Style {
         Style { background, ..self }
     }
 
+    /// Returns this style with stronger contrast, for low-vision users.
+    ///
+    /// With a background, the foreground becomes black or white, whichever contrasts more with
+    /// it. A foreground alone is made lighter or darker and more saturated.
+    pub fn high_contrast(&self) -> Style {
+        match (&self.foreground, &self.background) {
+            (_, Some(background)) => {
+                let black = crate::color::BLACK;
+                let white = crate::color::WHITE;
+                let foreground =
+                    if background.contrast_with(&black) >= background.contrast_with(&white) {
+                        black
+                    } else {
+                        white
+                    };
+                Style::fg(foreground).with_bg(background)
+            }
+            (Some(foreground), None) => Style::fg(foreground.boosted()),
+            (None, None) => Style::new(),
+        }
+    }
+
     pub fn or(&self, default: &Style) -> Style {
         Style {
             foreground: self
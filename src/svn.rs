@@ -0,0 +1,94 @@
+// Copyright (C) 2020 Stephane Raux. Distributed under the MIT license.
+
+//! [Subversion](https://subversion.apache.org) working copy information.
+//!
+//! Information is obtained by running `svn`, as the working copy database is not meant to be read
+//! directly.
+
+use crate::cancel::SharedCancellation;
+use once_cell::unsync::OnceCell;
+use std::{
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+/// Subversion working copy containing the working directory
+#[derive(Debug)]
+pub struct SvnRepo {
+    root: PathBuf,
+    cancellation: SharedCancellation,
+    relative_url: OnceCell<Option<String>>,
+}
+
+impl SvnRepo {
+    /// Looks for a working copy containing `dir`.
+    pub fn discover(dir: &Path) -> Option<Self> {
+        let root = crate::vcs::find_root(dir, ".svn")?;
+        Some(SvnRepo {
+            root: root.to_owned(),
+            cancellation: Default::default(),
+            relative_url: OnceCell::new(),
+        })
+    }
+
+    /// Shares the token killing the commands run to get information once cancelled. The token is
+    /// read whenever a command runs, so that commands follow the current cancellation scope.
+    pub(crate) fn with_cancellation(self, cancellation: SharedCancellation) -> Self {
+        Self {
+            cancellation,
+            ..self
+        }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Returns the URL of the working copy relative to the repository root, e.g. `^/trunk`.
+    pub fn relative_url(&self) -> Option<&str> {
+        let url = self
+            .relative_url
+            .get_or_try_init(|| {
+                let cancellation = self.cancellation.get();
+                let url = crate::process::output(
+                    Command::new("svn")
+                        .args(["info", "--show-item", "relative-url"])
+                        .current_dir(&self.root),
+                    &cancellation,
+                );
+                // A command killed by a scoped deadline may succeed for a later producer.
+                match url {
+                    None if cancellation.is_cancelled() => Err(()),
+                    url => Ok(url),
+                }
+            })
+            .ok()?;
+        url.as_deref()
+    }
+
+    /// Returns the branch following the standard layout, e.g. `trunk` or the name of a branch or
+    /// tag.
+    pub fn branch(&self) -> Option<&str> {
+        self.relative_url().map(branch_from_relative_url)
+    }
+}
+
+fn branch_from_relative_url(url: &str) -> &str {
+    let path = url.strip_prefix("^/").unwrap_or(url);
+    ["branches/", "tags/"]
+        .iter()
+        .find_map(|prefix| path.split_once(prefix))
+        .map_or(path, |(_, rest)| rest.split('/').next().unwrap_or(rest))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::branch_from_relative_url;
+
+    #[test]
+    fn branch_follows_standard_layout() {
+        assert_eq!(branch_from_relative_url("^/trunk"), "trunk");
+        assert_eq!(branch_from_relative_url("^/branches/fix/src"), "fix");
+        assert_eq!(branch_from_relative_url("^/project/tags/1.0"), "1.0");
+    }
+}
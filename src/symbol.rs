@@ -0,0 +1,257 @@
+// Copyright (C) 2020 Stephane Raux. Distributed under the MIT license.
+
+//! Symbols shown by blocks, e.g. as prefixes.
+//!
+//! Each symbol comes in three flavors so that the prompt looks coherent whether or not a
+//! [Nerd Font](https://www.nerdfonts.com/) is installed, or even when only ASCII can be
+//! displayed. Blocks show the flavor for the [`SymbolSet`] returned by
+//! [`Environment::symbols`](crate::Environment::symbols).
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::{borrow::Cow, cell::Cell, env};
+
+/// Set of symbols to use in built-in defaults
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    Default,
+    Deserialize,
+    Eq,
+    PartialEq,
+    Serialize,
+    strum::Display,
+    strum::EnumString,
+)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+pub enum SymbolSet {
+    /// Glyphs from Nerd Fonts
+    #[default]
+    Nerd,
+    /// Characters found in most fonts
+    Unicode,
+    /// ASCII characters only
+    Ascii,
+}
+
+impl SymbolSet {
+    /// Returns the symbol set used to show symbols on the current thread.
+    pub fn current() -> Self {
+        CURRENT_SET.with(Cell::get)
+    }
+
+    /// Returns the symbol set requested by the `ELIPROMPT_SYMBOLS` environment variable, e.g. on
+    /// a terminal without Nerd Fonts.
+    ///
+    /// Invalid values are logged and ignored.
+    pub fn requested() -> Option<Self> {
+        let value = env::var("ELIPROMPT_SYMBOLS")
+            .ok()
+            .filter(|v| !v.is_empty())?;
+        match value.parse() {
+            Ok(set) => Some(set),
+            Err(_) => {
+                tracing::warn!(
+                    "Ignoring invalid symbol set in ELIPROMPT_SYMBOLS: {}",
+                    value
+                );
+                None
+            }
+        }
+    }
+
+    /// Calls `f` with `self` as the symbol set used to show symbols on the current thread.
+    pub fn scope<F, T>(self, f: F) -> T
+    where
+        F: FnOnce() -> T,
+    {
+        struct Restore(SymbolSet);
+
+        impl Drop for Restore {
+            fn drop(&mut self) {
+                CURRENT_SET.with(|current| current.set(self.0));
+            }
+        }
+
+        let _restore = Restore(CURRENT_SET.with(|current| current.replace(self)));
+        f()
+    }
+}
+
+thread_local! {
+    static CURRENT_SET: Cell<SymbolSet> = const { Cell::new(SymbolSet::Nerd) };
+}
+
+/// Symbol with a variant for each [`SymbolSet`]
+///
+/// A symbol is configured either as a string used whatever the symbol set, or as an object with a
+/// `nerd`, `unicode` and `ascii` variant.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Symbol {
+    nerd: Cow<'static, str>,
+    unicode: Cow<'static, str>,
+    ascii: Cow<'static, str>,
+}
+
+impl Symbol {
+    pub const fn new(nerd: &'static str, unicode: &'static str, ascii: &'static str) -> Self {
+        Symbol {
+            nerd: Cow::Borrowed(nerd),
+            unicode: Cow::Borrowed(unicode),
+            ascii: Cow::Borrowed(ascii),
+        }
+    }
+
+    /// Returns the variant of this symbol for `set`.
+    pub fn get(&self, set: SymbolSet) -> &str {
+        match set {
+            SymbolSet::Nerd => &self.nerd,
+            SymbolSet::Unicode => &self.unicode,
+            SymbolSet::Ascii => &self.ascii,
+        }
+    }
+}
+
+impl From<&str> for Symbol {
+    fn from(s: &str) -> Self {
+        s.to_owned().into()
+    }
+}
+
+impl From<String> for Symbol {
+    fn from(s: String) -> Self {
+        Symbol {
+            nerd: s.clone().into(),
+            unicode: s.clone().into(),
+            ascii: s.into(),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(untagged)]
+enum SymbolRepr {
+    Same(String),
+    Variants {
+        nerd: String,
+        unicode: String,
+        ascii: String,
+    },
+}
+
+impl<'de> Deserialize<'de> for Symbol {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Ok(match SymbolRepr::deserialize(deserializer)? {
+            SymbolRepr::Same(s) => s.into(),
+            SymbolRepr::Variants {
+                nerd,
+                unicode,
+                ascii,
+            } => Symbol {
+                nerd: nerd.into(),
+                unicode: unicode.into(),
+                ascii: ascii.into(),
+            },
+        })
+    }
+}
+
+impl Serialize for Symbol {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let repr = if self.nerd == self.unicode && self.nerd == self.ascii {
+            SymbolRepr::Same(self.nerd.clone().into_owned())
+        } else {
+            SymbolRepr::Variants {
+                nerd: self.nerd.clone().into_owned(),
+                unicode: self.unicode.clone().into_owned(),
+                ascii: self.ascii.clone().into_owned(),
+            }
+        };
+        repr.serialize(serializer)
+    }
+}
+
+pub const AZURE: Symbol = Symbol::new("\u{ebd8} ", "az ", "az ");
+pub const CI: Symbol = Symbol::new("\u{f0ad} ", "⚙ ", "ci ");
+pub const CONTAINER: Symbol = Symbol::new("\u{f4b7} ", "⬢ ", "ctr ");
+pub const DATE: Symbol = Symbol::new("\u{f073} ", "", "");
+pub const DIRENV: Symbol = Symbol::new("\u{f462} ", "⇲ ", "env ");
+pub const ELAPSED: Symbol = Symbol::new("\u{fa1a}", "⌛ ", "");
+pub const ELLIPSIS: Symbol = Symbol::new("…", "…", "...");
+pub const EXIT_CODE: Symbol = Symbol::new("\u{f071}", "⚠ ", "!");
+pub const GIT_AHEAD: Symbol = Symbol::new("↑", "↑", "^");
+pub const GIT_BEHIND: Symbol = Symbol::new("↓", "↓", "v");
+pub const GIT_COMMIT_AGE: Symbol = Symbol::new("\u{f017}", "◷ ", "");
+pub const GIT_CONFLICTS: Symbol = Symbol::new("✖ ", "✖ ", "x ");
+pub const GIT_HEAD: Symbol = Symbol::new("\u{e725}", "⎇ ", "");
+pub const GIT_IDENTITY: Symbol = Symbol::new("\u{f007}", "☺ ", "");
+pub const GIT_MODIFIED: Symbol = Symbol::new("✚", "✚", "*");
+pub const GIT_OPERATION: Symbol = Symbol::new("\u{f126} ", "⑂ ", "");
+pub const GIT_PATH: Symbol = Symbol::new("\u{f7a1}", "± ", "");
+pub const GIT_STAGED: Symbol = Symbol::new("●", "●", "+");
+pub const GIT_SUBMODULE_OUT_OF_SYNC: Symbol = Symbol::new("↻", "↻", "~");
+pub const GIT_SUBMODULE_UNINITIALIZED: Symbol = Symbol::new("∅", "∅", "?");
+pub const GIT_SUBMODULES: Symbol = Symbol::new("\u{f1e6}", "⊂ ", "");
+pub const GIT_TAG: Symbol = Symbol::new("\u{f02b}", "# ", "#");
+pub const GIT_UNTRACKED: Symbol = Symbol::new("…", "…", "?");
+pub const GO: Symbol = Symbol::new("\u{e627} ", "go ", "go ");
+pub const JAVA: Symbol = Symbol::new("\u{e738} ", "java ", "java ");
+pub const JJ_BOOKMARKS: Symbol = Symbol::new("\u{f02e}", "⚑ ", "");
+pub const JJ_CHANGE: Symbol = Symbol::new("\u{f417}", "◉ ", "@");
+pub const KUBERNETES: Symbol = Symbol::new("\u{fd31} ", "☸ ", "k8s ");
+pub const LOCAL_IP: Symbol = Symbol::new("\u{f0e8} ", "", "");
+pub const LOCAL_ONLY: Symbol = Symbol::new(" \u{f0164}", " ⌂", " ~");
+pub const LOCALE: Symbol = Symbol::new("\u{f1ab} ", "⚠ ", "! ");
+pub const MARKER: Symbol = Symbol::new("\u{f024} ", "⚑ ", "!");
+pub const MULTIPLEXER: Symbol = Symbol::new("\u{ebc8} ", "▣ ", "mux ");
+pub const NIX: Symbol = Symbol::new("\u{f313} ", "❄ ", "nix ");
+pub const NOTE: Symbol = Symbol::new("\u{f27b} ", "✉ ", "");
+pub const NOW_PLAYING: Symbol = Symbol::new("\u{f001} ", "♪ ", "");
+pub const PHP: Symbol = Symbol::new("\u{e73d} ", "php ", "php ");
+pub const PROMPT: Symbol = Symbol::new("→", "→", ">");
+pub const PUBLIC_IP: Symbol = Symbol::new("\u{f0ac} ", "", "");
+pub const PYTHON: Symbol = Symbol::new("\u{e73c} ", "py ", "py ");
+pub const QUOTA: Symbol = Symbol::new("\u{f0a0} ", "⛁ ", "quota ");
+pub const ROOT: Symbol = Symbol::new("\u{f0e7} ", "# ", "# ");
+pub const RULE: Symbol = Symbol::new("─", "─", "-");
+pub const RUST: Symbol = Symbol::new("\u{e7a8} ", "rs ", "rs ");
+pub const SHELL_LEVEL: Symbol = Symbol::new("\u{f120} ", "↕ ", "lvl ");
+pub const SSH: Symbol = Symbol::new("\u{f489}", "⇄", "ssh");
+pub const TERRAFORM: Symbol = Symbol::new("\u{f1062} ", "tf ", "tf ");
+pub const TIME: Symbol = Symbol::new("\u{f017} ", "◷ ", "");
+pub const TODO: Symbol = Symbol::new("\u{f0ae} ", "☐ ", "todo ");
+pub const VCS_HEAD: Symbol = Symbol::new("\u{e0a0}", "⎇ ", "");
+pub const VPN: Symbol = Symbol::new("\u{f023} ", "⚿ ", "vpn ");
+pub const WORKING_DIRECTORY: Symbol = Symbol::new("\u{f07c}", "", "");
+
+#[cfg(test)]
+mod tests {
+    use super::{Symbol, SymbolSet, GIT_HEAD};
+
+    #[test]
+    fn scope_sets_and_restores_current_set() {
+        assert_eq!(SymbolSet::current(), SymbolSet::Nerd);
+        let inner = SymbolSet::Ascii.scope(SymbolSet::current);
+        assert_eq!(inner, SymbolSet::Ascii);
+        assert_eq!(SymbolSet::current(), SymbolSet::Nerd);
+    }
+
+    #[test]
+    fn symbols_are_strings_or_variants() {
+        let plain = serde_json::from_str::<Symbol>(r#""$ ""#).unwrap();
+        assert_eq!(plain, Symbol::from("$ "));
+        assert_eq!(plain.get(SymbolSet::Ascii), "$ ");
+        assert_eq!(serde_json::to_string(&plain).unwrap(), r#""$ ""#);
+        let json = serde_json::to_string(&GIT_HEAD).unwrap();
+        let head = serde_json::from_str::<Symbol>(&json).unwrap();
+        assert_eq!(head, GIT_HEAD);
+        assert_eq!(head.get(SymbolSet::Unicode), "⎇ ");
+    }
+}
@@ -0,0 +1,117 @@
+// Copyright (C) 2020 Stephane Raux. Distributed under the MIT license.
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::{
+    fmt::{self, Display},
+    str::FromStr,
+};
+use thiserror::Error;
+
+/// Text in which `{value}` is replaced with the value computed by a block
+///
+/// Literal braces are written `{{` and `}}`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Template {
+    source: String,
+    segments: Vec<Segment>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum Segment {
+    Literal(String),
+    Value,
+}
+
+impl Template {
+    pub fn new(source: &str) -> Result<Self, InvalidTemplate> {
+        let mut segments = Vec::new();
+        let mut literal = String::new();
+        let mut rest = source;
+        while let Some(i) = rest.find(['{', '}']) {
+            literal.push_str(&rest[..i]);
+            rest = &rest[i..];
+            if let Some(r) = rest.strip_prefix("{{") {
+                literal.push('{');
+                rest = r;
+            } else if let Some(r) = rest.strip_prefix("}}") {
+                literal.push('}');
+                rest = r;
+            } else if let Some(r) = rest.strip_prefix("{value}") {
+                if !literal.is_empty() {
+                    segments.push(Segment::Literal(std::mem::take(&mut literal)));
+                }
+                segments.push(Segment::Value);
+                rest = r;
+            } else {
+                return Err(InvalidTemplate(source.to_owned()));
+            }
+        }
+        literal.push_str(rest);
+        if !literal.is_empty() {
+            segments.push(Segment::Literal(literal));
+        }
+        Ok(Template {
+            source: source.to_owned(),
+            segments,
+        })
+    }
+
+    /// Returns the template text with placeholders replaced with `value`.
+    pub fn render(&self, value: &str) -> String {
+        self.segments
+            .iter()
+            .map(|segment| match segment {
+                Segment::Literal(s) => s.as_str(),
+                Segment::Value => value,
+            })
+            .collect()
+    }
+}
+
+impl FromStr for Template {
+    type Err = InvalidTemplate;
+
+    fn from_str(s: &str) -> Result<Self, InvalidTemplate> {
+        Template::new(s)
+    }
+}
+
+impl Display for Template {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+impl Serialize for Template {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.source)
+    }
+}
+
+impl<'de> Deserialize<'de> for Template {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+#[derive(Clone, Debug, Eq, Error, PartialEq)]
+#[error("Invalid template (only {{value}} is supported and literal braces must be doubled): {0}")]
+pub struct InvalidTemplate(String);
+
+#[cfg(test)]
+mod tests {
+    use crate::Template;
+
+    #[test]
+    fn value_placeholder_is_replaced() {
+        let template = Template::new("on {value} {{x}}").unwrap();
+        assert_eq!(template.render("main"), "on main {x}");
+    }
+
+    #[test]
+    fn unknown_placeholder_is_rejected() {
+        assert!(Template::new("{branch}").is_err());
+        assert!(Template::new("{value").is_err());
+    }
+}
@@ -0,0 +1,99 @@
+// Copyright (C) 2020 Stephane Raux. Distributed under the MIT license.
+
+//! Interface shared by all version control systems.
+
+use crate::{GitRepo, Head, HgRepo, JjRepo, SvnRepo};
+use std::path::Path;
+
+/// Repository of any version control system
+///
+/// This gives producers that do not care which system is used a single way to get information
+/// about the working directory. See [`Environment::vcs`](crate::Environment::vcs).
+pub trait Vcs {
+    /// Returns the name of the version control system, e.g. `git`.
+    fn name(&self) -> &'static str;
+
+    /// Returns the root of the working tree.
+    fn root(&self) -> &Path;
+
+    /// Returns what is checked out: a branch or bookmark name if there is one, a revision
+    /// otherwise.
+    fn head(&self) -> Option<String>;
+}
+
+impl Vcs for GitRepo {
+    fn name(&self) -> &'static str {
+        "git"
+    }
+
+    fn root(&self) -> &Path {
+        self.workdir().unwrap_or_else(|| self.git_dir())
+    }
+
+    fn head(&self) -> Option<String> {
+        match self.head() {
+            Ok(Head::Branch { name, .. }) => Some(name),
+            Ok(Head::Detached(mut commit)) => {
+                commit.truncate(7);
+                Some(commit)
+            }
+            Err(e) => {
+                tracing::error!("Failed to get git repository HEAD: {}", e);
+                None
+            }
+        }
+    }
+}
+
+impl Vcs for JjRepo {
+    fn name(&self) -> &'static str {
+        "jj"
+    }
+
+    fn root(&self) -> &Path {
+        self.root()
+    }
+
+    fn head(&self) -> Option<String> {
+        let working_copy = self.working_copy()?;
+        working_copy
+            .bookmarks
+            .first()
+            .or(Some(&working_copy.change_id))
+            .filter(|head| !head.is_empty())
+            .cloned()
+    }
+}
+
+impl Vcs for HgRepo {
+    fn name(&self) -> &'static str {
+        "hg"
+    }
+
+    fn root(&self) -> &Path {
+        self.root()
+    }
+
+    fn head(&self) -> Option<String> {
+        Some(self.bookmark().unwrap_or_else(|| self.branch()))
+    }
+}
+
+impl Vcs for SvnRepo {
+    fn name(&self) -> &'static str {
+        "svn"
+    }
+
+    fn root(&self) -> &Path {
+        self.root()
+    }
+
+    fn head(&self) -> Option<String> {
+        self.branch().map(ToOwned::to_owned)
+    }
+}
+
+/// Returns the closest ancestor of `dir`, including itself, containing the directory `marker`.
+pub(crate) fn find_root<'a>(dir: &'a Path, marker: &str) -> Option<&'a Path> {
+    dir.ancestors().find(|d| d.join(marker).is_dir())
+}